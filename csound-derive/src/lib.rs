@@ -0,0 +1,243 @@
+//! Derive macros for the [`csound`](https://crates.io/crates/csound) crate.
+//!
+//! This crate is not meant to be used directly, enable the `derive` feature of the csound crate
+//! and use the re-exported macros from there.
+extern crate proc_macro;
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::quote;
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::{parse_macro_input, Data, DeriveInput, Fields, Ident, LitStr, Token, Type};
+
+/// Implements `csound::CsoundChannels` for a struct with named fields.
+///
+/// Fields annotated with `#[channel(...)]` are bound to a csound's channel, see the
+/// `CsoundChannels` trait documentation in the csound crate for the attribute syntax.
+#[proc_macro_derive(CsoundChannels, attributes(channel))]
+pub fn derive_csound_channels(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match expand(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Kind {
+    Control,
+    String,
+}
+
+struct Channel {
+    field: Ident,
+    name: LitStr,
+    kind: Kind,
+    input: bool,
+    output: bool,
+}
+
+enum Arg {
+    Name(LitStr),
+    Kind(Kind, Span),
+    Input,
+    Output,
+}
+
+impl Parse for Arg {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        if input.peek(LitStr) {
+            return Ok(Arg::Name(input.parse()?));
+        }
+        let ident: Ident = input.parse()?;
+        match ident.to_string().as_str() {
+            "name" => {
+                input.parse::<Token![=]>()?;
+                Ok(Arg::Name(input.parse()?))
+            }
+            "kind" => {
+                input.parse::<Token![=]>()?;
+                // Accept both `kind = string` and `kind = "string"`
+                let (value, span) = if input.peek(LitStr) {
+                    let lit: LitStr = input.parse()?;
+                    (lit.value(), lit.span())
+                } else {
+                    let ident: Ident = input.parse()?;
+                    (ident.to_string(), ident.span())
+                };
+                match value.as_str() {
+                    "control" => Ok(Arg::Kind(Kind::Control, span)),
+                    "string" => Ok(Arg::Kind(Kind::String, span)),
+                    _ => Err(syn::Error::new(
+                        span,
+                        "unknown channel kind, expected `control` or `string`",
+                    )),
+                }
+            }
+            "input" => Ok(Arg::Input),
+            "output" => Ok(Arg::Output),
+            _ => Err(syn::Error::new(
+                ident.span(),
+                "unknown channel attribute, expected `name`, `kind`, `input` or `output`",
+            )),
+        }
+    }
+}
+
+fn is_string(ty: &Type) -> bool {
+    match ty {
+        Type::Path(path) => path
+            .path
+            .segments
+            .last()
+            .map(|segment| segment.ident == "String")
+            .unwrap_or(false),
+        _ => false,
+    }
+}
+
+fn parse_channels(input: &DeriveInput) -> syn::Result<Vec<Channel>> {
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    &input.ident,
+                    "CsoundChannels can only be derived for structs with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                "CsoundChannels can only be derived for structs",
+            ))
+        }
+    };
+
+    let mut channels: Vec<Channel> = Vec::new();
+    for field in fields {
+        for attr in field.attrs.iter().filter(|a| a.path.is_ident("channel")) {
+            let args = attr.parse_args_with(Punctuated::<Arg, Token![,]>::parse_terminated)?;
+            let mut name = None;
+            let mut kind = None;
+            let mut input = false;
+            let mut output = false;
+            for arg in args {
+                match arg {
+                    Arg::Name(lit) => name = Some(lit),
+                    Arg::Kind(k, span) => {
+                        if k == Kind::String && !is_string(&field.ty) {
+                            return Err(syn::Error::new(
+                                span,
+                                "string channels need a String field",
+                            ));
+                        }
+                        kind = Some(k)
+                    }
+                    Arg::Input => input = true,
+                    Arg::Output => output = true,
+                }
+            }
+            let name = name.ok_or_else(|| {
+                syn::Error::new_spanned(attr, "missing channel name, e.g #[channel(\"cutoff\")]")
+            })?;
+            if name.value().is_empty() || name.value().contains(char::is_whitespace) {
+                return Err(syn::Error::new(name.span(), "invalid channel name"));
+            }
+            if let Some(other) = channels.iter().find(|c| c.name.value() == name.value()) {
+                return Err(syn::Error::new(
+                    name.span(),
+                    format!(
+                        "the channel is already bound to the field `{}`",
+                        other.field
+                    ),
+                ));
+            }
+            let kind = kind.unwrap_or(if is_string(&field.ty) {
+                Kind::String
+            } else {
+                Kind::Control
+            });
+            channels.push(Channel {
+                field: field.ident.clone().unwrap(),
+                name,
+                kind,
+                input: input || !output,
+                output,
+            });
+        }
+    }
+    Ok(channels)
+}
+
+fn expand(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let channels = parse_channels(input)?;
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let specs = channels.iter().map(|c| {
+        let name = &c.name;
+        let kind = match c.kind {
+            Kind::Control => quote!(::csound::ChannelKind::Control),
+            Kind::String => quote!(::csound::ChannelKind::String),
+        };
+        let (input, output) = (c.input, c.output);
+        quote! {
+            ::csound::ChannelSpec { name: #name, kind: #kind, input: #input, output: #output }
+        }
+    });
+
+    let push = channels
+        .iter()
+        .enumerate()
+        .filter(|(_, c)| c.input)
+        .map(|(i, c)| {
+            let field = &c.field;
+            match c.kind {
+                Kind::Control => quote! {
+                    binding.write_control(#i, ::csound::ControlValue::to_control(&self.#field));
+                },
+                Kind::String => quote! {
+                    binding.write_string(#i, &self.#field)?;
+                },
+            }
+        });
+
+    let pull = channels
+        .iter()
+        .enumerate()
+        .filter(|(_, c)| c.output)
+        .map(|(i, c)| {
+            let field = &c.field;
+            match c.kind {
+                Kind::Control => quote! {
+                    self.#field = ::csound::ControlValue::from_control(binding.read_control(#i));
+                },
+                Kind::String => quote! {
+                    self.#field = binding.read_string(#i);
+                },
+            }
+        });
+
+    Ok(quote! {
+        impl #impl_generics ::csound::CsoundChannels for #ident #ty_generics #where_clause {
+            fn channels() -> &'static [::csound::ChannelSpec] {
+                &[#(#specs),*]
+            }
+
+            #[allow(unused_variables)]
+            fn push_channels(&self, binding: &::csound::ChannelBinding) -> ::csound::Result<()> {
+                #(#push)*
+                Ok(())
+            }
+
+            #[allow(unused_variables)]
+            fn pull_channels(&mut self, binding: &::csound::ChannelBinding) -> ::csound::Result<()> {
+                #(#pull)*
+                Ok(())
+            }
+        }
+    })
+}
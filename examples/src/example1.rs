@@ -1,7 +1,7 @@
 use csound::Csound;
 
 fn main() {
-    let cs = Csound::new();
+    let mut cs = Csound::new();
 
     let args = ["csound", "examples/test1.csd"];
     cs.compile(&args).unwrap();
@@ -111,7 +111,7 @@ static ORC: &str = "sr=44100
 endin";
 
 fn main() {
-    let cs = Csound::new();
+    let mut cs = Csound::new();
 
     cs.message_string_callback(|_, msg| println!("{}", msg));
 
@@ -26,7 +26,7 @@ endin";
 static SCO: &str = "i1 0 1";
 
 fn main() {
-    let cs = Csound::new();
+    let mut cs = Csound::new();
 
     /* Using SetOption() to configure Csound
     Note: use only one commandline flag at a time */
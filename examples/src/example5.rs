@@ -30,14 +30,11 @@
  * you want to hear, and comment out the others.
  */
 
-use csound::Csound;
+use csound::{Csound, PerformanceThread};
 use std::fmt::Write;
 
 use rand::Rng;
 
-use std::sync::{Arc, Mutex};
-use std::thread;
-
 /* Defining our Csound ORC code within a multiline String */
 static ORC: &str = "sr=44100
   ksmps=32
@@ -81,7 +78,7 @@ fn generate_example() -> String {
 }
 
 fn main() {
-    let cs = Csound::new();
+    let mut cs = Csound::new();
 
     /* Using SetOption() to configure Csound
     Note: use only one commandline flag at a time */
@@ -101,12 +98,9 @@ fn main() {
      * pass in our CSOUND structure. This call is asynchronous and
      * will immediately return back here to continue code execution
      */
-    let cs = Arc::new(Mutex::new(cs));
-    let cs = Arc::clone(&cs);
-
-    let child = thread::spawn(move || {
-        cs.lock().unwrap().perform();
-    });
+    let mut perf_thread = PerformanceThread::new(cs);
+    perf_thread.play();
 
-    child.join().unwrap();
+    /* Blocks until the end of the score */
+    perf_thread.join();
 }
@@ -88,7 +88,7 @@ fn generate_score() -> String {
 }
 
 fn main() {
-    let cs = Csound::new();
+    let mut cs = Csound::new();
 
     /* Using SetOption() to configure Csound
     Note: use only one commandline flag at a time */
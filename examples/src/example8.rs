@@ -76,7 +76,7 @@ static ORC: &str = "sr=44100
 endin";
 
 fn main() {
-    let cs = Csound::new();
+    let mut cs = Csound::new();
 
     /* Using SetOption() to configure Csound
     Note: use only one commandline flag at a time */
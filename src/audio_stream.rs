@@ -0,0 +1,325 @@
+use std::cell::UnsafeCell;
+use std::ffi::CString;
+use std::ptr;
+use std::slice;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use csound_sys as raw;
+use libc::c_int;
+use raw::{controlChannelType, CSOUND_STATUS};
+
+use crate::channels::{spin_lock, spin_unlock};
+use crate::error::{Error, Result};
+
+// Single producer single consumer ring of samples. The producer only writes `head`
+// and the consumer only writes `tail`, both are counters which wrap around.
+struct Ring {
+    buffer: Box<[UnsafeCell<f64>]>,
+    head: AtomicUsize,
+    tail: AtomicUsize,
+    underruns: AtomicUsize,
+    overruns: AtomicUsize,
+}
+
+// Each sample slot is accessed by one side at a time, the head and tail counters
+// publish the slots from the producer to the consumer and back.
+unsafe impl Sync for Ring {}
+
+impl Ring {
+    fn new(capacity: usize) -> Ring {
+        Ring {
+            buffer: (0..capacity).map(|_| UnsafeCell::new(0.0)).collect(),
+            head: AtomicUsize::new(0),
+            tail: AtomicUsize::new(0),
+            underruns: AtomicUsize::new(0),
+            overruns: AtomicUsize::new(0),
+        }
+    }
+
+    fn capacity(&self) -> usize {
+        self.buffer.len()
+    }
+
+    fn available(&self) -> usize {
+        self.head
+            .load(Ordering::Acquire)
+            .wrapping_sub(self.tail.load(Ordering::Acquire))
+    }
+
+    // Must only be called by the producer
+    fn push(&self, samples: &[f64]) -> usize {
+        let head = self.head.load(Ordering::Relaxed);
+        let tail = self.tail.load(Ordering::Acquire);
+        let free = self.capacity() - head.wrapping_sub(tail);
+        let len = samples.len().min(free);
+        for (i, sample) in samples[..len].iter().enumerate() {
+            unsafe { *self.buffer[head.wrapping_add(i) % self.capacity()].get() = *sample }
+        }
+        self.head.store(head.wrapping_add(len), Ordering::Release);
+        len
+    }
+
+    // Must only be called by the consumer
+    fn pop(&self, samples: &mut [f64]) -> usize {
+        let tail = self.tail.load(Ordering::Relaxed);
+        let head = self.head.load(Ordering::Acquire);
+        let len = samples.len().min(head.wrapping_sub(tail));
+        for (i, sample) in samples[..len].iter_mut().enumerate() {
+            *sample = unsafe { *self.buffer[tail.wrapping_add(i) % self.capacity()].get() };
+        }
+        self.tail.store(tail.wrapping_add(len), Ordering::Release);
+        len
+    }
+}
+
+impl std::fmt::Debug for Ring {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("Ring")
+            .field("capacity", &self.capacity())
+            .field("available", &self.available())
+            .finish()
+    }
+}
+
+/// Streams audio from any thread into a csound's input audio channel.
+///
+/// It is created by [`Csound::stream_to_audio_channel`](struct.Csound.html#method.stream_to_audio_channel).
+/// The samples are queued in a lock-free ring buffer, csound moves *ksmps* samples from the ring
+/// into the channel at the beginning of every control period, so the
+/// [*chnget*](http://www.csounds.com/manual/html/chnget.html) opcode reads them in that period.
+/// If the ring has less than *ksmps* samples, the rest of the channel is filled with silence
+/// and an underrun is counted.
+///
+/// The sender is the only producer of its ring, so writing takes `&mut self`.
+/// The stream is closed when the sender is dropped.
+#[derive(Debug)]
+pub struct AudioSender {
+    ring: Arc<Ring>,
+}
+
+/// Streams audio from a csound's output audio channel to any thread.
+///
+/// It is created by [`Csound::stream_from_audio_channel`](struct.Csound.html#method.stream_from_audio_channel).
+/// At the beginning of every control period csound copies the *ksmps* samples
+/// written into the channel during the previous period, e.g by the [*chnset*](http://www.csounds.com/manual/html/chnset.html)
+/// opcode, into a lock-free ring buffer. If the ring doesn't have space for them, they are
+/// dropped and an overrun is counted.
+///
+/// The receiver is the only consumer of its ring, so reading takes `&mut self`.
+/// The stream is closed when the receiver is dropped.
+#[derive(Debug)]
+pub struct AudioReceiver {
+    ring: Arc<Ring>,
+}
+
+impl AudioSender {
+    /// Queues *samples* to be written into the channel.
+    /// # Returns
+    /// The number of samples queued, less than *samples* length if the ring is full.
+    pub fn write(&mut self, samples: &[f64]) -> usize {
+        self.ring.push(samples)
+    }
+
+    /// # Returns
+    /// The number of samples which can be queued.
+    pub fn free(&self) -> usize {
+        self.ring.capacity() - self.ring.available()
+    }
+
+    /// # Returns
+    /// The ring capacity in samples.
+    pub fn capacity(&self) -> usize {
+        self.ring.capacity()
+    }
+
+    /// # Returns
+    /// The number of control periods in which the ring didn't have *ksmps* samples.
+    pub fn underruns(&self) -> usize {
+        self.ring.underruns.load(Ordering::Relaxed)
+    }
+}
+
+impl AudioReceiver {
+    /// Reads the queued samples into *samples*.
+    /// # Returns
+    /// The number of samples read.
+    pub fn read(&mut self, samples: &mut [f64]) -> usize {
+        self.ring.pop(samples)
+    }
+
+    /// # Returns
+    /// The number of samples ready to be read.
+    pub fn available(&self) -> usize {
+        self.ring.available()
+    }
+
+    /// # Returns
+    /// The ring capacity in samples.
+    pub fn capacity(&self) -> usize {
+        self.ring.capacity()
+    }
+
+    /// # Returns
+    /// The number of control periods whose samples were dropped because the ring was full.
+    pub fn overruns(&self) -> usize {
+        self.ring.overruns.load(Ordering::Relaxed)
+    }
+}
+
+enum Direction {
+    Input,
+    Output,
+}
+
+struct AudioStream {
+    name: CString,
+    direction: Direction,
+    ring: Arc<Ring>,
+}
+
+/// The audio channels bridged to ring buffers, processed once per control period
+/// from the sense event callback.
+#[derive(Default)]
+pub(crate) struct AudioStreams {
+    streams: Vec<AudioStream>,
+}
+
+impl AudioStreams {
+    pub(crate) fn add_input(&mut self, name: &str, capacity: usize) -> Result<AudioSender> {
+        let ring = self.add(name, capacity, Direction::Input)?;
+        Ok(AudioSender { ring })
+    }
+
+    pub(crate) fn add_output(&mut self, name: &str, capacity: usize) -> Result<AudioReceiver> {
+        let ring = self.add(name, capacity, Direction::Output)?;
+        Ok(AudioReceiver { ring })
+    }
+
+    fn add(&mut self, name: &str, capacity: usize, direction: Direction) -> Result<Arc<Ring>> {
+        if capacity == 0 {
+            return Err(Error::InvalidArgument(
+                "the ring capacity must be greater than zero",
+            ));
+        }
+        let ring = Arc::new(Ring::new(capacity));
+        self.streams.push(AudioStream {
+            name: CString::new(name)?,
+            direction,
+            ring: Arc::clone(&ring),
+        });
+        Ok(ring)
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.streams.is_empty()
+    }
+
+    /// Moves one control period of samples between every ring and its channel.
+    ///
+    /// The channel pointers are requested on each call, because csound frees them on reset.
+    pub(crate) unsafe fn process(&mut self, csound: *mut raw::CSOUND) {
+        // Streams whose sender or receiver was dropped are closed
+        self.streams
+            .retain(|stream| Arc::strong_count(&stream.ring) > 1);
+        if self.streams.is_empty() {
+            return;
+        }
+        let ksmps = raw::csoundGetKsmps(csound) as usize;
+        for stream in self.streams.iter() {
+            let bits = match stream.direction {
+                Direction::Input => controlChannelType::CSOUND_INPUT_CHANNEL,
+                Direction::Output => controlChannelType::CSOUND_OUTPUT_CHANNEL,
+            };
+            let mut ptr: *mut f64 = ptr::null_mut();
+            // Channels with another type are skipped
+            if raw::csoundGetChannelPtr(
+                csound,
+                &mut ptr as *mut *mut _,
+                stream.name.as_ptr(),
+                (controlChannelType::CSOUND_AUDIO_CHANNEL | bits) as c_int,
+            ) != CSOUND_STATUS::CSOUND_SUCCESS
+                || ptr.is_null()
+            {
+                continue;
+            }
+            let lock = raw::csoundGetChannelLock(csound, stream.name.as_ptr()) as *mut c_int;
+            spin_lock(lock);
+            let channel = slice::from_raw_parts_mut(ptr, ksmps);
+            match stream.direction {
+                Direction::Input => {
+                    let read = stream.ring.pop(channel);
+                    if read < ksmps {
+                        channel[read..].iter_mut().for_each(|sample| *sample = 0.0);
+                        stream.ring.underruns.fetch_add(1, Ordering::Relaxed);
+                    }
+                }
+                Direction::Output => {
+                    if stream.ring.capacity() - stream.ring.available() < ksmps {
+                        stream.ring.overruns.fetch_add(1, Ordering::Relaxed);
+                    } else {
+                        stream.ring.push(channel);
+                    }
+                }
+            }
+            spin_unlock(lock);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Ring;
+
+    #[test]
+    fn full_and_empty() {
+        let ring = Ring::new(4);
+        let mut out = [0.0; 4];
+        assert_eq!(ring.pop(&mut out), 0);
+        assert_eq!(ring.push(&[1.0, 2.0, 3.0, 4.0]), 4);
+        assert_eq!(ring.available(), 4);
+        assert_eq!(ring.push(&[5.0]), 0);
+        assert_eq!(ring.pop(&mut out), 4);
+        assert_eq!(out, [1.0, 2.0, 3.0, 4.0]);
+        assert_eq!(ring.available(), 0);
+    }
+
+    #[test]
+    fn partial_push_and_pop() {
+        let ring = Ring::new(4);
+        assert_eq!(ring.push(&[1.0, 2.0, 3.0, 4.0, 5.0, 6.0]), 4);
+        let mut out = [0.0; 3];
+        assert_eq!(ring.pop(&mut out), 3);
+        assert_eq!(out, [1.0, 2.0, 3.0]);
+        let mut out = [0.0; 3];
+        assert_eq!(ring.pop(&mut out), 1);
+        assert_eq!(out, [4.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn wrap_around() {
+        let ring = Ring::new(3);
+        let mut out = [0.0; 2];
+        for i in 0..10 {
+            let block = [i as f64, i as f64 + 0.5];
+            assert_eq!(ring.push(&block), 2);
+            assert_eq!(ring.pop(&mut out), 2);
+            assert_eq!(out, block);
+        }
+        assert_eq!(ring.available(), 0);
+    }
+
+    #[test]
+    fn wrapping_counters() {
+        let ring = Ring::new(4);
+        ring.head
+            .store(usize::MAX - 1, std::sync::atomic::Ordering::Relaxed);
+        ring.tail
+            .store(usize::MAX - 1, std::sync::atomic::Ordering::Relaxed);
+        assert_eq!(ring.push(&[1.0, 2.0, 3.0]), 3);
+        assert_eq!(ring.available(), 3);
+        let mut out = [0.0; 4];
+        assert_eq!(ring.pop(&mut out), 3);
+        assert_eq!(&out[..3], &[1.0, 2.0, 3.0]);
+    }
+}
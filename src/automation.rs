@@ -0,0 +1,349 @@
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex, MutexGuard, Weak};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::csound::Csound;
+use crate::error::Result;
+use crate::handle::CsoundHandle;
+
+/// A control channel write at a performance time.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct AutomationEvent {
+    /// The performance time in samples when the value was written.
+    pub time: usize,
+    /// The channel name.
+    pub channel: String,
+    /// The value written.
+    pub value: f64,
+}
+
+/// A timeline of control channel writes.
+///
+/// It is recorded with an [`AutomationRecorder`](struct.AutomationRecorder.html) and played back with an
+/// [`AutomationPlayer`](struct.AutomationPlayer.html).
+/// Automations are saved as text, one event per line with the time in samples,
+/// the channel name and the value separated by tabs. Lines starting with `#` are ignored.
+/// # Example
+/// ```
+/// use csound::Automation;
+///
+/// let mut automation = Automation::new();
+/// automation.push(64, "cutoff", 1000.0);
+/// automation.push(0, "amp", 0.5);
+///
+/// let mut file = Vec::new();
+/// automation.write_to(&mut file).unwrap();
+/// let loaded = Automation::read_from(&file[..]).unwrap();
+/// assert_eq!(loaded, automation);
+/// assert_eq!(loaded.events()[0].channel, "amp");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Automation {
+    events: Vec<AutomationEvent>,
+}
+
+impl Automation {
+    /// Creates an empty automation.
+    pub fn new() -> Automation {
+        Automation::default()
+    }
+
+    /// Adds an event, keeping the events sorted by time.
+    /// Events with the same time keep the insertion order.
+    pub fn push(&mut self, time: usize, channel: &str, value: f64) {
+        let index = self.events.partition_point(|event| event.time <= time);
+        self.events.insert(
+            index,
+            AutomationEvent {
+                time,
+                channel: channel.to_owned(),
+                value,
+            },
+        );
+    }
+
+    /// # Returns
+    /// The events sorted by time.
+    pub fn events(&self) -> &[AutomationEvent] {
+        &self.events
+    }
+
+    /// # Returns
+    /// The number of events.
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    /// # Returns
+    /// true if there are not events.
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    /// # Returns
+    /// The time of the last event in samples, or None if there are not events.
+    pub fn end_time(&self) -> Option<usize> {
+        self.events.last().map(|event| event.time)
+    }
+
+    /// Moves every event *offset* samples earlier, events before *offset* are moved to time 0.
+    /// Useful to replay a recording which started in the middle of a performance.
+    pub fn shift_to_start(&mut self, offset: usize) {
+        for event in self.events.iter_mut() {
+            event.time = event.time.saturating_sub(offset);
+        }
+    }
+
+    /// Writes the automation as text into *writer*.
+    pub fn write_to<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writeln!(writer, "# time\tchannel\tvalue")?;
+        for event in &self.events {
+            if event.channel.is_empty() || event.channel.contains(char::is_whitespace) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("invalid channel name {:?}", event.channel),
+                ));
+            }
+            writeln!(
+                writer,
+                "{}\t{}\t{:?}",
+                event.time, event.channel, event.value
+            )?;
+        }
+        writer.flush()
+    }
+
+    /// Reads an automation written by [`Automation::write_to`](struct.Automation.html#method.write_to).
+    pub fn read_from<R: Read>(reader: R) -> io::Result<Automation> {
+        let mut automation = Automation::new();
+        for (number, line) in BufReader::new(reader).lines().enumerate() {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let invalid = || {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("invalid automation event at line {}", number + 1),
+                )
+            };
+            let mut fields = line.split('\t');
+            let time = fields
+                .next()
+                .and_then(|t| t.parse().ok())
+                .ok_or_else(invalid)?;
+            let channel = fields
+                .next()
+                .filter(|c| !c.is_empty())
+                .ok_or_else(invalid)?;
+            let value = fields
+                .next()
+                .and_then(|v| v.parse().ok())
+                .ok_or_else(invalid)?;
+            if fields.next().is_some() {
+                return Err(invalid());
+            }
+            automation.push(time, channel, value);
+        }
+        Ok(automation)
+    }
+
+    /// Saves the automation into the file at *path*.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        self.write_to(BufWriter::new(File::create(path)?))
+    }
+
+    /// Loads the automation saved in the file at *path*.
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Automation> {
+        Automation::read_from(File::open(path)?)
+    }
+}
+
+/// Records every control channel write of the host into an [`Automation`](struct.Automation.html).
+///
+/// While the recorder is alive, the values written with
+/// [`Csound::set_control_channel`](struct.Csound.html#method.set_control_channel), a
+/// [`CsoundHandle`](struct.CsoundHandle.html), a [`PerformanceThread`](struct.PerformanceThread.html),
+/// [`Parameter`](struct.Parameter.html)s, channel bindings or control [`InputChannel`](struct.InputChannel.html)s
+/// are recorded, as well as the writes made through the recorder itself or replayed by an
+/// [`AutomationPlayer`](struct.AutomationPlayer.html).
+/// Each write is stamped with the performance time, so the recording can be replayed by an
+/// [`AutomationPlayer`](struct.AutomationPlayer.html) at the same control periods.
+/// A csound instance is recorded by one recorder at a time, creating a new recorder replaces the
+/// previous one, and the recording stops when the recorder and all its clones are dropped.
+/// The recorder can be cloned and sent to other threads, e.g a GUI or a MIDI thread,
+/// all the clones record into the same automation.
+/// # Example
+/// ```no_run
+/// use csound::{AutomationRecorder, Csound};
+/// use std::thread;
+///
+/// let mut cs = Csound::new();
+/// cs.compile_csd("synth.csd").unwrap();
+/// cs.start().unwrap();
+///
+/// let recorder = AutomationRecorder::new(cs.handle());
+/// let gui = recorder.clone();
+/// thread::spawn(move || {
+///     for i in 0..100 {
+///         gui.set_control_channel("cutoff", 200.0 + i as f64 * 10.0).unwrap();
+///         thread::sleep(std::time::Duration::from_millis(20));
+///     }
+/// });
+/// while !cs.perform_ksmps() {}
+/// recorder.take().save("session.automation").unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct AutomationRecorder {
+    handle: CsoundHandle,
+    automation: Arc<Mutex<Automation>>,
+}
+
+impl AutomationRecorder {
+    /// Creates a recorder of the csound instance of *handle*.
+    pub fn new(handle: CsoundHandle) -> AutomationRecorder {
+        let automation = Arc::new(Mutex::new(Automation::new()));
+        handle.engine.recorder.set(&automation);
+        AutomationRecorder { handle, automation }
+    }
+
+    /// Writes *value* into the control channel *name*, it is recorded as any other write.
+    /// The event is stamped with the performance time before the write,
+    /// which is the control period where csound reads the value.
+    pub fn set_control_channel(&self, name: &str, value: f64) -> Result<()> {
+        self.handle.set_control_channel(name, value)
+    }
+
+    /// # Returns
+    /// A copy of the events recorded so far.
+    pub fn automation(&self) -> Automation {
+        self.lock().clone()
+    }
+
+    /// # Returns
+    /// The events recorded so far, the recorder starts a new automation.
+    pub fn take(&self) -> Automation {
+        std::mem::take(&mut *self.lock())
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Automation> {
+        lock(&self.automation)
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    // The automation is always left in a valid state
+    match mutex.lock() {
+        Ok(guard) => guard,
+        Err(poisoned) => poisoned.into_inner(),
+    }
+}
+
+/// The automation recording a csound instance, every control channel write of the host is
+/// pushed into it.
+#[derive(Debug, Default)]
+pub(crate) struct RecorderSlot {
+    automation: Mutex<Option<Weak<Mutex<Automation>>>>,
+}
+
+impl RecorderSlot {
+    fn set(&self, automation: &Arc<Mutex<Automation>>) {
+        *lock(&self.automation) = Some(Arc::downgrade(automation));
+    }
+
+    /// Records the write of *value* into the channel *name*, if a recorder is alive.
+    /// It must be called before the write, so the event gets the time of the control period
+    /// where csound reads the value.
+    pub(crate) unsafe fn record(&self, csound: *mut csound_sys::CSOUND, name: &str, value: f64) {
+        let automation = match lock(&self.automation).as_ref().and_then(Weak::upgrade) {
+            Some(automation) => automation,
+            None => return,
+        };
+        let time = csound_sys::csoundGetCurrentTimeSamples(csound) as usize;
+        lock(&automation).push(time, name, value);
+    }
+}
+
+/// Plays an [`Automation`](struct.Automation.html) back.
+///
+/// [`AutomationPlayer::apply`](struct.AutomationPlayer.html#method.apply) has to be called before every
+/// control period, it writes the events recorded up to the current performance time, so each value
+/// reaches csound at the same control period it did while recording.
+/// # Example
+/// ```no_run
+/// use csound::{Automation, AutomationPlayer, Csound};
+///
+/// let mut cs = Csound::new();
+/// cs.set_option("-osession.wav").unwrap();
+/// cs.compile_csd("synth.csd").unwrap();
+/// cs.start().unwrap();
+///
+/// let mut player = AutomationPlayer::new(Automation::load("session.automation").unwrap());
+/// loop {
+///     player.apply(&cs).unwrap();
+///     if cs.perform_ksmps() {
+///         break;
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct AutomationPlayer {
+    automation: Automation,
+    position: usize,
+}
+
+impl AutomationPlayer {
+    /// Creates a player at the beginning of *automation*.
+    pub fn new(automation: Automation) -> AutomationPlayer {
+        AutomationPlayer {
+            automation,
+            position: 0,
+        }
+    }
+
+    /// Writes the pending events whose time is not later than the current performance time.
+    /// # Returns
+    /// The number of events written.
+    pub fn apply(&mut self, csound: &Csound) -> Result<usize> {
+        self.apply_until(&csound.shared_handle(), csound.get_current_sample_time())
+    }
+
+    /// Writes the pending events whose time is not later than *time*.
+    /// # Returns
+    /// The number of events written.
+    pub fn apply_until(&mut self, handle: &CsoundHandle, time: usize) -> Result<usize> {
+        let start = self.position;
+        while let Some(event) = self.automation.events.get(self.position) {
+            if event.time > time {
+                break;
+            }
+            handle.set_control_channel(&event.channel, event.value)?;
+            self.position += 1;
+        }
+        Ok(self.position - start)
+    }
+
+    /// # Returns
+    /// true if all the events have been written.
+    pub fn is_finished(&self) -> bool {
+        self.position >= self.automation.events.len()
+    }
+
+    /// Moves the player back to the beginning of the automation.
+    pub fn rewind(&mut self) {
+        self.position = 0;
+    }
+
+    /// # Returns
+    /// The automation played.
+    pub fn automation(&self) -> &Automation {
+        &self.automation
+    }
+}
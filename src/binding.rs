@@ -0,0 +1,340 @@
+use std::ops::{Deref, DerefMut};
+
+use crate::channels::{InputChannel, OutputChannel};
+use crate::csound::Csound;
+use crate::enums::{ControlChannel, ControlChannelType};
+use crate::error::{Error, Result};
+use crate::handle::CsoundHandle;
+
+/// The kind of data a bound channel carries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChannelKind {
+    /// A control channel, one f64 value.
+    Control,
+    /// A string channel.
+    String,
+}
+
+/// Description of a struct field bound to a csound's channel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChannelSpec {
+    /// The channel name.
+    pub name: &'static str,
+    /// The channel data type.
+    pub kind: ChannelKind,
+    /// The field is written into csound by [`Bound::push`](struct.Bound.html#method.push).
+    pub input: bool,
+    /// The field is read from csound by [`Bound::pull`](struct.Bound.html#method.pull).
+    pub output: bool,
+}
+
+/// Types which can be stored in a control channel.
+pub trait ControlValue {
+    fn to_control(&self) -> f64;
+    fn from_control(value: f64) -> Self;
+}
+
+impl ControlValue for f64 {
+    fn to_control(&self) -> f64 {
+        *self
+    }
+    fn from_control(value: f64) -> Self {
+        value
+    }
+}
+
+impl ControlValue for f32 {
+    fn to_control(&self) -> f64 {
+        f64::from(*self)
+    }
+    fn from_control(value: f64) -> Self {
+        value as f32
+    }
+}
+
+impl ControlValue for i32 {
+    fn to_control(&self) -> f64 {
+        f64::from(*self)
+    }
+    fn from_control(value: f64) -> Self {
+        value.round() as i32
+    }
+}
+
+impl ControlValue for u32 {
+    fn to_control(&self) -> f64 {
+        f64::from(*self)
+    }
+    fn from_control(value: f64) -> Self {
+        value.round() as u32
+    }
+}
+
+impl ControlValue for bool {
+    fn to_control(&self) -> f64 {
+        if *self {
+            1.0
+        } else {
+            0.0
+        }
+    }
+    fn from_control(value: f64) -> Self {
+        value != 0.0
+    }
+}
+
+/// Structs whose fields mirror a set of csound's channels.
+///
+/// This trait is usually implemented with `#[derive(CsoundChannels)]`, available with the `derive` feature.
+/// Each field annotated with `#[channel(...)]` is bound to a channel:
+/// - `#[channel("cutoff")]` binds the field to the input control channel *cutoff*.
+/// - `#[channel(name = "label", kind = string)]` binds the field to the string channel *label*,
+///   String fields are bound to string channels by default.
+/// - `input`, `output` or both select the direction, the default is `input`. Input fields are written into csound by
+///   [`Bound::push`](struct.Bound.html#method.push), output fields are updated by
+///   [`Bound::pull`](struct.Bound.html#method.pull).
+///
+/// Control fields can be of any [`ControlValue`](trait.ControlValue.html) type.
+/// # Example
+/// ```no_run
+/// # #[cfg(feature = "derive")]
+/// # mod example {
+/// use csound::{Csound, CsoundChannels};
+///
+/// #[derive(CsoundChannels, Default)]
+/// struct Synth {
+///     #[channel("cutoff")]
+///     cutoff: f64,
+///     #[channel(name = "label", kind = string)]
+///     label: String,
+///     #[channel(name = "rms", output)]
+///     rms: f64,
+///     // fields without the channel attribute are ignored
+///     presets: Vec<f64>,
+/// }
+///
+/// # pub fn run() {
+/// let mut cs = Csound::new();
+/// cs.compile_csd("synth.csd").unwrap();
+/// cs.start().unwrap();
+///
+/// // Fails if a channel is not declared in the orchestra with the field's type and direction
+/// let mut synth = Synth::default().bind(&cs).unwrap();
+/// while !cs.perform_ksmps() {
+///     synth.cutoff = 1200.0;
+///     synth.push().unwrap();
+///     synth.pull().unwrap();
+///     println!("rms: {}", synth.rms);
+/// }
+/// # }
+/// # }
+/// ```
+/// The derived [`channels`](trait.CsoundChannels.html#tymethod.channels) lists the bound fields:
+/// ```
+/// # #[cfg(feature = "derive")]
+/// # {
+/// use csound::{ChannelKind, ChannelSpec, CsoundChannels};
+///
+/// #[derive(CsoundChannels)]
+/// struct Mixer {
+///     #[channel("gain")]
+///     gain: f32,
+///     #[channel(name = "title")]
+///     title: String,
+///     #[channel(name = "mute", input, output)]
+///     mute: bool,
+///     #[channel(name = "peak", output)]
+///     peak: f64,
+///     #[channel(name = "preset", kind = "string", output)]
+///     preset: String,
+///     unbound: u32,
+/// }
+///
+/// let spec = |name, kind, input, output| ChannelSpec { name, kind, input, output };
+/// assert_eq!(
+///     Mixer::channels(),
+///     &[
+///         spec("gain", ChannelKind::Control, true, false),
+///         spec("title", ChannelKind::String, true, false),
+///         spec("mute", ChannelKind::Control, true, true),
+///         spec("peak", ChannelKind::Control, false, true),
+///         spec("preset", ChannelKind::String, false, true),
+///     ]
+/// );
+/// # }
+/// ```
+pub trait CsoundChannels: Sized {
+    /// The channels bound to the struct fields, in declaration order.
+    fn channels() -> &'static [ChannelSpec];
+
+    /// Writes the input fields into *binding*.
+    fn push_channels(&self, binding: &ChannelBinding) -> Result<()>;
+
+    /// Updates the output fields from *binding*.
+    fn pull_channels(&mut self, binding: &ChannelBinding) -> Result<()>;
+
+    /// Binds the struct to the csound's channels.
+    /// # Returns
+    /// The bound struct, an [`Error::ChannelNotFound`](enum.Error.html#variant.ChannelNotFound)
+    /// if a channel doesn't exist, or an [`Error::WrongChannelType`](enum.Error.html#variant.WrongChannelType)
+    /// if its type or direction does not match its field.
+    fn bind(self, csound: &Csound) -> Result<Bound<'_, Self>> {
+        Ok(Bound {
+            binding: ChannelBinding::new(csound, Self::channels())?,
+            value: self,
+        })
+    }
+}
+
+enum Slot<'a> {
+    ControlInput(InputChannel<'a, ControlChannel>),
+    ControlOutput(OutputChannel<'a, ControlChannel>),
+    String(&'static str),
+}
+
+/// The csound's channels bound to a [`CsoundChannels`](trait.CsoundChannels.html) struct.
+///
+/// Control channels are accessed atomically and string channels through the csound's
+/// thread-safe get/set functions.
+pub struct ChannelBinding<'a> {
+    csound: &'a Csound,
+    handle: CsoundHandle,
+    slots: Vec<Slot<'a>>,
+}
+
+impl<'a> ChannelBinding<'a> {
+    /// Requests every channel in *specs*.
+    ///
+    /// The channels must already exist, e.g declared with the `chn_k` and `chn_S` opcodes,
+    /// so misspelled names are reported instead of creating new channels.
+    /// # Returns
+    /// [`Error::ChannelNotFound`](enum.Error.html#variant.ChannelNotFound) if a channel doesn't exist,
+    /// or [`Error::WrongChannelType`](enum.Error.html#variant.WrongChannelType) if it is not a control
+    /// or string channel as the spec requires, or it is not an input or output channel when the spec is.
+    pub fn new(csound: &'a Csound, specs: &[ChannelSpec]) -> Result<ChannelBinding<'a>> {
+        let channels = csound.channel_list()?;
+        for spec in specs {
+            let kind = match spec.kind {
+                ChannelKind::Control => ControlChannelType::CSOUND_CONTROL_CHANNEL,
+                ChannelKind::String => ControlChannelType::CSOUND_STRING_CHANNEL,
+            };
+            let info = channels
+                .iter()
+                .find(|info| info.name == spec.name)
+                .ok_or_else(|| Error::ChannelNotFound(spec.name.to_owned()))?;
+            if info.kind() != kind
+                || (spec.input && !info.is_input())
+                || (spec.output && !info.is_output())
+            {
+                return Err(Error::WrongChannelType(spec.name.to_owned()));
+            }
+        }
+
+        let mut slots = Vec::with_capacity(specs.len());
+        for spec in specs {
+            let slot = match spec.kind {
+                ChannelKind::Control => {
+                    if spec.output {
+                        let channel = csound.get_output_channel::<ControlChannel>(spec.name)?;
+                        if !spec.input {
+                            slots.push(Slot::ControlOutput(channel));
+                            continue;
+                        }
+                    }
+                    Slot::ControlInput(csound.get_input_channel::<ControlChannel>(spec.name)?)
+                }
+                ChannelKind::String => Slot::String(spec.name),
+            };
+            slots.push(slot);
+        }
+        Ok(ChannelBinding {
+            csound,
+            handle: csound.shared_handle(),
+            slots,
+        })
+    }
+
+    /// # Returns
+    /// The value of the control channel at *index*.
+    /// # Panic
+    /// If the channel at *index* is not a control channel.
+    pub fn read_control(&self, index: usize) -> f64 {
+        match &self.slots[index] {
+            Slot::ControlInput(channel) => channel.load(),
+            Slot::ControlOutput(channel) => channel.load(),
+            Slot::String(name) => panic!("the channel {} is not a control channel", name),
+        }
+    }
+
+    /// Writes *value* into the control channel at *index*.
+    /// # Panic
+    /// If the channel at *index* is not an input control channel.
+    pub fn write_control(&self, index: usize, value: f64) {
+        match &self.slots[index] {
+            Slot::ControlInput(channel) => channel.store(value),
+            _ => panic!("the channel at {} is not an input control channel", index),
+        }
+    }
+
+    /// # Returns
+    /// The content of the string channel at *index*.
+    /// # Panic
+    /// If the channel at *index* is not a string channel.
+    pub fn read_string(&self, index: usize) -> String {
+        match &self.slots[index] {
+            Slot::String(name) => self.csound.get_string_channel(name),
+            _ => panic!("the channel at {} is not a string channel", index),
+        }
+    }
+
+    /// Writes *value* into the string channel at *index*.
+    /// # Panic
+    /// If the channel at *index* is not a string channel.
+    pub fn write_string(&self, index: usize, value: &str) -> Result<()> {
+        match &self.slots[index] {
+            Slot::String(name) => self.handle.set_string_channel(name, value),
+            _ => panic!("the channel at {} is not a string channel", index),
+        }
+    }
+}
+
+/// A [`CsoundChannels`](trait.CsoundChannels.html) struct bound to a csound instance.
+///
+/// It dereferences to the struct, so fields can be changed as usual and then sent
+/// to csound with [`Bound::push`](struct.Bound.html#method.push).
+pub struct Bound<'a, T: CsoundChannels> {
+    value: T,
+    binding: ChannelBinding<'a>,
+}
+
+impl<'a, T: CsoundChannels> Bound<'a, T> {
+    /// Writes all the input fields into their channels.
+    pub fn push(&self) -> Result<()> {
+        self.value.push_channels(&self.binding)
+    }
+
+    /// Reads all the output fields from their channels.
+    pub fn pull(&mut self) -> Result<()> {
+        self.value.pull_channels(&self.binding)
+    }
+
+    /// # Returns
+    /// The struct, releasing the channels.
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+impl<'a, T: CsoundChannels> Deref for Bound<'a, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<'a, T: CsoundChannels> DerefMut for Bound<'a, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.value
+    }
+}
@@ -1,7 +1,13 @@
 use libc::c_void;
+use std::sync::{Arc, Mutex};
 
+use crate::audio_stream::AudioStreams;
+use crate::channels::PvsDataExt;
+use crate::cscore::Cscore;
 use crate::enums::{ChannelData, FileTypes, MessageType, Status};
 use crate::rtaudio::{CsAudioDevice, RtAudioParams};
+use crate::scheduler::{self, TaskQueue};
+use crate::watch::ChannelWatchers;
 
 use csound_sys as raw;
 use raw::{controlChannelType, CSOUND_STATUS};
@@ -21,137 +27,168 @@ pub struct FileInfo {
 
 #[doc(hidden)]
 #[derive(Default)]
-pub struct Callbacks<'a> {
-    pub message_cb: Option<Box<dyn FnMut(MessageType, &str) + 'a>>,
-    pub devlist_cb: Option<Box<dyn FnMut(CsAudioDevice) + 'a>>,
-    pub play_open_cb: Option<Box<dyn FnMut(&RtAudioParams) -> Status + 'a>>,
-    pub rec_open_cb: Option<Box<dyn FnMut(&RtAudioParams) -> Status + 'a>>,
-    pub rt_play_cb: Option<Box<dyn FnMut(&[f64]) + 'a>>,
-    pub rt_rec_cb: Option<Box<dyn FnMut(&mut [f64]) -> usize + 'a>>,
-    pub sense_event_cb: Option<Box<dyn FnMut() + 'a>>,
-    pub keyboard_cb: Option<Box<dyn FnMut() -> char + 'a>>, // TODO this callback doesn't work at the
+pub struct Callbacks {
+    pub message_cb: Option<Box<dyn FnMut(MessageType, &str) + Send>>,
+    pub devlist_cb: Option<Box<dyn FnMut(CsAudioDevice) + Send>>,
+    pub play_open_cb: Option<Box<dyn FnMut(&RtAudioParams) -> Status + Send>>,
+    pub rec_open_cb: Option<Box<dyn FnMut(&RtAudioParams) -> Status + Send>>,
+    pub rt_play_cb: Option<Box<dyn FnMut(&[f64]) + Send>>,
+    pub rt_rec_cb: Option<Box<dyn FnMut(&mut [f64]) -> usize + Send>>,
+    pub sense_event_cb: Option<Box<dyn FnMut() + Send>>,
+    pub keyboard_cb: Option<Box<dyn FnMut() -> char + Send>>, // TODO this callback doesn't work at the
     //csound side
-    pub rt_close_cb: Option<Box<dyn FnMut() + 'a>>,
-    pub cscore_cb: Option<Box<dyn FnMut() + 'a>>,
-    pub input_channel_cb: Option<Box<dyn FnMut(&str) -> ChannelData + 'a>>,
-    pub output_channel_cb: Option<Box<dyn FnMut(&str, ChannelData) + 'a>>,
-    pub file_open_cb: Option<Box<dyn FnMut(&FileInfo) + 'a>>,
-    pub midi_in_open_cb: Option<Box<dyn FnMut(&str) + 'a>>,
-    pub midi_out_open_cb: Option<Box<dyn FnMut(&str) + 'a>>,
-    pub midi_read_cb: Option<Box<dyn FnMut(&mut [u8]) -> usize + 'a>>,
-    pub midi_write_cb: Option<Box<dyn FnMut(&[u8]) -> usize + 'a>>,
-    pub midi_in_close_cb: Option<Box<dyn FnMut() + 'a>>,
-    pub midi_out_close_cb: Option<Box<dyn FnMut() + 'a>>,
-    pub yield_cb: Option<Box<dyn FnMut() -> bool + 'a>>,
+    pub rt_close_cb: Option<Box<dyn FnMut() + Send>>,
+    pub cscore_cb: Option<Box<dyn FnMut(&mut Cscore) + Send>>,
+    pub input_channel_cb: Option<Box<dyn FnMut(&str) -> ChannelData + Send>>,
+    pub output_channel_cb: Option<Box<dyn FnMut(&str, ChannelData) + Send>>,
+    pub file_open_cb: Option<Box<dyn FnMut(&FileInfo) + Send>>,
+    pub midi_in_open_cb: Option<Box<dyn FnMut(&str) + Send>>,
+    pub midi_out_open_cb: Option<Box<dyn FnMut(&str) + Send>>,
+    pub midi_read_cb: Option<Box<dyn FnMut(&mut [u8]) -> usize + Send>>,
+    pub midi_write_cb: Option<Box<dyn FnMut(&[u8]) -> usize + Send>>,
+    pub midi_in_close_cb: Option<Box<dyn FnMut() + Send>>,
+    pub midi_out_close_cb: Option<Box<dyn FnMut() + Send>>,
+    pub yield_cb: Option<Box<dyn FnMut() -> bool + Send>>,
+    pub(crate) watchers: ChannelWatchers,
+    pub(crate) audio_streams: AudioStreams,
+    pub(crate) scheduler: Option<Arc<Mutex<TaskQueue>>>,
+    sense_event_registered: bool,
 }
 
-impl<'a> Callbacks<'a> {
-    pub(crate) unsafe fn set_message_cb<F>(&'a mut self, csound: *mut raw::CSOUND, cb: F)
+impl Drop for Callbacks {
+    fn drop(&mut self) {
+        if let Some(queue) = self.scheduler.take() {
+            scheduler::close(&queue);
+        }
+    }
+}
+
+impl Callbacks {
+    pub(crate) unsafe fn set_message_cb<F>(&mut self, csound: *mut raw::CSOUND, cb: F)
     where
-        F: FnMut(MessageType, &str) + 'a,
+        F: FnMut(MessageType, &str) + Send + 'static,
     {
         self.message_cb = Some(Box::new(cb));
         raw::csoundSetMessageStringCallback(csound, Some(Trampoline::message_string_cb))
     }
 
-    pub(crate) unsafe fn set_devlist_cb<F>(&'a mut self, csound: *mut raw::CSOUND, cb: F)
+    pub(crate) unsafe fn set_devlist_cb<F>(&mut self, csound: *mut raw::CSOUND, cb: F)
     where
-        F: FnMut(CsAudioDevice) + 'a,
+        F: FnMut(CsAudioDevice) + Send + 'static,
     {
         self.devlist_cb = Some(Box::new(cb));
         raw::csoundSetAudioDeviceListCallback(csound, Some(Trampoline::audioDeviceListCallback));
     }
 
-    pub(crate) unsafe fn set_play_open_cb<F>(&'a mut self, csound: *mut raw::CSOUND, cb: F)
+    pub(crate) unsafe fn set_play_open_cb<F>(&mut self, csound: *mut raw::CSOUND, cb: F)
     where
-        F: FnMut(&RtAudioParams) -> Status + 'a,
+        F: FnMut(&RtAudioParams) -> Status + Send + 'static,
     {
         self.play_open_cb = Some(Box::new(cb));
         raw::csoundSetPlayopenCallback(csound, Some(Trampoline::playOpenCallback));
     }
 
-    pub(crate) unsafe fn set_rec_open_cb<F>(&'a mut self, csound: *mut raw::CSOUND, cb: F)
+    pub(crate) unsafe fn set_rec_open_cb<F>(&mut self, csound: *mut raw::CSOUND, cb: F)
     where
-        F: FnMut(&RtAudioParams) -> Status + 'a,
+        F: FnMut(&RtAudioParams) -> Status + Send + 'static,
     {
-        self.play_open_cb = Some(Box::new(cb));
+        self.rec_open_cb = Some(Box::new(cb));
         raw::csoundSetRecopenCallback(csound, Some(Trampoline::recOpenCallback));
     }
 
-    pub(crate) unsafe fn set_rt_play_cb<F>(&'a mut self, csound: *mut raw::CSOUND, cb: F)
+    pub(crate) unsafe fn set_rt_play_cb<F>(&mut self, csound: *mut raw::CSOUND, cb: F)
     where
-        F: FnMut(&[f64]) + 'a,
+        F: FnMut(&[f64]) + Send + 'static,
     {
         self.rt_play_cb = Some(Box::new(cb));
         csound_sys::csoundSetRtplayCallback(csound, Some(Trampoline::rtplayCallback));
     }
 
-    pub(crate) unsafe fn set_rt_rec_cb<F>(&'a mut self, csound: *mut raw::CSOUND, cb: F)
+    pub(crate) unsafe fn set_rt_rec_cb<F>(&mut self, csound: *mut raw::CSOUND, cb: F)
     where
-        F: FnMut(&mut [f64]) -> usize + 'a,
+        F: FnMut(&mut [f64]) -> usize + Send + 'static,
     {
         self.rt_rec_cb = Some(Box::new(cb));
         csound_sys::csoundSetRtrecordCallback(csound, Some(Trampoline::rtrecordCallback));
     }
 
-    pub(crate) unsafe fn set_rt_close_cb<F>(&'a mut self, csound: *mut raw::CSOUND, cb: F)
+    pub(crate) unsafe fn set_rt_close_cb<F>(&mut self, csound: *mut raw::CSOUND, cb: F)
     where
-        F: FnMut() + 'a,
+        F: FnMut() + Send + 'static,
     {
         self.rt_close_cb = Some(Box::new(cb));
         csound_sys::csoundSetRtcloseCallback(csound, Some(Trampoline::rtcloseCallback));
     }
 
-    pub(crate) unsafe fn set_sense_event_cb<F>(&'a mut self, csound: *mut raw::CSOUND, cb: F)
+    pub(crate) unsafe fn set_sense_event_cb<F>(&mut self, csound: *mut raw::CSOUND, cb: F)
     where
-        F: FnMut() + 'a,
+        F: FnMut() + Send + 'static,
     {
+        self.register_sense_event_cb(csound);
         self.sense_event_cb = Some(Box::new(cb));
-        csound_sys::csoundRegisterSenseEventCallback(
-            csound,
-            Some(Trampoline::senseEventCallback),
-            ::std::ptr::null_mut() as *mut c_void,
-        );
     }
 
-    /*pub(crate) unsafe fn set_cscore_cb<F>(&'a mut self, csound: *mut raw::CSOUND, cb: F)
+    // csound keeps a list of sense event callbacks, so the trampoline
+    // is registered only once and it calls the user closure, the audio streams and the channel watchers.
+    pub(crate) unsafe fn register_sense_event_cb(&mut self, csound: *mut raw::CSOUND) {
+        if !self.sense_event_registered {
+            csound_sys::csoundRegisterSenseEventCallback(
+                csound,
+                Some(Trampoline::senseEventCallback),
+                ::std::ptr::null_mut() as *mut c_void,
+            );
+            self.sense_event_registered = true;
+        }
+    }
+
+    // csoundReset clears the sense event callbacks, so the trampoline is registered again
+    // if there is something left to call.
+    pub(crate) unsafe fn reset_sense_event_cb(&mut self, csound: *mut raw::CSOUND) {
+        self.sense_event_registered = false;
+        if self.sense_event_cb.is_some()
+            || !self.watchers.is_empty()
+            || !self.audio_streams.is_empty()
+            || self.scheduler.is_some()
+        {
+            self.register_sense_event_cb(csound);
+        }
+    }
+
+    pub(crate) unsafe fn set_cscore_cb<F>(&mut self, csound: *mut raw::CSOUND, cb: F)
     where
-        F: FnMut() + 'a,
+        F: FnMut(&mut Cscore) + Send + 'static,
     {
         self.cscore_cb = Some(Box::new(cb));
-        csound_sys::csoundSetCscoreCallback(
-            csound,
-            Some(Trampoline::scoreCallback),
-        );
-    }*/
+        csound_sys::csoundSetCscoreCallback(csound, Some(Trampoline::scoreCallback));
+    }
 
-    pub(crate) unsafe fn set_input_channel_cb<F>(&'a mut self, csound: *mut raw::CSOUND, cb: F)
+    pub(crate) unsafe fn set_input_channel_cb<F>(&mut self, csound: *mut raw::CSOUND, cb: F)
     where
-        F: FnMut(&str) -> ChannelData + 'a,
+        F: FnMut(&str) -> ChannelData + Send + 'static,
     {
         self.input_channel_cb = Some(Box::new(cb));
         csound_sys::csoundSetInputChannelCallback(csound, Some(Trampoline::inputChannelCallback));
     }
 
-    pub(crate) unsafe fn set_output_channel_cb<F>(&'a mut self, csound: *mut raw::CSOUND, cb: F)
+    pub(crate) unsafe fn set_output_channel_cb<F>(&mut self, csound: *mut raw::CSOUND, cb: F)
     where
-        F: FnMut(&str, ChannelData) + 'a,
+        F: FnMut(&str, ChannelData) + Send + 'static,
     {
         self.output_channel_cb = Some(Box::new(cb));
         csound_sys::csoundSetOutputChannelCallback(csound, Some(Trampoline::outputChannelCallback));
     }
 
-    pub(crate) unsafe fn set_file_open_cb<F>(&'a mut self, csound: *mut raw::CSOUND, cb: F)
+    pub(crate) unsafe fn set_file_open_cb<F>(&mut self, csound: *mut raw::CSOUND, cb: F)
     where
-        F: FnMut(&FileInfo) + 'a,
+        F: FnMut(&FileInfo) + Send + 'static,
     {
         self.file_open_cb = Some(Box::new(cb));
         csound_sys::csoundSetFileOpenCallback(csound, Some(Trampoline::fileOpenCallback));
     }
 
-    pub(crate) unsafe fn set_midi_in_open_cb<F>(&'a mut self, csound: *mut raw::CSOUND, cb: F)
+    pub(crate) unsafe fn set_midi_in_open_cb<F>(&mut self, csound: *mut raw::CSOUND, cb: F)
     where
-        F: FnMut(&str) + 'a,
+        F: FnMut(&str) + Send + 'static,
     {
         self.midi_in_open_cb = Some(Box::new(cb));
         csound_sys::csoundSetExternalMidiInOpenCallback(
@@ -160,9 +197,9 @@ impl<'a> Callbacks<'a> {
         );
     }
 
-    pub(crate) unsafe fn set_midi_out_open_cb<F>(&'a mut self, csound: *mut raw::CSOUND, cb: F)
+    pub(crate) unsafe fn set_midi_out_open_cb<F>(&mut self, csound: *mut raw::CSOUND, cb: F)
     where
-        F: FnMut(&str) + 'a,
+        F: FnMut(&str) + Send + 'static,
     {
         self.midi_out_open_cb = Some(Box::new(cb));
         csound_sys::csoundSetExternalMidiOutOpenCallback(
@@ -171,25 +208,25 @@ impl<'a> Callbacks<'a> {
         );
     }
 
-    pub(crate) unsafe fn set_midi_read_cb<F>(&'a mut self, csound: *mut raw::CSOUND, cb: F)
+    pub(crate) unsafe fn set_midi_read_cb<F>(&mut self, csound: *mut raw::CSOUND, cb: F)
     where
-        F: FnMut(&mut [u8]) -> usize + 'a,
+        F: FnMut(&mut [u8]) -> usize + Send + 'static,
     {
         self.midi_read_cb = Some(Box::new(cb));
         csound_sys::csoundSetExternalMidiReadCallback(csound, Some(Trampoline::midiReadCallback));
     }
 
-    pub(crate) unsafe fn set_midi_write_cb<F>(&'a mut self, csound: *mut raw::CSOUND, cb: F)
+    pub(crate) unsafe fn set_midi_write_cb<F>(&mut self, csound: *mut raw::CSOUND, cb: F)
     where
-        F: FnMut(&[u8]) -> usize + 'a,
+        F: FnMut(&[u8]) -> usize + Send + 'static,
     {
         self.midi_write_cb = Some(Box::new(cb));
         csound_sys::csoundSetExternalMidiWriteCallback(csound, Some(Trampoline::midiWriteCallback));
     }
 
-    pub(crate) unsafe fn set_midi_in_close_cb<F>(&'a mut self, csound: *mut raw::CSOUND, cb: F)
+    pub(crate) unsafe fn set_midi_in_close_cb<F>(&mut self, csound: *mut raw::CSOUND, cb: F)
     where
-        F: FnMut() + 'a,
+        F: FnMut() + Send + 'static,
     {
         self.midi_in_close_cb = Some(Box::new(cb));
         csound_sys::csoundSetExternalMidiInCloseCallback(
@@ -198,9 +235,9 @@ impl<'a> Callbacks<'a> {
         );
     }
 
-    pub(crate) unsafe fn set_midi_out_close_cb<F>(&'a mut self, csound: *mut raw::CSOUND, cb: F)
+    pub(crate) unsafe fn set_midi_out_close_cb<F>(&mut self, csound: *mut raw::CSOUND, cb: F)
     where
-        F: FnMut() + 'a,
+        F: FnMut() + Send + 'static,
     {
         self.midi_out_close_cb = Some(Box::new(cb));
         csound_sys::csoundSetExternalMidiOutCloseCallback(
@@ -209,9 +246,9 @@ impl<'a> Callbacks<'a> {
         );
     }
 
-    pub(crate) unsafe fn set_yield_cb<F>(&'a mut self, csound: *mut raw::CSOUND, cb: F)
+    pub(crate) unsafe fn set_yield_cb<F>(&mut self, csound: *mut raw::CSOUND, cb: F)
     where
-        F: FnMut() -> bool + 'a,
+        F: FnMut() -> bool + Send + 'static,
     {
         self.yield_cb = Some(Box::new(cb));
         csound_sys::csoundSetYieldCallback(csound, Some(Trampoline::yieldCallback));
@@ -223,6 +260,7 @@ pub mod Trampoline {
 
     use super::*;
     use crate::csound::CallbackHandler;
+    use crate::error::Error;
     use crate::rtaudio::{CsAudioDevice, RtAudioParams};
     use libc::{c_char, c_int, c_uchar, c_void, memcpy};
     use std::ffi::{CStr, CString};
@@ -240,22 +278,32 @@ pub mod Trampoline {
         None
     }
 
-    pub fn convert_str_to_c<T>(string: T) -> Result<CString, &'static str>
+    pub fn convert_str_to_c<T>(string: T) -> Result<CString, Error>
     where
         T: AsRef<str>,
     {
         let string = string.as_ref();
         if string.is_empty() {
-            return Err("Failed to convert empty string to C");
+            return Err(Error::EmptyString);
         }
-        CString::new(string).map_err(|_| "Failed converting rust string to CString")
+        Ok(CString::new(string)?)
     }
 
-    fn catch<T, F: FnOnce() -> T>(f: F) -> Option<T> {
+    // Panics must not unwind across the csound's C frames, so the user closures are called
+    // through this function. The panic payload is kept in the callback handler and the
+    // performance is stopped, the panic will be resumed by the next perform call.
+    fn catch<T, F: FnOnce() -> T>(csound: *mut raw::CSOUND, f: F) -> Option<T> {
         match panic::catch_unwind(AssertUnwindSafe(f)) {
             Ok(ret) => Some(ret),
-            Err(_) => {
-                std::process::exit(-1);
+            Err(payload) => {
+                unsafe {
+                    let handler = raw::csoundGetHostData(csound) as *mut CallbackHandler;
+                    if !handler.is_null() && (*handler).panic.is_none() {
+                        (*handler).panic = Some(payload);
+                    }
+                    raw::csoundStop(csound);
+                }
+                None
             }
         }
     }
@@ -264,7 +312,7 @@ pub mod Trampoline {
         _csound: *mut raw::CSOUND,
         _attr: c_int,
         _format: *const c_char,
-        _args: *mut csound_sys::__va_list_tag
+        _args: *mut csound_sys::__va_list_tag,
     ) {
     }
 
@@ -273,7 +321,7 @@ pub mod Trampoline {
         attr: c_int,
         message: *const c_char,
     ) {
-        catch(|| unsafe {
+        catch(csound, || unsafe {
             let info = CStr::from_ptr(message);
             if let Ok(s) = info.to_str() {
                 if let Some(fun) = (*(raw::csoundGetHostData(csound) as *mut CallbackHandler))
@@ -290,14 +338,17 @@ pub mod Trampoline {
     /****** Event callbacks functions *******************************************************************/
 
     pub extern "C" fn senseEventCallback(csound: *mut raw::CSOUND, _userData: *mut c_void) {
-        catch(|| unsafe {
-            if let Some(fun) = (*(raw::csoundGetHostData(csound) as *mut CallbackHandler))
-                .callbacks
-                .sense_event_cb
-                .as_mut()
-            {
+        catch(csound, || unsafe {
+            let callbacks =
+                &mut (*(raw::csoundGetHostData(csound) as *mut CallbackHandler)).callbacks;
+            if let Some(fun) = callbacks.sense_event_cb.as_mut() {
                 fun();
             }
+            callbacks.audio_streams.process(csound);
+            callbacks.watchers.poll(csound);
+            if let Some(queue) = callbacks.scheduler.clone() {
+                crate::scheduler::dispatch(&queue, csound);
+            }
         });
     }
 
@@ -307,7 +358,7 @@ pub mod Trampoline {
         csound: *mut raw::CSOUND,
         dev: *const raw::csRtAudioParams,
     ) -> c_int {
-        catch(|| unsafe {
+        catch(csound, || unsafe {
             let rtParams = RtAudioParams {
                 devName: ptr_to_string((*dev).devName),
                 devNum: (*dev).devNum as u32,
@@ -326,14 +377,14 @@ pub mod Trampoline {
             }
             0
         })
-        .unwrap()
+        .unwrap_or(CSOUND_STATUS::CSOUND_ERROR)
     }
 
     pub extern "C" fn recOpenCallback(
         csound: *mut raw::CSOUND,
         dev: *const raw::csRtAudioParams,
     ) -> c_int {
-        catch(|| unsafe {
+        catch(csound, || unsafe {
             let rtParams = RtAudioParams {
                 devName: ptr_to_string((*dev).devName),
                 devNum: (*dev).devNum as u32,
@@ -352,11 +403,11 @@ pub mod Trampoline {
             }
             -1
         })
-        .unwrap()
+        .unwrap_or(CSOUND_STATUS::CSOUND_ERROR)
     }
 
     pub extern "C" fn rtcloseCallback(csound: *mut raw::CSOUND) {
-        catch(|| unsafe {
+        catch(csound, || unsafe {
             if let Some(fun) = (*(raw::csoundGetHostData(csound) as *mut CallbackHandler))
                 .callbacks
                 .rt_close_cb
@@ -368,7 +419,7 @@ pub mod Trampoline {
     }
 
     pub extern "C" fn rtplayCallback(csound: *mut raw::CSOUND, outBuf: *const f64, nbytes: c_int) {
-        catch(|| unsafe {
+        catch(csound, || unsafe {
             let out = slice::from_raw_parts(outBuf, nbytes as usize);
             if let Some(fun) = (*(raw::csoundGetHostData(csound) as *mut CallbackHandler))
                 .callbacks
@@ -385,7 +436,7 @@ pub mod Trampoline {
         outBuf: *mut f64,
         nbytes: c_int,
     ) -> c_int {
-        catch(|| unsafe {
+        catch(csound, || unsafe {
             let mut buff = slice::from_raw_parts_mut(outBuf, nbytes as usize);
             if let Some(fun) = (*(raw::csoundGetHostData(csound) as *mut CallbackHandler))
                 .callbacks
@@ -396,7 +447,7 @@ pub mod Trampoline {
             }
             -1
         })
-        .unwrap()
+        .unwrap_or(0)
     }
 
     pub extern "C" fn audioDeviceListCallback(
@@ -404,7 +455,7 @@ pub mod Trampoline {
         dev: *mut raw::CS_AUDIODEVICE,
         isOutput: c_int,
     ) -> c_int {
-        catch(|| unsafe {
+        catch(csound, || unsafe {
             let audioDevice = CsAudioDevice {
                 device_name: ptr_to_string((*dev).device_name.as_ptr()),
                 device_id: ptr_to_string((*dev).device_id.as_ptr()),
@@ -421,7 +472,7 @@ pub mod Trampoline {
             }
             0
         })
-        .unwrap()
+        .unwrap_or(0)
     }
 
     /*pub extern "C" fn keyboard_callback(
@@ -450,7 +501,7 @@ pub mod Trampoline {
         operation: c_int,
         isTemp: c_int,
     ) {
-        catch(|| unsafe {
+        catch(csound, || unsafe {
             let name = ptr_to_string(filePath);
             let file_info = FileInfo {
                 name,
@@ -470,29 +521,108 @@ pub mod Trampoline {
 
     /* Score Handling callbacks ********************************************************* */
 
-    // Sets an pub external callback for Cscore processing. Pass NULL to reset to the internal cscore() function (which does nothing).
-    // This callback is retained after a csoundReset() call.
-    /*pub extern "C" fn scoreCallback(csound: *mut raw::CSOUND) {
-        catch(|| unsafe {
+    // Called by csound to process the score when the -C option is set,
+    // it reads the sorted score and writes the score which is performed.
+    pub extern "C" fn scoreCallback(csound: *mut raw::CSOUND) {
+        catch(csound, || unsafe {
             if let Some(fun) = (*(raw::csoundGetHostData(csound) as *mut CallbackHandler))
                 .callbacks
                 .cscore_cb
                 .as_mut()
             {
-                fun();
+                fun(&mut Cscore::new(csound));
             }
         });
-    }*/
+    }
 
     /* Channels and events callbacks **************************************************** */
 
+    // Layouts of csound's CS_TYPE (only its name is read), AUXCH and PVSDAT,
+    // csound-sys does not export them.
+    #[repr(C)]
+    struct CsType {
+        var_type_name: *const c_char,
+    }
+
+    #[repr(C)]
+    struct AuxCh {
+        next: *mut c_void,
+        size: usize,
+        auxp: *mut c_void,
+        endp: *mut c_void,
+    }
+
+    #[repr(C)]
+    struct PvsDat {
+        N: i32,
+        sliding: c_int,
+        NB: i32,
+        overlap: i32,
+        winsize: i32,
+        wintype: c_int,
+        format: i32,
+        framecount: u32,
+        frame: AuxCh,
+    }
+
+    // The type of the invalue/outvalue argument
+    enum ValueKind {
+        Control,
+        String,
+        Audio,
+        Pvs,
+        Unknown,
+    }
+
+    unsafe fn value_kind(
+        csound: *mut raw::CSOUND,
+        channelName: *const c_char,
+        channelType: *const c_void,
+    ) -> ValueKind {
+        if !channelType.is_null() {
+            let type_name = (*(channelType as *const CsType)).var_type_name;
+            if !type_name.is_null() {
+                return match CStr::from_ptr(type_name).to_bytes() {
+                    b"k" | b"i" | b"c" | b"r" => ValueKind::Control,
+                    b"S" => ValueKind::String,
+                    b"a" => ValueKind::Audio,
+                    b"f" => ValueKind::Pvs,
+                    _ => ValueKind::Unknown,
+                };
+            }
+        }
+        // Without the argument type, the channel type is asked to csound
+        let mut ptr = ::std::ptr::null_mut();
+        let ptr: *mut *mut f64 = &mut ptr as *mut *mut _;
+        let channel_type = raw::csoundGetChannelPtr(csound, ptr, channelName, 0);
+        match (channel_type & controlChannelType::CSOUND_CHANNEL_TYPE_MASK as i32) as u32 {
+            controlChannelType::CSOUND_CONTROL_CHANNEL => ValueKind::Control,
+            controlChannelType::CSOUND_STRING_CHANNEL => ValueKind::String,
+            controlChannelType::CSOUND_AUDIO_CHANNEL => ValueKind::Audio,
+            controlChannelType::CSOUND_PVS_CHANNEL => ValueKind::Pvs,
+            _ => ValueKind::Unknown,
+        }
+    }
+
+    // Length of the frame, only non sliding frames with N + 2 floats are supported
+    fn pvs_frame_len(fsig: &PvsDat) -> Option<usize> {
+        let len = fsig.N.max(0) as usize + 2;
+        if fsig.sliding != 0
+            || fsig.frame.auxp.is_null()
+            || fsig.frame.size < len * ::std::mem::size_of::<f32>()
+        {
+            return None;
+        }
+        Some(len)
+    }
+
     pub extern "C" fn inputChannelCallback(
         csound: *mut raw::CSOUND,
         channelName: *const c_char,
         channelValuePtr: *mut c_void,
-        _channelType: *const c_void,
+        channelType: *const c_void,
     ) {
-        catch(|| unsafe {
+        catch(csound, || unsafe {
             let name = (CStr::from_ptr(channelName)).to_str();
             if name.is_err() {
                 return;
@@ -509,21 +639,49 @@ pub mod Trampoline {
                 return;
             };
 
-            match result {
-                ChannelData::CS_CONTROL_CHANNEL(data) => {
+            // The data is only written if it matches the opcode's argument type
+            match (value_kind(csound, channelName, channelType), result) {
+                (ValueKind::Control, ChannelData::CS_CONTROL_CHANNEL(data))
+                | (ValueKind::Unknown, ChannelData::CS_CONTROL_CHANNEL(data)) => {
                     *(channelValuePtr as *mut f64) = data;
                 }
 
-                ChannelData::CS_STRING_CHANNEL(s) => {
+                (ValueKind::String, ChannelData::CS_STRING_CHANNEL(s))
+                | (ValueKind::Unknown, ChannelData::CS_STRING_CHANNEL(s)) => {
                     let len = s.len();
                     let c_str = CString::new(s);
-                    if raw::csoundGetChannelDatasize(csound, channelName) as usize <= len {
+                    // The string and its nul terminator must fit in the channel
+                    if len < raw::csoundGetChannelDatasize(csound, channelName) as usize {
                         if let Ok(ptr) = c_str {
-                            memcpy(channelValuePtr, ptr.as_ptr() as *mut c_void, len);
+                            memcpy(channelValuePtr, ptr.as_ptr() as *mut c_void, len + 1);
                         }
                     }
                 }
 
+                (ValueKind::Audio, ChannelData::CS_AUDIO_CHANNEL(samples)) => {
+                    let ksmps = raw::csoundGetKsmps(csound) as usize;
+                    let audio = slice::from_raw_parts_mut(channelValuePtr as *mut f64, ksmps);
+                    let len = samples.len().min(ksmps);
+                    audio[..len].copy_from_slice(&samples[..len]);
+                }
+
+                (ValueKind::Pvs, ChannelData::CS_PVS_CHANNEL(pvs)) => {
+                    let fsig = &mut *(channelValuePtr as *mut PvsDat);
+                    if fsig.N as u32 != pvs.N {
+                        return;
+                    }
+                    if let Some(len) = pvs_frame_len(fsig) {
+                        let frame = slice::from_raw_parts_mut(fsig.frame.auxp as *mut f32, len);
+                        let len = len.min(pvs.frame.len());
+                        frame[..len].copy_from_slice(&pvs.frame[..len]);
+                        fsig.overlap = pvs.overlap as _;
+                        fsig.winsize = pvs.winsize as _;
+                        fsig.wintype = pvs.wintype as _;
+                        fsig.format = pvs.format as _;
+                        fsig.framecount = pvs.framecount;
+                    }
+                }
+
                 _ => {}
             }
         });
@@ -533,18 +691,14 @@ pub mod Trampoline {
         csound: *mut raw::CSOUND,
         channelName: *const c_char,
         channelValuePtr: *mut c_void,
-        _channelType: *const c_void,
+        channelType: *const c_void,
     ) {
-        catch(|| unsafe {
+        catch(csound, || unsafe {
             let name = (CStr::from_ptr(channelName)).to_str();
             if name.is_err() {
                 return;
             }
             let name = name.unwrap();
-            let mut ptr = ::std::ptr::null_mut();
-            let ptr: *mut *mut f64 = &mut ptr as *mut *mut _;
-            let channel_type = raw::csoundGetChannelPtr(csound, ptr, channelName, 0);
-            let channel_type = channel_type & controlChannelType::CSOUND_CHANNEL_TYPE_MASK as i32;
 
             let fun = if let Some(fun) = (*(raw::csoundGetHostData(csound) as *mut CallbackHandler))
                 .callbacks
@@ -556,14 +710,14 @@ pub mod Trampoline {
                 return;
             };
 
-            match channel_type as u32 {
-                controlChannelType::CSOUND_CONTROL_CHANNEL => {
+            match value_kind(csound, channelName, channelType) {
+                ValueKind::Control => {
                     let value = *(channelValuePtr as *mut f64);
                     let data = ChannelData::CS_CONTROL_CHANNEL(value);
                     fun(name, data);
                 }
 
-                controlChannelType::CSOUND_STRING_CHANNEL => {
+                ValueKind::String => {
                     let data = ChannelData::CS_STRING_CHANNEL(
                         ptr_to_string(channelValuePtr as *const c_char)
                             .unwrap_or_else(|| "".to_owned()),
@@ -571,7 +725,32 @@ pub mod Trampoline {
                     fun(name, data);
                 }
 
-                _ => {}
+                ValueKind::Audio => {
+                    let ksmps = raw::csoundGetKsmps(csound) as usize;
+                    let audio = slice::from_raw_parts(channelValuePtr as *const f64, ksmps);
+                    fun(name, ChannelData::CS_AUDIO_CHANNEL(audio.to_vec()));
+                }
+
+                ValueKind::Pvs => {
+                    let fsig = &*(channelValuePtr as *const PvsDat);
+                    if let Some(len) = pvs_frame_len(fsig) {
+                        let frame = slice::from_raw_parts(fsig.frame.auxp as *const f32, len);
+                        let data = ChannelData::CS_PVS_CHANNEL(PvsDataExt {
+                            N: fsig.N as u32,
+                            sliding: fsig.sliding as u32,
+                            NB: fsig.NB,
+                            overlap: fsig.overlap as u32,
+                            winsize: fsig.winsize as u32,
+                            wintype: fsig.wintype as u32,
+                            format: fsig.format as u32,
+                            framecount: fsig.framecount,
+                            frame: frame.to_vec(),
+                        });
+                        fun(name, data);
+                    }
+                }
+
+                ValueKind::Unknown => {}
             }
         });
     }
@@ -584,7 +763,7 @@ pub mod Trampoline {
         _userData: *mut *mut c_void,
         devName: *const c_char,
     ) -> c_int {
-        catch(|| unsafe {
+        catch(csound, || unsafe {
             let name = match CStr::from_ptr(devName).to_str() {
                 Ok(s) => s,
                 _ => return CSOUND_STATUS::CSOUND_ERROR,
@@ -598,7 +777,7 @@ pub mod Trampoline {
             }
             CSOUND_STATUS::CSOUND_SUCCESS
         })
-        .unwrap()
+        .unwrap_or(CSOUND_STATUS::CSOUND_ERROR)
     }
 
     // Sets callback for opening real time MIDI output.
@@ -607,7 +786,7 @@ pub mod Trampoline {
         _userData: *mut *mut c_void,
         devName: *const c_char,
     ) -> c_int {
-        catch(|| unsafe {
+        catch(csound, || unsafe {
             let name = match CStr::from_ptr(devName).to_str() {
                 Ok(s) => s,
                 _ => return CSOUND_STATUS::CSOUND_ERROR,
@@ -621,7 +800,7 @@ pub mod Trampoline {
             }
             CSOUND_STATUS::CSOUND_SUCCESS
         })
-        .unwrap()
+        .unwrap_or(CSOUND_STATUS::CSOUND_ERROR)
     }
 
     // Sets callback for reading from real time MIDI input.
@@ -631,7 +810,7 @@ pub mod Trampoline {
         buf: *mut c_uchar,
         nbytes: c_int,
     ) -> c_int {
-        catch(|| unsafe {
+        catch(csound, || unsafe {
             let mut out = slice::from_raw_parts_mut(buf, nbytes as usize);
             if let Some(fun) = (*(raw::csoundGetHostData(csound) as *mut CallbackHandler))
                 .callbacks
@@ -642,7 +821,7 @@ pub mod Trampoline {
             }
             -1
         })
-        .unwrap()
+        .unwrap_or(0)
     }
 
     // Sets callback for writing to real time MIDI output.
@@ -653,7 +832,7 @@ pub mod Trampoline {
         buf: *const u8,
         nbytes: c_int,
     ) -> c_int {
-        catch(|| unsafe {
+        catch(csound, || unsafe {
             let buffer = slice::from_raw_parts(buf, nbytes as usize);
             if let Some(fun) = (*(raw::csoundGetHostData(csound) as *mut CallbackHandler))
                 .callbacks
@@ -664,7 +843,7 @@ pub mod Trampoline {
             }
             -1
         })
-        .unwrap()
+        .unwrap_or(0)
     }
 
     //Sets callback for closing real time MIDI input.
@@ -672,7 +851,7 @@ pub mod Trampoline {
         csound: *mut raw::CSOUND,
         _userData: *mut c_void,
     ) -> c_int {
-        catch(|| unsafe {
+        catch(csound, || unsafe {
             if let Some(fun) = (*(raw::csoundGetHostData(csound) as *mut CallbackHandler))
                 .callbacks
                 .midi_in_close_cb
@@ -682,7 +861,7 @@ pub mod Trampoline {
             }
             CSOUND_STATUS::CSOUND_SUCCESS
         })
-        .unwrap()
+        .unwrap_or(CSOUND_STATUS::CSOUND_ERROR)
     }
 
     // Sets callback for closing real time MIDI output.
@@ -690,7 +869,7 @@ pub mod Trampoline {
         csound: *mut raw::CSOUND,
         _userData: *mut c_void,
     ) -> c_int {
-        catch(|| unsafe {
+        catch(csound, || unsafe {
             if let Some(fun) = (*(raw::csoundGetHostData(csound) as *mut CallbackHandler))
                 .callbacks
                 .midi_out_close_cb
@@ -700,11 +879,11 @@ pub mod Trampoline {
             }
             CSOUND_STATUS::CSOUND_SUCCESS
         })
-        .unwrap()
+        .unwrap_or(CSOUND_STATUS::CSOUND_ERROR)
     }
 
     pub extern "C" fn yieldCallback(csound: *mut raw::CSOUND) -> c_int {
-        catch(|| unsafe {
+        catch(csound, || unsafe {
             if let Some(fun) = (*(raw::csoundGetHostData(csound) as *mut CallbackHandler))
                 .callbacks
                 .yield_cb
@@ -714,7 +893,7 @@ pub mod Trampoline {
             }
             0
         })
-        .unwrap()
+        .unwrap_or(0)
     }
 }
 
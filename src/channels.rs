@@ -1,8 +1,18 @@
+use std::ffi::CStr;
 use std::marker::PhantomData;
+use std::mem;
 use std::ops::{Deref, DerefMut};
 use std::slice;
+use std::sync::atomic::{AtomicU64, Ordering};
 
-use crate::enums::{AudioChannel, ControlChannel, ControlChannelType, StrChannel};
+use libc::{c_char, c_int, c_void};
+
+use crate::csound::Inner;
+use crate::enums::{
+    AudioChannel, ControlChannel, ControlChannelType, FsigChannel, PvsFormat, StrChannel,
+    VarChannel, WindowType,
+};
+use crate::error::{Error, Result};
 
 /// Indicates the channel behaivor.
 #[derive(Debug, PartialEq, Clone)]
@@ -81,6 +91,128 @@ pub struct ChannelInfo {
     pub hints: ChannelHints,
 }
 
+impl ChannelInfo {
+    /// # Returns
+    /// The channel data type, one of the `ControlChannelType` CSOUND_*_CHANNEL values.
+    pub fn kind(&self) -> ControlChannelType {
+        ControlChannelType::from_bits_truncate(self.type_ as u32)
+            & ControlChannelType::CSOUND_CHANNEL_TYPE_MASK
+    }
+
+    /// # Returns
+    /// true if the channel is written by the host.
+    pub fn is_input(&self) -> bool {
+        ControlChannelType::from_bits_truncate(self.type_ as u32)
+            .contains(ControlChannelType::CSOUND_INPUT_CHANNEL)
+    }
+
+    /// # Returns
+    /// true if the channel is read by the host.
+    pub fn is_output(&self) -> bool {
+        ControlChannelType::from_bits_truncate(self.type_ as u32)
+            .contains(ControlChannelType::CSOUND_OUTPUT_CHANNEL)
+    }
+}
+
+// Layout of csound's ARRAYDAT, csound-sys does not export it.
+// Variable channels holding arrays point to this struct.
+#[repr(C)]
+pub(crate) struct ArrayDat {
+    dimensions: c_int,
+    sizes: *mut c_int,
+    array_member_size: c_int,
+    array_type: *mut c_void,
+    data: *mut f64,
+    allocated: usize,
+}
+
+impl ArrayDat {
+    // Only i-rate and k-rate arrays hold one f64 per member, the members of a-rate arrays
+    // are ksmps samples and other arrays hold pointers, e.g the STRINGDATs of S[] arrays.
+    unsafe fn check_numeric(&self) -> Result<()> {
+        // The first field of csound's CS_TYPE is its name
+        let type_name = self.array_type as *const *const c_char;
+        let numeric = !type_name.is_null()
+            && !(*type_name).is_null()
+            && matches!(CStr::from_ptr(*type_name).to_bytes(), b"i" | b"k")
+            && self.array_member_size as usize == mem::size_of::<f64>();
+        if numeric {
+            Ok(())
+        } else {
+            Err(Error::InvalidArgument(
+                "only i-rate and k-rate arrays can be accessed through variable channels",
+            ))
+        }
+    }
+
+    // Number of f64 values the array holds.
+    unsafe fn len(&self) -> usize {
+        if self.data.is_null() || self.sizes.is_null() || self.dimensions <= 0 {
+            return 0;
+        }
+        let members = slice::from_raw_parts(self.sizes, self.dimensions as usize)
+            .iter()
+            .map(|&size| size.max(0) as usize)
+            .product::<usize>();
+        members * self.array_member_size.max(0) as usize / mem::size_of::<f64>()
+    }
+
+    unsafe fn sizes(&self) -> &[c_int] {
+        if self.sizes.is_null() || self.dimensions <= 0 {
+            return &[];
+        }
+        slice::from_raw_parts(self.sizes, self.dimensions as usize)
+    }
+}
+
+/// View of an array stored in a variable channel, see [`VarChannel`](enum.VarChannel.html).
+///
+/// Only i-rate and k-rate arrays can be viewed, reading other arrays returns an error.
+/// # Example
+/// ```no_run
+/// use csound::{Csound, VarChannel};
+///
+/// let mut cs = Csound::new();
+/// cs.compile_csd("partials.csd").unwrap();
+/// cs.start().unwrap();
+/// let partials = cs.get_output_channel::<VarChannel>("partials").unwrap();
+/// while !cs.perform_ksmps() {
+///     let array = partials.read().unwrap();
+///     println!("{:?} array, first value {:?}", array.sizes, array.get(&[0, 0]));
+/// }
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct ArrayView<'c> {
+    /// The array size in each dimension.
+    pub sizes: &'c [i32],
+    /// The array values in row-major order.
+    pub data: &'c [f64],
+}
+
+impl<'c> ArrayView<'c> {
+    /// # Returns
+    /// The number of dimensions of the array.
+    pub fn dimensions(&self) -> usize {
+        self.sizes.len()
+    }
+
+    /// # Returns
+    /// The value at *index*, one index per dimension, or None if it is out of bounds.
+    pub fn get(&self, index: &[usize]) -> Option<f64> {
+        if index.len() != self.sizes.len() {
+            return None;
+        }
+        let mut offset = 0;
+        for (&i, &size) in index.iter().zip(self.sizes) {
+            if i >= size as usize {
+                return None;
+            }
+            offset = offset * size as usize + i;
+        }
+        self.data.get(offset).copied()
+    }
+}
+
 /// Holds pvs data info of a pvs channel.
 ///
 /// To be used with [pvsin](http://www.csounds.com/manual/html/pvsin.html),
@@ -88,7 +220,22 @@ pub struct ChannelInfo {
 /// [`Csound::get_pvs_channel`](struct.Csound.html#method.get_pvs_channel) and [`Csound::set_pvs_channel`](struct.Csound.html#method.set_pvs_channel)
 /// methods.
 ///
-#[derive(Debug, Clone)]
+/// A frame holds `N/2 + 1` bins, each bin is a pair of values whose meaning depends on the
+/// [`format`](struct.PvsDataExt.html#method.format), so the *frame* buffer has `N + 2` elements.
+/// # Example
+/// ```
+/// use csound::{PvsDataExt, PvsFormat};
+///
+/// let mut pvs = PvsDataExt::new(1024);
+/// assert_eq!(pvs.frame.len(), 1026);
+/// assert_eq!(pvs.format(), PvsFormat::PVS_AMP_FREQ);
+/// for (amp, freq) in pvs.bins_mut() {
+///     *amp = 0.5;
+///     *freq = 440.0;
+/// }
+/// assert_eq!(pvs.bins().count(), 513);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
 pub struct PvsDataExt {
     pub N: u32,
     pub sliding: u32,
@@ -105,39 +252,282 @@ impl PvsDataExt {
     /// Creates a new pvs data channel struct.
     ///
     /// # Arguments
-    /// * `winsize` The number of elements in the pvs window and also it is the
-    /// number of samples in the frame buffer.
-    pub fn new(winsize: u32) -> PvsDataExt {
+    /// * `N` The FFT size of the analysis, the frame buffer will hold `N + 2` elements.
+    ///
+    /// The window size is set to `N`, the overlap to `N/4`, the window to
+    /// [`PVS_WIN_HANN`](enum.WindowType.html) and the format to [`PVS_AMP_FREQ`](enum.PvsFormat.html).
+    pub fn new(N: u32) -> PvsDataExt {
+        PvsDataExt::with_params(
+            N,
+            N / 4,
+            N,
+            WindowType::PVS_WIN_HANN,
+            PvsFormat::PVS_AMP_FREQ,
+        )
+    }
+
+    /// Creates a new pvs data channel struct with the analysis parameters.
+    ///
+    /// # Arguments
+    /// * `N` The FFT size of the analysis.
+    /// * `overlap` The analysis hop size.
+    /// * `winsize` The analysis window size.
+    /// * `wintype` The analysis window type.
+    /// * `format` The frame data format.
+    pub fn with_params(
+        N: u32,
+        overlap: u32,
+        winsize: u32,
+        wintype: WindowType,
+        format: PvsFormat,
+    ) -> PvsDataExt {
         PvsDataExt {
-            N: winsize,
+            N,
             sliding: 0,
             NB: 0,
-            overlap: 0,
+            overlap,
             winsize,
-            wintype: 0,
-            format: 0,
+            wintype: wintype.to_u32(),
+            format: format.to_u32(),
             framecount: 0,
-            frame: vec![0.0; winsize as usize],
+            frame: vec![0.0; N as usize + 2],
         }
     }
+
+    /// # Returns
+    /// The frame data format.
+    pub fn format(&self) -> PvsFormat {
+        PvsFormat::from(self.format)
+    }
+
+    /// Sets the frame data format.
+    pub fn set_format(&mut self, format: PvsFormat) {
+        self.format = format.to_u32();
+    }
+
+    /// # Returns
+    /// The analysis window type.
+    pub fn window_type(&self) -> WindowType {
+        WindowType::from(self.wintype)
+    }
+
+    /// Sets the analysis window type.
+    pub fn set_window_type(&mut self, wintype: WindowType) {
+        self.wintype = wintype.to_u32();
+    }
+
+    /// # Returns
+    /// The number of elements a frame needs, `N + 2`.
+    pub fn frame_len(&self) -> usize {
+        self.N as usize + 2
+    }
+
+    /// # Returns
+    /// The number of bins in a frame, `N/2 + 1`.
+    pub fn bin_count(&self) -> usize {
+        self.N as usize / 2 + 1
+    }
+
+    /// # Returns
+    /// An iterator over the frame bins, e.g (amplitude, frequency) pairs
+    /// for frames in the [`PVS_AMP_FREQ`](enum.PvsFormat.html) format.
+    pub fn bins(&self) -> impl Iterator<Item = (f32, f32)> + '_ {
+        let len = self.frame_len().min(self.frame.len());
+        self.frame[..len]
+            .chunks_exact(2)
+            .map(|bin| (bin[0], bin[1]))
+    }
+
+    /// # Returns
+    /// An iterator over mutable references to the frame bins.
+    pub fn bins_mut(&mut self) -> impl Iterator<Item = (&mut f32, &mut f32)> + '_ {
+        let len = self.frame_len().min(self.frame.len());
+        self.frame[..len].chunks_exact_mut(2).map(|bin| {
+            let (first, second) = bin.split_at_mut(1);
+            (&mut first[0], &mut second[0])
+        })
+    }
 }
 
 /// Struct represents a csound input channel object.
+///
+/// The `read`/`write` functions and the Deref implementations access the channel memory
+/// without any synchronization, so the channel can not be sent to other threads.
+/// Use [`InputChannel::into_shared`](struct.InputChannel.html#method.into_shared) to get a
+/// [`SharedInputChannel`](struct.SharedInputChannel.html), which only exposes the synchronized functions,
+/// if the channel is accessed from another thread while csound performs.
 #[derive(Debug)]
 pub struct InputChannel<'a, T> {
     pub(crate) ptr: *mut f64,
     pub(crate) len: usize,
+    pub(crate) lock: *mut c_int,
+    pub(crate) name: String,
+    pub(crate) engine: &'a Inner,
     pub(crate) phantom: PhantomData<&'a mut T>,
 }
 
 /// Struct represents a csound output channel object.
+///
+/// See [`InputChannel`](struct.InputChannel.html) about the thread-safety of the channel access.
 #[derive(Debug)]
 pub struct OutputChannel<'a, T> {
     pub(crate) ptr: *mut f64,
     pub(crate) len: usize,
+    pub(crate) lock: *mut c_int,
     pub(crate) phantom: PhantomData<&'a T>,
 }
 
+impl<'a, T> InputChannel<'a, T> {
+    /// Converts this channel into a [`SharedInputChannel`](struct.SharedInputChannel.html),
+    /// which can be sent to another thread.
+    pub fn into_shared(self) -> SharedInputChannel<'a, T> {
+        SharedInputChannel(self)
+    }
+}
+
+impl<'a, T> OutputChannel<'a, T> {
+    /// Converts this channel into a [`SharedOutputChannel`](struct.SharedOutputChannel.html),
+    /// which can be sent to another thread.
+    pub fn into_shared(self) -> SharedOutputChannel<'a, T> {
+        SharedOutputChannel(self)
+    }
+}
+
+/// An input channel which can be sent to another thread.
+///
+/// It is created with [`InputChannel::into_shared`](struct.InputChannel.html#method.into_shared)
+/// and it only exposes the functions which synchronize with csound, the atomic `load` and `store`
+/// of control channels and the `lock` of audio, string and variable channels,
+/// so it can be used from a thread while another thread performs.
+/// # Example
+/// ```no_run
+/// use csound::{ControlChannel, Csound};
+/// use std::thread;
+///
+/// let mut csound = Csound::new();
+/// csound.compile_csd("some.csd").unwrap();
+/// csound.start().unwrap();
+/// let gain = csound
+///     .get_input_channel::<ControlChannel>("gain")
+///     .unwrap()
+///     .into_shared();
+/// thread::scope(|s| {
+///     s.spawn(move || gain.store(0.5));
+///     while !csound.perform_ksmps() {}
+/// });
+/// ```
+/// The channels themselves can not be sent, since their `read` and `write` functions are not synchronized:
+/// ```compile_fail
+/// use csound::{ControlChannel, Csound};
+/// use std::thread;
+///
+/// let csound = Csound::new();
+/// let gain = csound.get_input_channel::<ControlChannel>("gain").unwrap();
+/// thread::scope(|s| {
+///     s.spawn(|| gain.write(0.5));
+///     while !csound.perform_ksmps() {}
+/// });
+/// ```
+#[derive(Debug)]
+pub struct SharedInputChannel<'a, T>(InputChannel<'a, T>);
+
+/// An output channel which can be sent to another thread.
+///
+/// See [`SharedInputChannel`](struct.SharedInputChannel.html).
+#[derive(Debug)]
+pub struct SharedOutputChannel<'a, T>(OutputChannel<'a, T>);
+
+// The channel memory is owned by csound and it is valid as long as the Csound object is borrowed,
+// and every function of the shared channels synchronizes with csound.
+unsafe impl<'a, T> Send for SharedInputChannel<'a, T> {}
+unsafe impl<'a, T> Send for SharedOutputChannel<'a, T> {}
+
+impl<'a> SharedOutputChannel<'a, ControlChannel> {
+    /// See [`OutputChannel::load`](struct.OutputChannel.html#method.load).
+    pub fn load(&self) -> f64 {
+        self.0.load()
+    }
+}
+
+impl<'a> SharedInputChannel<'a, ControlChannel> {
+    /// See [`InputChannel::load`](struct.InputChannel.html#method.load).
+    pub fn load(&self) -> f64 {
+        self.0.load()
+    }
+
+    /// See [`InputChannel::store`](struct.InputChannel.html#method.store).
+    pub fn store(&self, value: f64) {
+        self.0.store(value)
+    }
+}
+
+/// Guard which holds the csound's spinlock of a channel, giving read access to its content.
+///
+/// It is returned by [`OutputChannel::lock`](struct.OutputChannel.html#method.lock),
+/// the lock is released when the guard is dropped.
+#[derive(Debug)]
+pub struct ChannelReadGuard<'c, T: ?Sized> {
+    lock: *mut c_int,
+    data: &'c T,
+}
+
+/// Guard which holds the csound's spinlock of a channel, giving write access to its content.
+///
+/// It is returned by [`InputChannel::lock`](struct.InputChannel.html#method.lock),
+/// the lock is released when the guard is dropped.
+#[derive(Debug)]
+pub struct ChannelWriteGuard<'c, T: ?Sized> {
+    lock: *mut c_int,
+    data: &'c mut T,
+}
+
+pub(crate) unsafe fn spin_lock(lock: *mut c_int) {
+    // csound returns a null lock for channels which it does not know about
+    if !lock.is_null() {
+        csound_sys::csoundSpinLock(lock as *mut _);
+    }
+}
+
+pub(crate) unsafe fn spin_unlock(lock: *mut c_int) {
+    if !lock.is_null() {
+        csound_sys::csoundSpinUnLock(lock as *mut _);
+    }
+}
+
+impl<'c, T: ?Sized> Deref for ChannelReadGuard<'c, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.data
+    }
+}
+
+impl<'c, T: ?Sized> Deref for ChannelWriteGuard<'c, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.data
+    }
+}
+
+impl<'c, T: ?Sized> DerefMut for ChannelWriteGuard<'c, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.data
+    }
+}
+
+impl<'c, T: ?Sized> Drop for ChannelReadGuard<'c, T> {
+    fn drop(&mut self) {
+        unsafe { spin_unlock(self.lock) }
+    }
+}
+
+impl<'c, T: ?Sized> Drop for ChannelWriteGuard<'c, T> {
+    fn drop(&mut self) {
+        unsafe { spin_unlock(self.lock) }
+    }
+}
+
 pub trait IsChannel {
     fn c_type() -> ControlChannelType;
 }
@@ -157,6 +547,16 @@ impl IsChannel for StrChannel {
         ControlChannelType::CSOUND_STRING_CHANNEL
     }
 }
+impl IsChannel for FsigChannel {
+    fn c_type() -> ControlChannelType {
+        ControlChannelType::CSOUND_PVS_CHANNEL
+    }
+}
+impl IsChannel for VarChannel {
+    fn c_type() -> ControlChannelType {
+        ControlChannelType::CSOUND_VAR_CHANNEL
+    }
+}
 
 // CONTROL CHANNEL
 impl<'a> OutputChannel<'a, ControlChannel> {
@@ -167,15 +567,44 @@ impl<'a> OutputChannel<'a, ControlChannel> {
     pub fn read(&'a self) -> f64 {
         unsafe { *self.ptr }
     }
+
+    /// Atomically reads the control channel's value.
+    ///
+    /// Unlike [`OutputChannel::read`](struct.OutputChannel.html#method.read)
+    /// this is safe to call while another thread is performing.
+    pub fn load(&self) -> f64 {
+        unsafe { f64::from_bits((*(self.ptr as *const AtomicU64)).load(Ordering::SeqCst)) }
+    }
 }
 
 impl<'a> InputChannel<'a, ControlChannel> {
     /// Writes data to csound's control channel
     pub fn write(&self, inp: f64) {
         unsafe {
+            self.engine
+                .recorder
+                .record(self.engine.csound, &self.name, inp);
             *self.ptr = inp;
         }
     }
+
+    /// Atomically reads the control channel's value.
+    pub fn load(&self) -> f64 {
+        unsafe { f64::from_bits((*(self.ptr as *const AtomicU64)).load(Ordering::SeqCst)) }
+    }
+
+    /// Atomically writes *value* into the control channel.
+    ///
+    /// Unlike [`InputChannel::write`](struct.InputChannel.html#method.write)
+    /// this is safe to call while another thread is performing.
+    pub fn store(&self, value: f64) {
+        unsafe {
+            self.engine
+                .recorder
+                .record(self.engine.csound, &self.name, value);
+            (*(self.ptr as *const AtomicU64)).store(value.to_bits(), Ordering::SeqCst)
+        }
+    }
 }
 
 // AUDIO CHANNEL
@@ -196,6 +625,10 @@ impl<'a> InputChannel<'a, AudioChannel> {
     /// A slice of ksmps audio samples to be copied into the channel's buffer
     /// If this slice is longer than the channel's buffer, only
     /// Channel's size elements would be copied
+    ///
+    /// The samples are copied without synchronization, use
+    /// [`Csound::stream_to_audio_channel`](struct.Csound.html#method.stream_to_audio_channel)
+    /// to stream audio into the channel from another thread.
     pub fn write(&self, inp: &[f64]) {
         let mut len = inp.len();
         let size = self.len;
@@ -238,6 +671,222 @@ impl<'a> InputChannel<'a, StrChannel> {
     }
 }
 
+// PVS CHANNEL
+impl<'a> OutputChannel<'a, FsigChannel> {
+    /// Copies the channel's last frame into *pvs_data*, holding the channel lock.
+    ///
+    /// # Returns
+    /// [`Error::InvalidArgument`](enum.Error.html#variant.InvalidArgument) if the analysis size
+    /// doesn't match the channel's one, or [`Error::BufferTooSmall`](enum.Error.html#variant.BufferTooSmall)
+    /// if the frame buffer can not hold `N + 2` elements.
+    pub fn read(&self, pvs_data: &mut PvsDataExt) -> Result<()> {
+        unsafe {
+            spin_lock(self.lock);
+            let result = copy_from_fsig(&*(self.ptr as *const csound_sys::PVSDATEXT), pvs_data);
+            spin_unlock(self.lock);
+            result
+        }
+    }
+}
+
+impl<'a> InputChannel<'a, FsigChannel> {
+    /// Copies *pvs_data* into the channel, holding the channel lock.
+    ///
+    /// The channel frame is allocated by the [*pvsin*](http://www.csounds.com/manual/html/pvsin.html) opcode,
+    /// use [`Csound::set_pvs_channel`](struct.Csound.html#method.set_pvs_channel) to write a channel
+    /// which is not read by *pvsin* yet.
+    /// # Returns
+    /// [`Error::InvalidArgument`](enum.Error.html#variant.InvalidArgument) if the analysis size
+    /// doesn't match the channel's one, or [`Error::BufferTooSmall`](enum.Error.html#variant.BufferTooSmall)
+    /// if the channel frame is not allocated yet or *pvs_data* has not `N + 2` elements.
+    pub fn write(&self, pvs_data: &PvsDataExt) -> Result<()> {
+        unsafe {
+            spin_lock(self.lock);
+            let result = copy_to_fsig(pvs_data, &mut *(self.ptr as *mut csound_sys::PVSDATEXT));
+            spin_unlock(self.lock);
+            result
+        }
+    }
+
+    /// Copies the channel's current frame into *pvs_data*, holding the channel lock.
+    pub fn read(&self, pvs_data: &mut PvsDataExt) -> Result<()> {
+        unsafe {
+            spin_lock(self.lock);
+            let result = copy_from_fsig(&*(self.ptr as *const csound_sys::PVSDATEXT), pvs_data);
+            spin_unlock(self.lock);
+            result
+        }
+    }
+}
+
+unsafe fn copy_from_fsig(fsig: &csound_sys::PVSDATEXT, pvs_data: &mut PvsDataExt) -> Result<()> {
+    let N = fsig.N.max(0) as usize;
+    if N != pvs_data.N as usize {
+        return Err(Error::InvalidArgument(
+            "the analysis size doesn't match the channel's one",
+        ));
+    }
+    if pvs_data.frame.len() < N + 2 {
+        return Err(Error::BufferTooSmall {
+            required: N + 2,
+            available: pvs_data.frame.len(),
+        });
+    }
+    if !fsig.frame.is_null() {
+        std::ptr::copy(fsig.frame, pvs_data.frame.as_mut_ptr(), N + 2);
+    }
+    pvs_data.sliding = fsig.sliding as _;
+    pvs_data.NB = fsig.NB as _;
+    pvs_data.overlap = fsig.overlap as _;
+    pvs_data.winsize = fsig.winsize as _;
+    pvs_data.wintype = fsig.wintype as _;
+    pvs_data.format = fsig.format as _;
+    pvs_data.framecount = fsig.framecount as _;
+    Ok(())
+}
+
+unsafe fn copy_to_fsig(pvs_data: &PvsDataExt, fsig: &mut csound_sys::PVSDATEXT) -> Result<()> {
+    // pvsin reads frames of the size it allocated
+    if !fsig.frame.is_null() && fsig.N.max(0) as usize != pvs_data.N as usize {
+        return Err(Error::InvalidArgument(
+            "the analysis size doesn't match the channel's one",
+        ));
+    }
+    let available = if fsig.frame.is_null() {
+        0
+    } else {
+        fsig.N.max(0) as usize + 2
+    };
+    let required = pvs_data.frame_len();
+    if available < required || pvs_data.frame.len() < required {
+        return Err(Error::BufferTooSmall {
+            required,
+            available: available.min(pvs_data.frame.len()),
+        });
+    }
+    std::ptr::copy(pvs_data.frame.as_ptr(), fsig.frame, required);
+    fsig.sliding = pvs_data.sliding as _;
+    fsig.NB = pvs_data.NB as _;
+    fsig.overlap = pvs_data.overlap as _;
+    fsig.winsize = pvs_data.winsize as _;
+    fsig.wintype = pvs_data.wintype as _;
+    fsig.format = pvs_data.format as _;
+    fsig.framecount = pvs_data.framecount as _;
+    Ok(())
+}
+
+// VAR CHANNEL
+impl<'a> OutputChannel<'a, VarChannel> {
+    /// Reads the array stored in a csound's variable channel
+    ///
+    /// # Returns
+    /// A view of the array values and its dimensions, or an error
+    /// if the channel holds an array which is not i-rate or k-rate.
+    pub fn read(&self) -> Result<ArrayView<'_>> {
+        unsafe { array_view(self.ptr as *const ArrayDat) }
+    }
+
+    /// Acquires the csound's spinlock of this channel, giving access to the array values.
+    ///
+    /// The guard should be released quickly because the performance thread
+    /// spins while waiting for it.
+    /// # Returns
+    /// An error if the channel holds an array which is not i-rate or k-rate.
+    pub fn lock(&mut self) -> Result<ChannelReadGuard<'_, [f64]>> {
+        unsafe {
+            spin_lock(self.lock);
+            match array_view(self.ptr as *const ArrayDat) {
+                Ok(view) => Ok(ChannelReadGuard {
+                    lock: self.lock,
+                    data: view.data,
+                }),
+                Err(e) => {
+                    spin_unlock(self.lock);
+                    Err(e)
+                }
+            }
+        }
+    }
+}
+
+impl<'a> InputChannel<'a, VarChannel> {
+    /// Reads the array stored in a csound's variable channel
+    ///
+    /// # Returns
+    /// A view of the array values and its dimensions, or an error
+    /// if the channel holds an array which is not i-rate or k-rate.
+    pub fn read(&self) -> Result<ArrayView<'_>> {
+        unsafe { array_view(self.ptr as *const ArrayDat) }
+    }
+
+    /// Writes values into the array stored in a variable channel
+    ///
+    /// # Arguments
+    /// A slice of values in row-major order to be copied into the array.
+    /// If this slice is longer than the array, only
+    /// array's size elements would be copied
+    /// # Returns
+    /// An error if the channel holds an array which is not i-rate or k-rate.
+    pub fn write(&self, inp: &[f64]) -> Result<()> {
+        unsafe {
+            let array = match numeric_array(self.ptr as *const ArrayDat)? {
+                Some(array) => array,
+                None => return Ok(()),
+            };
+            let len = array.len().min(inp.len());
+            std::ptr::copy(inp.as_ptr(), array.data, len);
+            Ok(())
+        }
+    }
+
+    /// Acquires the csound's spinlock of this channel, giving access to the array values.
+    ///
+    /// The guard should be released quickly because the performance thread
+    /// spins while waiting for it.
+    /// # Returns
+    /// An error if the channel holds an array which is not i-rate or k-rate.
+    pub fn lock(&mut self) -> Result<ChannelWriteGuard<'_, [f64]>> {
+        unsafe {
+            spin_lock(self.lock);
+            let data = match numeric_array(self.ptr as *const ArrayDat) {
+                Ok(Some(array)) => slice::from_raw_parts_mut(array.data, array.len()),
+                Ok(None) => &mut [],
+                Err(e) => {
+                    spin_unlock(self.lock);
+                    return Err(e);
+                }
+            };
+            Ok(ChannelWriteGuard {
+                lock: self.lock,
+                data,
+            })
+        }
+    }
+}
+
+// The array of a variable channel, None if csound did not allocate it yet.
+unsafe fn numeric_array<'c>(array: *const ArrayDat) -> Result<Option<&'c ArrayDat>> {
+    if array.is_null() || (*array).data.is_null() {
+        return Ok(None);
+    }
+    let array = &*array;
+    array.check_numeric()?;
+    Ok(Some(array))
+}
+
+unsafe fn array_view<'c>(array: *const ArrayDat) -> Result<ArrayView<'c>> {
+    Ok(match numeric_array(array)? {
+        Some(array) => ArrayView {
+            sizes: array.sizes(),
+            data: slice::from_raw_parts(array.data as *const f64, array.len()),
+        },
+        None => ArrayView {
+            sizes: &[],
+            data: &[],
+        },
+    })
+}
+
 impl<'a> AsRef<f64> for OutputChannel<'a, ControlChannel> {
     fn as_ref(&self) -> &f64 {
         unsafe { &*self.ptr }
@@ -320,6 +969,87 @@ macro_rules! impl_deref_mut_for_channel_ptr {
     };
 }
 
+// Internal macro used to generate AudioChannel and StrChannel implementations
+// of the lock functions.
+macro_rules! impl_lock_for_channel_ptr {
+    ($ct:ty, $t:ty) => {
+        impl<'a> OutputChannel<'a, $ct> {
+            /// Acquires the csound's spinlock of this channel.
+            ///
+            /// csound takes the same lock when its opcodes access the channel, so the content
+            /// can not change while the returned guard is alive.
+            /// The guard should be released quickly because the performance thread
+            /// spins while waiting for it.
+            pub fn lock(&mut self) -> ChannelReadGuard<'_, [$t]> {
+                unsafe {
+                    spin_lock(self.lock);
+                    ChannelReadGuard {
+                        lock: self.lock,
+                        data: slice::from_raw_parts(self.ptr as *const $t, self.len),
+                    }
+                }
+            }
+        }
+
+        impl<'a> InputChannel<'a, $ct> {
+            /// Acquires the csound's spinlock of this channel.
+            ///
+            /// csound takes the same lock when its opcodes access the channel, so csound will not
+            /// read the content while the returned guard is alive.
+            /// The guard should be released quickly because the performance thread
+            /// spins while waiting for it.
+            pub fn lock(&mut self) -> ChannelWriteGuard<'_, [$t]> {
+                unsafe {
+                    spin_lock(self.lock);
+                    ChannelWriteGuard {
+                        lock: self.lock,
+                        data: slice::from_raw_parts_mut(self.ptr as *mut $t, self.len),
+                    }
+                }
+            }
+        }
+    };
+}
+
+impl_lock_for_channel_ptr!(AudioChannel, f64);
+impl_lock_for_channel_ptr!(StrChannel, u8);
+
+// Internal macro used to generate the lock functions of the shared channels.
+macro_rules! impl_lock_for_shared_channel {
+    ($ct:ty, $t:ty) => {
+        impl<'a> SharedOutputChannel<'a, $ct> {
+            /// See [`OutputChannel::lock`](struct.OutputChannel.html#method.lock).
+            pub fn lock(&mut self) -> ChannelReadGuard<'_, [$t]> {
+                self.0.lock()
+            }
+        }
+
+        impl<'a> SharedInputChannel<'a, $ct> {
+            /// See [`InputChannel::lock`](struct.InputChannel.html#method.lock).
+            pub fn lock(&mut self) -> ChannelWriteGuard<'_, [$t]> {
+                self.0.lock()
+            }
+        }
+    };
+}
+
+impl_lock_for_shared_channel!(AudioChannel, f64);
+impl_lock_for_shared_channel!(StrChannel, u8);
+
+impl<'a> SharedOutputChannel<'a, VarChannel> {
+    /// See [`OutputChannel::lock`](struct.OutputChannel.html#method.lock).
+    pub fn lock(&mut self) -> Result<ChannelReadGuard<'_, [f64]>> {
+        self.0.lock()
+    }
+}
+
+impl<'a> SharedInputChannel<'a, VarChannel> {
+    /// See [`InputChannel::lock`](struct.InputChannel.html#method.lock).
+    pub fn lock(&mut self) -> Result<ChannelWriteGuard<'_, [f64]>> {
+        self.0.lock()
+    }
+}
+
 impl_deref_for_channel_ptr!(ControlChannel, f64);
 impl_deref_for_channel_ptr!(AudioChannel, [f64]);
 impl_deref_for_channel_ptr!(StrChannel, [u8]);
@@ -0,0 +1,286 @@
+use std::convert::TryFrom;
+use std::ffi::CString;
+use std::ptr;
+use std::slice;
+
+use csound_sys as raw;
+use libc::{c_char, c_int};
+
+use crate::callbacks::Trampoline;
+use crate::error::{Error, Result};
+
+// Layouts of the csound's cscore.h structures and functions, csound-sys does not export them.
+#[repr(C)]
+struct CsHdr {
+    prvblk: *mut CsHdr,
+    nxtblk: *mut CsHdr,
+    type_: i16,
+    size: i16,
+}
+
+#[repr(C)]
+struct Event {
+    h: CsHdr,
+    strarg: *mut c_char,
+    op: c_char,
+    pcnt: i16,
+    p2orig: f64,
+    p3orig: f64,
+    // p[0] is not used, the p-fields start at p[1]
+    p: [f64; 1],
+}
+
+#[repr(C)]
+struct EvList {
+    h: CsHdr,
+    nslots: c_int,
+    nevents: c_int,
+    // e[0] is not used, the events start at e[1]
+    e: [*mut Event; 1],
+}
+
+extern "C" {
+    fn cscoreCreateEvent(csound: *mut raw::CSOUND, pcnt: c_int) -> *mut Event;
+    fn cscoreGetEvent(csound: *mut raw::CSOUND) -> *mut Event;
+    fn cscorePutEvent(csound: *mut raw::CSOUND, event: *mut Event);
+    fn cscorePutString(csound: *mut raw::CSOUND, s: *mut c_char);
+    fn cscoreFreeEvent(csound: *mut raw::CSOUND, event: *mut Event);
+    fn cscoreListGetSection(csound: *mut raw::CSOUND) -> *mut EvList;
+    fn cscoreListGetUntil(csound: *mut raw::CSOUND, time: f64) -> *mut EvList;
+    fn cscoreListFreeEvents(csound: *mut raw::CSOUND, list: *mut EvList);
+}
+
+/// A score event of a Cscore program.
+///
+/// Cscore works with the sorted numeric score, so the events of the input score
+/// already have the carry, ramps and macros expanded, and their times warped by the tempo.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CscoreEvent {
+    /// The statement opcode, e.g 'i', 'f', 's', 'w' or 'e'.
+    pub opcode: char,
+    /// The p-fields starting from p1.
+    pub pfields: Vec<f64>,
+    /// The string argument of the event, e.g the file name of a GEN01 table.
+    pub string: Option<String>,
+    /// The start time in beats, before the tempo warping.
+    pub p2_orig: f64,
+    /// The duration in beats, before the tempo warping.
+    pub p3_orig: f64,
+}
+
+impl CscoreEvent {
+    /// Creates an event without string argument,
+    /// the original start time and duration are taken from p2 and p3.
+    pub fn new(opcode: char, pfields: &[f64]) -> CscoreEvent {
+        CscoreEvent {
+            opcode,
+            pfields: pfields.to_vec(),
+            string: None,
+            p2_orig: pfields.get(1).copied().unwrap_or_default(),
+            p3_orig: pfields.get(2).copied().unwrap_or_default(),
+        }
+    }
+
+    /// # Returns
+    /// The p-field *index*, starting from p1, or None if the event doesn't have it.
+    pub fn pfield(&self, index: usize) -> Option<f64> {
+        index
+            .checked_sub(1)
+            .and_then(|i| self.pfields.get(i))
+            .copied()
+    }
+
+    unsafe fn from_raw(event: *const Event) -> CscoreEvent {
+        let pcnt = (*event).pcnt.max(0) as usize;
+        let p = ptr::addr_of!((*event).p) as *const f64;
+        CscoreEvent {
+            opcode: (*event).op as u8 as char,
+            pfields: slice::from_raw_parts(p.add(1), pcnt).to_vec(),
+            string: Trampoline::ptr_to_string((*event).strarg),
+            p2_orig: (*event).p2orig,
+            p3_orig: (*event).p3orig,
+        }
+    }
+}
+
+/// Reads and writes the score events of a Cscore program.
+///
+/// It is passed to the closures of [`Csound::cscore_callback`](struct.Csound.html#method.cscore_callback)
+/// and [`Csound::run_cscore`](struct.Csound.html#method.run_cscore). The events are read from
+/// the input score in order and the events written form the score which csound performs.
+/// Events which are not written are removed from the score.
+pub struct Cscore {
+    csound: *mut raw::CSOUND,
+}
+
+impl Cscore {
+    pub(crate) fn new(csound: *mut raw::CSOUND) -> Cscore {
+        Cscore { csound }
+    }
+
+    /// # Returns
+    /// The next event of the input score, or None at the end of the score.
+    pub fn get_event(&mut self) -> Option<CscoreEvent> {
+        unsafe {
+            let event = cscoreGetEvent(self.csound);
+            if event.is_null() {
+                return None;
+            }
+            let result = CscoreEvent::from_raw(event);
+            cscoreFreeEvent(self.csound, event);
+            Some(result)
+        }
+    }
+
+    /// # Returns
+    /// The events of the input score up to the end of the section,
+    /// the `s` or `e` statement which ends it is read but not included.
+    pub fn get_section(&mut self) -> Vec<CscoreEvent> {
+        unsafe { self.take_list(cscoreListGetSection(self.csound)) }
+    }
+
+    /// # Returns
+    /// The events of the input score which start before *time* in beats.
+    pub fn get_until(&mut self, time: f64) -> Vec<CscoreEvent> {
+        unsafe { self.take_list(cscoreListGetUntil(self.csound, time)) }
+    }
+
+    /// Writes *event* into the output score.
+    /// # Returns
+    /// An error if the string argument contains a nul byte, or an `InvalidArgument` error
+    /// if the event has more than 32767 p-fields.
+    pub fn put_event(&mut self, event: &CscoreEvent) -> Result<()> {
+        // cscore stores the p-field count in a short
+        let pcnt = i16::try_from(event.pfields.len()).map_err(|_| {
+            Error::InvalidArgument("cscore events can't have more than 32767 p-fields")
+        })?;
+        let string = match &event.string {
+            Some(s) => Some(CString::new(s.as_str())?),
+            None => None,
+        };
+        unsafe {
+            let raw_event = cscoreCreateEvent(self.csound, c_int::from(pcnt));
+            if raw_event.is_null() {
+                return Ok(());
+            }
+            (*raw_event).op = event.opcode as c_char;
+            (*raw_event).pcnt = pcnt;
+            (*raw_event).p2orig = event.p2_orig;
+            (*raw_event).p3orig = event.p3_orig;
+            (*raw_event).strarg = string
+                .as_ref()
+                .map_or(ptr::null_mut(), |s| s.as_ptr() as *mut c_char);
+            let p = ptr::addr_of_mut!((*raw_event).p) as *mut f64;
+            ptr::copy_nonoverlapping(event.pfields.as_ptr(), p.add(1), event.pfields.len());
+            cscorePutEvent(self.csound, raw_event);
+            // The string is owned by rust
+            (*raw_event).strarg = ptr::null_mut();
+            cscoreFreeEvent(self.csound, raw_event);
+        }
+        Ok(())
+    }
+
+    /// Writes all the *events* into the output score.
+    pub fn put_events(&mut self, events: &[CscoreEvent]) -> Result<()> {
+        events.iter().try_for_each(|event| self.put_event(event))
+    }
+
+    /// Writes a score statement into the output score, e.g `"s"`.
+    pub fn put_string(&mut self, statement: &str) -> Result<()> {
+        let statement = CString::new(statement)?;
+        unsafe {
+            cscorePutString(self.csound, statement.as_ptr() as *mut c_char);
+        }
+        Ok(())
+    }
+
+    unsafe fn take_list(&mut self, list: *mut EvList) -> Vec<CscoreEvent> {
+        if list.is_null() {
+            return Vec::new();
+        }
+        let result = list_events(list);
+        cscoreListFreeEvents(self.csound, list);
+        result
+    }
+}
+
+unsafe fn list_events(list: *const EvList) -> Vec<CscoreEvent> {
+    let len = (*list).nevents.max(0) as usize;
+    let events = ptr::addr_of!((*list).e) as *const *mut Event;
+    (0..len)
+        .map(|i| *events.add(i + 1))
+        .filter(|event| !event.is_null())
+        .map(|event| CscoreEvent::from_raw(event))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // An event with room for 4 p-fields
+    #[repr(C)]
+    struct Event4 {
+        event: Event,
+        p: [f64; 4],
+    }
+
+    // A list with room for 3 events
+    #[repr(C)]
+    struct EvList3 {
+        h: CsHdr,
+        nslots: c_int,
+        nevents: c_int,
+        e: [*mut Event; 4],
+    }
+
+    fn header() -> CsHdr {
+        CsHdr {
+            prvblk: ptr::null_mut(),
+            nxtblk: ptr::null_mut(),
+            type_: 0,
+            size: 0,
+        }
+    }
+
+    fn event(op: char, pfields: &[f64]) -> Event4 {
+        let mut p = [0.0; 4];
+        p[..pfields.len()].copy_from_slice(pfields);
+        Event4 {
+            event: Event {
+                h: header(),
+                strarg: ptr::null_mut(),
+                op: op as c_char,
+                pcnt: pfields.len() as i16,
+                p2orig: pfields.get(1).copied().unwrap_or_default(),
+                p3orig: pfields.get(2).copied().unwrap_or_default(),
+                p: [0.0],
+            },
+            p,
+        }
+    }
+
+    #[test]
+    fn list_events_start_at_one() {
+        let mut first = event('i', &[1.0, 0.0, 1.0, 440.0]);
+        let mut middle = event('f', &[1.0, 0.0, 8192.0]);
+        let mut last = event('i', &[2.0, 1.0, 0.5]);
+        let mut list = EvList3 {
+            h: header(),
+            nslots: 3,
+            nevents: 3,
+            e: [
+                // The unused slot must not be read
+                ptr::NonNull::dangling().as_ptr(),
+                &mut first.event,
+                &mut middle.event,
+                &mut last.event,
+            ],
+        };
+        let events = unsafe { list_events(&mut list as *mut EvList3 as *const EvList) };
+        assert_eq!(events.len(), 3);
+        assert_eq!(events[0], CscoreEvent::new('i', &[1.0, 0.0, 1.0, 440.0]));
+        assert_eq!(events[1].opcode, 'f');
+        assert_eq!(events[2], CscoreEvent::new('i', &[2.0, 1.0, 0.5]));
+    }
+}
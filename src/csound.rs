@@ -1,26 +1,39 @@
 #![allow(non_camel_case_types, non_upper_case_globals, non_snake_case)]
 
+use std::any::Any;
 use std::marker::PhantomData;
 use std::mem;
 
-use std::cell::RefCell;
+use std::cell::Cell;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
 
 use std::ops::{Deref, DerefMut};
+use std::panic;
 use std::ptr;
 use std::slice;
 
+use crate::audio_stream::{AudioReceiver, AudioSender};
+use crate::automation::RecorderSlot;
 use crate::callbacks::*;
 use crate::channels::{
     ChannelBehavior, ChannelHints, ChannelInfo, InputChannel, IsChannel, OutputChannel, PvsDataExt,
 };
 
+use crate::cscore::Cscore;
 use crate::enums::{ChannelData, ControlChannelType, Language, MessageType, Status};
+use crate::error::{Error, Result};
+use crate::handle::CsoundHandle;
+use crate::options::CsoundParams;
 use crate::rtaudio::{CsAudioDevice, CsMidiDevice, RtAudioParams};
+use crate::scheduler::{Scheduler, TaskQueue};
+use crate::score_event::ScoreEvent;
+use crate::snapshot::ChannelSnapshot;
+use crate::watch::{WatchId, WatchOptions};
 use csound_sys::{controlChannelType, CSOUND_STATUS, RTCLOCK};
 
-use std::ffi::{CStr, CString, NulError};
+use std::ffi::{CStr, CString};
 use std::str;
-use std::str::Utf8Error;
 
 use libc::{c_char, c_double, c_int, c_long, c_void};
 
@@ -48,8 +61,10 @@ pub struct OpcodeListEntry {
 }
 
 #[derive(Default)]
-pub(crate) struct CallbackHandler<'c> {
-    pub callbacks: Callbacks<'c>,
+pub(crate) struct CallbackHandler {
+    pub callbacks: Callbacks,
+    // Payload of a panic caught in some callback, it is resumed by the perform functions.
+    pub panic: Option<Box<dyn Any + Send>>,
 }
 
 /// Opaque struct representing an csound object
@@ -58,20 +73,31 @@ pub(crate) struct CallbackHandler<'c> {
 /// The Engine element is the inner representation of the CSOUND opaque pointer and is
 /// the object wich talk directly with the libcsound c library.
 ///
+/// The closures passed to the callback setters (e.g [`Csound::message_string_callback`](struct.Csound.html#method.message_string_callback))
+/// are owned by the csound instance until it is dropped, so they have to be `Send + 'static`.
+/// Setting a callback again replaces the previous closure, which is dropped.
+///
 #[derive(Debug)]
 pub struct Csound {
     /// Inner representation of the CSOUND opaque pointer
-    pub(crate) engine: Inner,
+    pub(crate) engine: Arc<Inner>,
+    // Most of the csound's functions are not thread-safe, so Csound must not be Sync.
+    // Use a CsoundHandle to share the instance between threads.
+    not_sync: PhantomData<Cell<()>>,
 }
 
 /// Opaque struct representing a csound object
 #[derive(Debug)]
 pub(crate) struct Inner {
     pub(crate) csound: *mut csound_sys::CSOUND,
-    use_msg_buffer: RefCell<bool>,
+    use_msg_buffer: AtomicBool,
+    pub(crate) recorder: RecorderSlot,
 }
 
+// The Inner pointer is shared by the Csound instance and its handles, which
+// only call the csound's thread-safe functions.
 unsafe impl Send for Inner {}
+unsafe impl Sync for Inner {}
 
 impl Default for Csound {
     fn default() -> Self {
@@ -83,19 +109,21 @@ impl Default for Csound {
             // set default callback which does not nothing
             csound_sys::csoundSetDefaultMessageCallback(Some(Trampoline::default_message_callback));
 
-            let callback_handler = Box::new(CallbackHandler {
-                callbacks: Callbacks::default(),
-            });
+            let callback_handler = Box::new(CallbackHandler::default());
             let host_data_ptr = Box::into_raw(callback_handler) as *mut c_void;
 
             let csound_sys = csound_sys::csoundCreate(host_data_ptr);
             assert!(!csound_sys.is_null());
 
-            let engine = Inner {
+            let engine = Arc::new(Inner {
                 csound: csound_sys,
-                use_msg_buffer: RefCell::new(false),
-            };
-            Csound { engine }
+                use_msg_buffer: AtomicBool::new(false),
+                recorder: RecorderSlot::default(),
+            });
+            Csound {
+                engine,
+                not_sync: PhantomData,
+            }
         }
     }
 }
@@ -114,7 +142,7 @@ impl Csound {
     /// use csound::{Csound, MessageType};
     ///
     ///  // Creates a Csound instance and use a custom callback handler
-    /// let csound = Csound::new();
+    /// let mut csound = Csound::new();
     /// // enable the message callback passing a closure to the custom callback handler
     /// csound.message_string_callback( |mtype: MessageType, message: &str| {
     ///     println!("message type: {:?} message content:  {}", mtype, message);
@@ -130,19 +158,16 @@ impl Csound {
     /// Initializes the csound library with specific flags(see: [anchor text]()).
     /// This function is called internally by Csound::new(), so there is generally no need to use it explicitly unless
     /// you need to avoid default initilization that sets signal handlers and atexit() callbacks.
-    /// Return value is Ok() on success or an error message in case of failure
-    pub fn initialize(flags: i32) -> Result<(), &'static str> {
+    /// Return value is Ok() on success or an [`Error::Engine`](enum.Error.html#variant.Engine) in case of failure,
+    /// a positive status code means that the initialization was done already.
+    pub fn initialize(flags: i32) -> Result<()> {
         unsafe {
             match csound_sys::csoundInitialize(flags as c_int) {
-                CSOUND_STATUS::CSOUND_ERROR => Err("Can't to initialize csound "),
                 CSOUND_STATUS::CSOUND_SUCCESS => Ok(()),
-                value => {
-                    if value > 0 {
-                        Err("Initialization was done already")
-                    } else {
-                        Err("Unknown error - can not to initialize")
-                    }
-                }
+                code => Err(Error::Engine {
+                    operation: "csoundInitialize",
+                    code,
+                }),
             }
         }
     }
@@ -151,18 +176,41 @@ impl Csound {
     ///
     /// NB: blank spaces are not allowed.
     /// # Returns
-    /// returns Ok on success or an error message in case the option is invalid.
-    pub fn set_option(&self, option: &str) -> Result<(), String> {
-        let op =
-            CString::new(option).map_err(|e| format!("Error parsing the csound option: {}", e))?;
+    /// returns Ok on success or [`Error::InvalidOption`](enum.Error.html#variant.InvalidOption) in case the option is invalid.
+    pub fn set_option(&self, option: &str) -> Result<()> {
+        let op = CString::new(option)?;
         unsafe {
             match csound_sys::csoundSetOption(self.engine.csound, op.as_ptr()) {
                 CSOUND_STATUS::CSOUND_SUCCESS => Ok(()),
-                _ => Err(format!("Csound option {} not valid", option)),
+                _ => Err(Error::InvalidOption(option.to_owned())),
             }
         }
     }
 
+    /// # Returns
+    /// The current csound's engine parameters.
+    /// see [`CsoundParams`](struct.CsoundParams.html)
+    pub fn get_params(&self) -> CsoundParams {
+        unsafe {
+            let mut params: csound_sys::CSOUND_PARAMS = mem::zeroed();
+            csound_sys::csoundGetParams(self.engine.csound, &mut params as *mut _);
+            CsoundParams::from(&params)
+        }
+    }
+
+    /// Configures csound with the parameters in *params*.
+    ///
+    /// NB: csound ignores this call once the engine has been compiled,
+    /// the usual way is to read the current parameters with [`Csound::get_params`](struct.Csound.html#method.get_params),
+    /// modify them and set them back before compiling.
+    /// See also the typed [`Options`](struct.Options.html) builder.
+    pub fn set_params(&self, params: &CsoundParams) {
+        unsafe {
+            let mut raw = csound_sys::CSOUND_PARAMS::from(params);
+            csound_sys::csoundSetParams(self.engine.csound, &mut raw as *mut _);
+        }
+    }
+
     /// Prepares Csound for performance.
     ///
     /// Normally called after compiling a csd file or an orc file, in which case score preprocessing is performed and
@@ -176,18 +224,23 @@ impl Csound {
     /// use csound::Csound;
     ///
     /// # let csd_filename = "file.csd";
-    /// let csound = Csound::new();
+    /// let mut csound = Csound::new();
     /// csound.compile_csd(csd_filename).unwrap();
     /// csound.start();
     /// // ...
     /// ```
     ///
-    pub fn start(&self) -> Result<(), &'static str> {
+    /// # Returns
+    /// An [`Error::Engine`](enum.Error.html#variant.Engine) if csound is already started,
+    /// call [`Csound::reset`](struct.Csound.html#method.reset) before starting again.
+    pub fn start(&self) -> Result<()> {
         unsafe {
-            if csound_sys::csoundStart(self.engine.csound) == CSOUND_STATUS::CSOUND_SUCCESS {
-                Ok(())
-            } else {
-                Err("Csound is already started, call reset() before starting again.")
+            match csound_sys::csoundStart(self.engine.csound) {
+                CSOUND_STATUS::CSOUND_SUCCESS => Ok(()),
+                code => Err(Error::Engine {
+                    operation: "csoundStart",
+                    code,
+                }),
             }
         }
     }
@@ -204,6 +257,32 @@ impl Csound {
         unsafe { csound_sys::csoundGetAPIVersion() as u32 }
     }
 
+    /// Creates a [`CsoundHandle`](struct.CsoundHandle.html) to this csound instance.
+    /// The handle can be cloned and sent to other threads, in order to control the
+    /// engine while it is performing.
+    ///
+    /// The handle can send score events and code which redefine tables, so, like
+    /// [`Csound::reset`](struct.Csound.html#method.reset), it takes `&mut self` and
+    /// can not be created while a table, buffer or channel view is alive:
+    /// ```compile_fail
+    /// let mut csound = csound::Csound::new();
+    /// let table = csound.get_table(1).unwrap();
+    /// let handle = csound.handle();
+    /// handle.send_score_event_async('f', &[-1.0, 0.0]);
+    /// csound.perform_ksmps();
+    /// println!("{:?}", table.as_slice());
+    /// ```
+    pub fn handle(&mut self) -> CsoundHandle {
+        self.shared_handle()
+    }
+
+    // A handle for the crate's own use, which doesn't send events or code
+    pub(crate) fn shared_handle(&self) -> CsoundHandle {
+        CsoundHandle {
+            engine: Arc::clone(&self.engine),
+        }
+    }
+
     /* Engine performance functions implementations ********************************************************* */
 
     /// Stops the performance of a csound's instance
@@ -216,9 +295,73 @@ impl Csound {
 
     /// Resets all internal memory and state in preparation for a new performance.
     /// Enables external software to run successive Csound performances without reloading Csound.
-    pub fn reset(&self) {
+    /// The callbacks, channel watchers, audio streams and scheduled tasks set up before keep working
+    /// after the reset.
+    ///
+    /// Resetting or compiling frees the memory behind the csound's tables, buffers and channels,
+    /// and score events (e.g `f` statements) can redefine or delete tables,
+    /// so those methods take `&mut self` and every view obtained before, a [`Table`](struct.Table.html),
+    /// a [`BufferPtr`](struct.BufferPtr.html), an [`InputChannel`](struct.InputChannel.html) or an
+    /// [`OutputChannel`](struct.OutputChannel.html), has to be dropped first.
+    /// The following uses are rejected at compile time:
+    /// ```compile_fail
+    /// let mut csound = csound::Csound::new();
+    /// let table = csound.get_table(1).unwrap();
+    /// csound.reset();
+    /// println!("{:?}", table.as_slice());
+    /// ```
+    /// ```compile_fail
+    /// let mut csound = csound::Csound::new();
+    /// let spout = csound.get_spout().unwrap();
+    /// csound.reset();
+    /// println!("{:?}", spout.as_slice());
+    /// ```
+    /// ```compile_fail
+    /// use csound::ControlChannel;
+    /// let mut csound = csound::Csound::new();
+    /// let channel = csound.get_input_channel::<ControlChannel>("amp").unwrap();
+    /// csound.compile_orc("instr 1\nendin");
+    /// channel.write(0.5);
+    /// ```
+    /// ```compile_fail
+    /// use csound::AudioChannel;
+    /// let mut csound = csound::Csound::new();
+    /// let channel = csound.get_output_channel::<AudioChannel>("out").unwrap();
+    /// csound.compile_csd("other.csd");
+    /// println!("{:?}", channel.read());
+    /// ```
+    /// ```compile_fail
+    /// let mut csound = csound::Csound::new();
+    /// let input = csound.get_input_buffer().unwrap();
+    /// csound.eval_code("gi_table ftgen 1, 0, 1024, 10, 1");
+    /// drop(input);
+    /// ```
+    /// ```compile_fail
+    /// let mut csound = csound::Csound::new();
+    /// let table = csound.get_table(1).unwrap();
+    /// csound.send_score_event('f', &[-1.0, 0.0]);
+    /// println!("{:?}", table.as_slice());
+    /// ```
+    /// while dropping the views before resetting works:
+    /// ```no_run
+    /// let mut csound = csound::Csound::new();
+    /// csound.compile_csd("some.csd").unwrap();
+    /// csound.start().unwrap();
+    /// {
+    ///     let table = csound.get_table(1).unwrap();
+    ///     println!("{:?}", table.as_slice());
+    /// }
+    /// csound.reset();
+    /// ```
+    /// A [`CsoundHandle`](struct.CsoundHandle.html) can not be created while a view is alive either,
+    /// but the handles created before are not tracked, so the code and events they send from
+    /// other threads should not redefine tables in use.
+    pub fn reset(&mut self) {
         unsafe {
             csound_sys::csoundReset(self.engine.csound);
+            (*(csound_sys::csoundGetHostData(self.engine.csound) as *mut CallbackHandler))
+                .callbacks
+                .reset_sense_event_cb(self.engine.csound);
         }
     }
 
@@ -227,26 +370,28 @@ impl Csound {
     /// # Arguments
     /// * `args` A slice containing the arguments  to be passed to csound
     /// # Returns
-    /// A error message in case of failure
-    pub fn compile<T>(&self, args: &[T]) -> Result<(), String>
+    /// An [`Error`](enum.Error.html) in case of failure
+    pub fn compile<T>(&mut self, args: &[T]) -> Result<()>
     where
         T: AsRef<str> + std::fmt::Debug,
     {
         if args.is_empty() {
-            return Err("Not enough arguments".into());
+            return Err(Error::InvalidArgument("Not enough arguments"));
         }
 
-        let arguments: Vec<CString> = args
+        let arguments = args
             .iter()
             .map(|arg| CString::new(arg.as_ref()))
-            .filter_map(Result::ok)
-            .collect();
+            .collect::<std::result::Result<Vec<CString>, _>>()?;
         let mut args_raw: Vec<*const c_char> = arguments.iter().map(|arg| arg.as_ptr()).collect();
         let argv: *mut *const c_char = args_raw.as_mut_ptr();
         unsafe {
             match csound_sys::csoundCompile(self.engine.csound, args_raw.len() as c_int, argv) {
                 CSOUND_STATUS::CSOUND_SUCCESS => Ok(()),
-                _ => Err(format!("Can't compile csound arguments: {:?}", args)),
+                code => Err(Error::Engine {
+                    operation: "csoundCompile",
+                    code,
+                }),
             }
         }
     }
@@ -260,7 +405,7 @@ impl Csound {
     /// ```no_run
     /// use csound::Csound;
     ///
-    /// let csound  = Csound::new();
+    /// let mut csound  = Csound::new();
     /// csound.set_option("-an_option");
     /// csound.set_option("-another_option");
     /// csound.start();
@@ -281,7 +426,7 @@ impl Csound {
     /// ```no_run
     /// use csound::Csound;
     ///
-    /// let csound  = Csound::new();
+    /// let mut csound  = Csound::new();
     /// # let csd_filename = "file.csd";
     /// csound.compile_csd(csd_filename);
     /// csound.start();
@@ -290,7 +435,7 @@ impl Csound {
     /// ```
     /// # Arguments
     /// * `csd` A reference to .csd file name
-    pub fn compile_csd<T>(&self, csd: T) -> Result<(), &'static str>
+    pub fn compile_csd<T>(&mut self, csd: T) -> Result<()>
     where
         T: AsRef<str>,
     {
@@ -298,7 +443,10 @@ impl Csound {
         unsafe {
             match csound_sys::csoundCompileCsd(self.engine.csound, path.as_ptr()) {
                 CSOUND_STATUS::CSOUND_SUCCESS => Ok(()),
-                _ => Err("Can't compile the csd file"),
+                code => Err(Error::Engine {
+                    operation: "csoundCompileCsd",
+                    code,
+                }),
             }
         }
     }
@@ -308,7 +456,7 @@ impl Csound {
     /// This is convenient when it is desirable to package the csd as part of an application or a multi-language piece.
     /// # Arguments
     /// * `csd_text` A reference to the text to be compiled by csound
-    pub fn compile_csd_text<T>(&self, csdText: T) -> Result<(), &'static str>
+    pub fn compile_csd_text<T>(&mut self, csdText: T) -> Result<()>
     where
         T: AsRef<str>,
     {
@@ -316,7 +464,10 @@ impl Csound {
         unsafe {
             match csound_sys::csoundCompileCsdText(self.engine.csound, path.as_ptr()) {
                 CSOUND_STATUS::CSOUND_SUCCESS => Ok(()),
-                _ => Err("Can't compile the csd file"),
+                code => Err(Error::Engine {
+                    operation: "csoundCompileCsdText",
+                    code,
+                }),
             }
         }
     }
@@ -326,7 +477,7 @@ impl Csound {
     /// ```
     /// use csound::Csound;
     ///
-    /// let csound  = Csound::new();
+    /// let mut csound  = Csound::new();
     /// let orc_code = "instr 1
     ///                 a1 rand 0dbfs/4
     ///                 out a1
@@ -335,7 +486,7 @@ impl Csound {
     /// ```
     /// # Arguments
     /// * `orcPath` A reference to orchestra strings
-    pub fn compile_orc<T>(&self, orc: T) -> Result<(), &'static str>
+    pub fn compile_orc<T>(&mut self, orc: T) -> Result<()>
     where
         T: AsRef<str>,
     {
@@ -343,7 +494,10 @@ impl Csound {
         unsafe {
             match csound_sys::csoundCompileOrc(self.engine.csound, path.as_ptr()) {
                 CSOUND_STATUS::CSOUND_SUCCESS => Ok(()),
-                _ => Err("Can't to compile orc file"),
+                code => Err(Error::Engine {
+                    operation: "csoundCompileOrc",
+                    code,
+                }),
             }
         }
     }
@@ -353,7 +507,7 @@ impl Csound {
     /// The function returns following parsing and compilation.
     /// # Arguments
     /// * `orc` A reference to an csound's orchestra definitions
-    pub fn compile_orc_async<T>(&self, orc: T) -> Result<(), &'static str>
+    pub fn compile_orc_async<T>(&mut self, orc: T) -> Result<()>
     where
         T: AsRef<str>,
     {
@@ -361,7 +515,10 @@ impl Csound {
         unsafe {
             match csound_sys::csoundCompileOrcAsync(self.engine.csound, path.as_ptr()) {
                 CSOUND_STATUS::CSOUND_SUCCESS => Ok(()),
-                _ => Err("Can't to compile orc file"),
+                code => Err(Error::Engine {
+                    operation: "csoundCompileOrcAsync",
+                    code,
+                }),
             }
         }
     }
@@ -373,7 +530,7 @@ impl Csound {
     ///   'return' opcode in global space.
     ///       code = "i1 = 2 + 2 \n return i1 \n"
     ///       retval = csound.eval_code(code)
-    pub fn eval_code<T>(&self, code: T) -> Result<f64, &'static str>
+    pub fn eval_code<T>(&mut self, code: T) -> Result<f64>
     where
         T: AsRef<str>,
     {
@@ -395,8 +552,12 @@ impl Csound {
     ///  Note that some csf file, text or score have to be compiled first and then *start()* must be called.
     ///  In the case of zero return value, *perform()* can be called again to continue the stopped performance.
     ///  Otherwise, [`Csound::reset`](struct.Csound.html#method.reset) should be called to clean up after the finished or failed performance.
+    /// # Panics
+    /// If a callback closure panicked, the performance is stopped and the panic is resumed here.
     pub fn perform(&self) -> i32 {
-        unsafe { csound_sys::csoundPerform(self.engine.csound) as i32 }
+        let result = unsafe { csound_sys::csoundPerform(self.engine.csound) as i32 };
+        self.resume_callback_panic();
+        result
     }
 
     /// Senses input events, and performs one control sample worth ```ksmps * number of channels * size_off::<f64> bytes``` of audio output.
@@ -406,8 +567,12 @@ impl Csound {
     /// performance with audio input and output(see: [`Csound::read_spin_buffer`](struct.Csound.html#method.read_spin_buffer), [`Csound::read_spout_buffer`](struct.Csound.html#method.read_spout_buffer))
     /// # Returns
     /// *false* during performance, and true when performance has finished. If called until it returns *true*, will perform an entire score.
+    /// # Panics
+    /// If a callback closure panicked, the performance is stopped and the panic is resumed here.
     pub fn perform_ksmps(&self) -> bool {
-        unsafe { csound_sys::csoundPerformKsmps(self.engine.csound) != 0 }
+        let finished = unsafe { csound_sys::csoundPerformKsmps(self.engine.csound) != 0 };
+        self.resume_callback_panic();
+        finished
     }
 
     /// Performs Csound, sensing real-time and score events and processing one buffer's worth (-b frames) of interleaved audio.
@@ -416,8 +581,22 @@ impl Csound {
     /// [`Csound::write_input_buffer`](struct.Csound.html#method.write_input_buffer) to write/read the csound's I/O buffers content.
     /// #Returns
     /// *false* during performance or *true* when performance has finished.
+    /// # Panics
+    /// If a callback closure panicked, the performance is stopped and the panic is resumed here.
     pub fn perform_buffer(&self) -> bool {
-        unsafe { csound_sys::csoundPerformBuffer(self.engine.csound) != 0 }
+        let finished = unsafe { csound_sys::csoundPerformBuffer(self.engine.csound) != 0 };
+        self.resume_callback_panic();
+        finished
+    }
+
+    // Resumes the panic of a callback which was caught by the trampolines.
+    fn resume_callback_panic(&self) {
+        unsafe {
+            let handler = csound_sys::csoundGetHostData(self.engine.csound) as *mut CallbackHandler;
+            if let Some(payload) = (*handler).panic.take() {
+                panic::resume_unwind(payload);
+            }
+        }
     }
 
     /*********************************** UDP ****************************************************/
@@ -428,11 +607,14 @@ impl Csound {
     /// * `port` The server port number.
     /// # Returns
     /// *Ok* on success or an error code on failure.
-    pub fn udp_server_start(&self, port: u32) -> Result<(), Status> {
+    pub fn udp_server_start(&self, port: u32) -> Result<()> {
         unsafe {
-            match Status::from(csound_sys::csoundUDPServerStart(self.engine.csound, port) as i32) {
-                Status::CS_SUCCESS => Ok(()),
-                e => Err(e),
+            match csound_sys::csoundUDPServerStart(self.engine.csound, port) {
+                CSOUND_STATUS::CSOUND_SUCCESS => Ok(()),
+                code => Err(Error::Engine {
+                    operation: "csoundUDPServerStart",
+                    code,
+                }),
             }
         }
     }
@@ -452,12 +634,15 @@ impl Csound {
 
     /// Closes the UDP server
     /// # Returns
-    /// *Ok* if the running server was successfully closed, Status code otherwise.
-    pub fn udp_server_close(&self) -> Result<(), Status> {
+    /// *Ok* if the running server was successfully closed, an error code otherwise.
+    pub fn udp_server_close(&self) -> Result<()> {
         unsafe {
-            match Status::from(csound_sys::csoundUDPServerClose(self.engine.csound) as i32) {
-                Status::CS_SUCCESS => Ok(()),
-                status => Err(status),
+            match csound_sys::csoundUDPServerClose(self.engine.csound) {
+                CSOUND_STATUS::CSOUND_SUCCESS => Ok(()),
+                code => Err(Error::Engine {
+                    operation: "csoundUDPServerClose",
+                    code,
+                }),
             }
         }
     }
@@ -470,20 +655,22 @@ impl Csound {
     /// * `mirror` If it is true, the messages will continue to be sent to the usual destination
     /// (see [`Csound::message_string_callback`](struct.Csound.html#method.message_string_callback) ) as well as to UDP.
     /// # Returns
-    /// *Ok* on success or an Status code if the UDP transmission could not be set up.
-    pub fn udp_console(&self, addr: &str, port: u32, mirror: bool) -> Result<(), Status> {
+    /// *Ok* on success or an error code if the UDP transmission could not be set up.
+    pub fn udp_console(&self, addr: &str, port: u32, mirror: bool) -> Result<()> {
         unsafe {
-            let ip = CString::new(addr).map_err(|_e| Status::CS_ERROR)?;
-            if csound_sys::csoundUDPConsole(
+            let ip = CString::new(addr)?;
+            match csound_sys::csoundUDPConsole(
                 self.engine.csound,
                 ip.as_ptr(),
                 port as c_int,
                 mirror as c_int,
-            ) == CSOUND_STATUS::CSOUND_SUCCESS
-            {
-                return Ok(());
+            ) {
+                CSOUND_STATUS::CSOUND_SUCCESS => Ok(()),
+                code => Err(Error::Engine {
+                    operation: "csoundUDPConsole",
+                    code,
+                }),
             }
-            Err(Status::CS_ERROR)
         }
     }
 
@@ -594,7 +781,7 @@ impl Csound {
     /// * `out_type`  can be one of "wav","aiff", "au","raw", "paf", "svx", "nist", "voc", "ircam","w64","mat4", "mat5", "pvf","xi", "htk","sds","avr",
     /// "wavex","sd2", "flac", "caf","wve","ogg","mpc2k","rf64", or NULL (use default or realtime IO).
     /// * `format` can be one of "alaw", "schar", "uchar", "float", "double", "long", "short", "ulaw", "24bit", "vorbis", or NULL (use default or realtime IO).
-    pub fn set_output(&self, name: &str, out_type: &str, format: &str) -> Result<(), NulError> {
+    pub fn set_output(&self, name: &str, out_type: &str, format: &str) -> Result<()> {
         unsafe {
             let devName = CString::new(name)?;
             let devType = CString::new(out_type)?;
@@ -618,7 +805,7 @@ impl Csound {
     /// let csound = Csound::new();
     /// let (output_type, output_format) = csound.get_output_format().unwrap();
     /// ```
-    pub fn get_output_format(&self) -> Result<(String, String), Utf8Error> {
+    pub fn get_output_format(&self) -> Result<(String, String)> {
         let otype = vec![b'\0'; OUTPUT_TYPE_LENGTH];
         let format = vec![b'\0'; OUTPUT_FORMAT_LENGTH];
         unsafe {
@@ -639,7 +826,7 @@ impl Csound {
     /// Sets input source
     /// # Arguments
     /// * `name` The source device name.
-    pub fn set_input(&self, name: &str) -> Result<(), NulError> {
+    pub fn set_input(&self, name: &str) -> Result<()> {
         unsafe {
             let devName = CString::new(name)?;
             csound_sys::csoundSetInput(self.engine.csound, devName.as_ptr());
@@ -648,7 +835,7 @@ impl Csound {
     }
 
     /// Set MIDI file input name
-    pub fn set_midi_file_input(&self, name: &str) -> Result<(), NulError> {
+    pub fn set_midi_file_input(&self, name: &str) -> Result<()> {
         unsafe {
             let devName = CString::new(name)?;
             csound_sys::csoundSetMIDIFileInput(self.engine.csound, devName.as_ptr());
@@ -657,7 +844,7 @@ impl Csound {
     }
 
     /// Set MIDI file output name
-    pub fn set_midi_file_output(&self, name: &str) -> Result<(), NulError> {
+    pub fn set_midi_file_output(&self, name: &str) -> Result<()> {
         unsafe {
             let devName = CString::new(name)?;
             csound_sys::csoundSetMIDIFileOutput(self.engine.csound, devName.as_ptr());
@@ -666,7 +853,7 @@ impl Csound {
     }
 
     /// Set MIDI input device name/number
-    pub fn set_midi_input(&self, name: &str) -> Result<(), NulError> {
+    pub fn set_midi_input(&self, name: &str) -> Result<()> {
         unsafe {
             let devName = CString::new(name)?;
             csound_sys::csoundSetMIDIInput(self.engine.csound, devName.as_ptr());
@@ -675,7 +862,7 @@ impl Csound {
     }
 
     /// Set MIDI output device name
-    pub fn set_midi_output(&self, name: &str) -> Result<(), NulError> {
+    pub fn set_midi_output(&self, name: &str) -> Result<()> {
         unsafe {
             let devName = CString::new(name)?;
             csound_sys::csoundSetMIDIOutput(self.engine.csound, devName.as_ptr());
@@ -686,7 +873,7 @@ impl Csound {
     /* Engine general Realtime Audio I/O functions implmentations ********************************************************* */
 
     /// Sets the current RT audio module
-    pub fn set_rt_audio_module(&self, name: &str) -> Result<(), NulError> {
+    pub fn set_rt_audio_module(&self, name: &str) -> Result<()> {
         unsafe {
             let devName = CString::new(name)?;
             csound_sys::csoundSetRTAudioModule(self.engine.csound, devName.as_ptr());
@@ -715,7 +902,7 @@ impl Csound {
     /// ```no_run
     /// use csound::Csound;
     ///
-    /// let csound = Csound::new();
+    /// let mut csound = Csound::new();
     /// csound.compile_csd("some_file_path");
     /// csound.start();
     /// let input_buffer_ptr = csound.get_input_buffer();
@@ -748,7 +935,7 @@ impl Csound {
     /// ```no_run
     /// use csound::Csound;
     ///
-    /// let csound = Csound::new();
+    /// let mut csound = Csound::new();
     /// csound.compile_csd("some_file_path");
     /// csound.start();
     /// let output_buffer_ptr = csound.get_output_buffer();
@@ -781,7 +968,7 @@ impl Csound {
     /// ```no_run
     /// use csound::Csound;
     ///
-    /// let csound = Csound::new();
+    /// let mut csound = Csound::new();
     /// csound.compile_csd("some_file_path");
     /// csound.start();
     /// let spin = csound.get_spin();
@@ -814,7 +1001,7 @@ impl Csound {
     /// ```no_run
     /// use csound::Csound;
     ///
-    /// let csound = Csound::new();
+    /// let mut csound = Csound::new();
     /// csound.compile_csd("some_file_path");
     /// csound.start();
     /// let spout = csound.get_spout();
@@ -846,12 +1033,12 @@ impl Csound {
     /// [`Csound::get_output_buffer_size`](struct.Csound.html#method.get_output_buffer_size), samples.
     /// # Returns
     /// The number of samples copied into the slice on success, or an
-    /// error message if the internal csound's buffer has not been initialized.
+    /// [`Error::NotStarted`](enum.Error.html#variant.NotStarted) if the internal csound's buffer has not been initialized.
     /// # Example
     /// ```no_run
     /// use csound::Csound;
     ///
-    /// let csound = Csound::new();
+    /// let mut csound = Csound::new();
     /// csound.compile_csd("some_file_path");
     /// csound.start();
     /// let output_buffer_length = csound.get_output_buffer_size();
@@ -868,7 +1055,7 @@ impl Csound {
         since = "0.1.5",
         note = "please use Csound::get_output_buffer object instead"
     )]
-    pub fn read_output_buffer(&self, output: &mut [f64]) -> Result<usize, &'static str> {
+    pub fn read_output_buffer(&self, output: &mut [f64]) -> Result<usize> {
         let size = self.get_output_buffer_size();
         let obuffer =
             unsafe { csound_sys::csoundGetOutputBuffer(self.engine.csound) as *const f64 };
@@ -882,7 +1069,7 @@ impl Csound {
                 return Ok(len);
             }
         }
-        Err("The output buffer is not initialized, call the 'compile()' and 'start()' methods.")
+        Err(Error::NotStarted)
     }
 
     /// Method used when you want to copy custom audio samples into the csound buffer to be processed.
@@ -891,12 +1078,12 @@ impl Csound {
     /// the Csound's input buffer.
     /// # Returns
     /// The number of samples copied into the csound's input buffer or an
-    /// error message if the internal csound's buffer has not been initialized.
+    /// [`Error::NotStarted`](enum.Error.html#variant.NotStarted) if the internal csound's buffer has not been initialized.
     /// # Example
     /// ```no_run
     /// use csound::Csound;
     ///
-    /// let csound = Csound::new();
+    /// let mut csound = Csound::new();
     /// csound.compile_csd("some_file_path");
     /// csound.start();
     /// let input_buffer_length = csound.get_input_buffer_size();
@@ -915,7 +1102,7 @@ impl Csound {
         since = "0.1.5",
         note = "please use Csound::get_input_buffer object instead"
     )]
-    pub fn write_input_buffer(&self, input: &[f64]) -> Result<usize, &'static str> {
+    pub fn write_input_buffer(&self, input: &[f64]) -> Result<usize> {
         let size = self.get_input_buffer_size();
         let ibuffer = unsafe { csound_sys::csoundGetInputBuffer(self.engine.csound) as *mut f64 };
         let mut len = input.len();
@@ -928,18 +1115,18 @@ impl Csound {
                 return Ok(len);
             }
         }
-        Err("The input buffer is not initialized, call the 'compile()' and 'start()' methods.")
+        Err(Error::NotStarted)
     }
 
     /// Enables external software to read audio from Csound after calling [`Csound::perform_ksmps`](struct.Csound.html#method.perform_ksmps)
     /// # Returns
     /// The number of samples copied  or an
-    /// error message if the internal csound's buffer has not been initialized.
+    /// [`Error::NotStarted`](enum.Error.html#variant.NotStarted) if the internal csound's buffer has not been initialized.
     /// # Example
     /// ```no_run
     /// use csound::Csound;
     ///
-    /// let csound = Csound::new();
+    /// let mut csound = Csound::new();
     /// csound.compile_csd("some_file_path");
     /// csound.start();
     /// let spout_length = csound.get_ksmps() * csound.output_channels();
@@ -955,7 +1142,7 @@ impl Csound {
     /// Use [`Csound::get_spout`](struct.Csound.html#method.get_spout) to get a [`BufferPtr`](struct.BufferPtr.html)
     /// object.
     #[deprecated(since = "0.1.5", note = "please use Csound::get_spout object instead")]
-    pub fn read_spout_buffer(&self, output: &mut [f64]) -> Result<usize, &'static str> {
+    pub fn read_spout_buffer(&self, output: &mut [f64]) -> Result<usize> {
         let size = self.get_ksmps() as usize * self.output_channels() as usize;
         let spout = unsafe { csound_sys::csoundGetSpout(self.engine.csound) as *const f64 };
         let mut len = output.len();
@@ -968,19 +1155,19 @@ impl Csound {
                 return Ok(len);
             }
         }
-        Err("The spout buffer is not initialized, call the 'compile()' and 'start()' methods.")
+        Err(Error::NotStarted)
     }
 
     /// Enables external software to write audio into Csound before calling [`Csound::perform_ksmps`](struct.Csound.html#method.perform_ksmps)
     /// [`Csound::get_ksmps`](struct.Csound.html#method.get_ksmps) * [`Csound::input_channels`](struct.Csound.html#method.input_channels).
     /// # Returns
     /// The number of samples copied  or an
-    /// error message if the internal csound's buffer has not been initialized.
+    /// [`Error::NotStarted`](enum.Error.html#variant.NotStarted) if the internal csound's buffer has not been initialized.
     /// # Example
     /// ```no_run
     /// use csound::Csound;
     ///
-    /// let csound = Csound::new();
+    /// let mut csound = Csound::new();
     /// csound.compile_csd("some_file_path");
     /// csound.start();
     /// let spin_length = csound.get_ksmps() * csound.input_channels();
@@ -996,7 +1183,7 @@ impl Csound {
     /// Use [`Csound::get_spin`](struct.Csound.html#method.get_spin) to get a [`BufferPtr`](struct.BufferPtr.html)
     /// object.
     #[deprecated(since = "0.1.5", note = "please use Csound::get_spin object instead")]
-    pub fn write_spin_buffer(&self, input: &[f64]) -> Result<usize, &'static str> {
+    pub fn write_spin_buffer(&self, input: &[f64]) -> Result<usize> {
         let size = self.get_ksmps() as usize * self.input_channels() as usize;
         let spin = unsafe { csound_sys::csoundGetSpin(self.engine.csound) as *mut f64 };
         let mut len = input.len();
@@ -1009,7 +1196,7 @@ impl Csound {
                 return Ok(len);
             }
         }
-        Err("The spin buffer is not initialized, call the 'compile()' and 'start()' methods.")
+        Err(Error::NotStarted)
     }
 
     /// Clears the spin buffer.
@@ -1186,20 +1373,21 @@ impl Csound {
 
     /// Reads, preprocesses, and loads a score from an ASCII string.
     /// It can be called repeatedly with the new score events being added to the currently scheduled ones.
-    pub fn read_score(&self, score: &str) -> Result<(), &'static str> {
+    pub fn read_score(&mut self, score: &str) -> Result<()> {
         unsafe {
             let s = Trampoline::convert_str_to_c(score)?;
-            if csound_sys::csoundReadScore(self.engine.csound, s.as_ptr())
-                == CSOUND_STATUS::CSOUND_SUCCESS
-            {
-                return Ok(());
+            match csound_sys::csoundReadScore(self.engine.csound, s.as_ptr()) {
+                CSOUND_STATUS::CSOUND_SUCCESS => Ok(()),
+                code => Err(Error::Engine {
+                    operation: "csoundReadScore",
+                    code,
+                }),
             }
-            Err("Invalid score")
         }
     }
 
     /// Asynchronous version of [`Csound::read_score`](struct.Csound.html#method.read_score)
-    pub fn read_score_async(&self, score: &str) -> Result<(), &'static str> {
+    pub fn read_score_async(&mut self, score: &str) -> Result<()> {
         unsafe {
             let s = Trampoline::convert_str_to_c(score)?;
             csound_sys::csoundReadScoreAsync(self.engine.csound, s.as_ptr());
@@ -1207,6 +1395,126 @@ impl Csound {
         }
     }
 
+    /// Sorts a score with the csound's score preprocessor, without running a performance.
+    ///
+    /// The score is read as [`Csound::read_score`](struct.Csound.html#method.read_score) does,
+    /// carry, ramps, macros, sections and repeats are expanded, the tempo statements are applied and
+    /// the events are sorted by time. The result is written in the csound's sorted score format,
+    /// the format of the *score.srt* file, where the times in beats are followed by the warped times.
+    /// The score data is passed through C temporary files.
+    ///
+    /// The score is sorted by a scratch csound instance, because sorting replaces the score
+    /// of the instance, so it can be called at any time, even while this instance performs.
+    /// # Returns
+    /// The sorted score or an error if the temporary files couldn't be used.
+    /// # Example
+    /// ```no_run
+    /// use csound::Csound;
+    ///
+    /// let cs = Csound::new();
+    /// let sorted = cs.sort_score("t 0 120\ni 1 1 1 440\ni 1 0 1 .\ne").unwrap();
+    /// println!("{}", sorted);
+    /// ```
+    pub fn sort_score(&self, score: &str) -> Result<String> {
+        let input = TempFile::with_content(score)?;
+        let output = TempFile::new()?;
+        let scratch = ScratchCsound::new();
+        unsafe {
+            match csound_sys::csoundScoreSort(scratch.0, input.0 as *mut _, output.0 as *mut _) {
+                CSOUND_STATUS::CSOUND_SUCCESS => output.read_to_string(),
+                code => Err(Error::Engine {
+                    operation: "csoundScoreSort",
+                    code,
+                }),
+            }
+        }
+    }
+
+    /// Extracts part of a sorted score, like the csound's `-x` option does.
+    /// # Arguments
+    /// * `score` A score in the sorted format, as returned by
+    ///   [`Csound::sort_score`](struct.Csound.html#method.sort_score).
+    /// * `extract` The extract specification, a list of instrument numbers followed by
+    ///   the first and last point to extract as `section:beat`, e.g `i 1 2 f 1:0 t 1:8`.
+    ///
+    /// As [`Csound::sort_score`](struct.Csound.html#method.sort_score), it runs on a scratch csound instance.
+    /// # Returns
+    /// The extracted score or an error if the temporary files couldn't be used.
+    pub fn extract_score(&self, score: &str, extract: &str) -> Result<String> {
+        let input = TempFile::with_content(score)?;
+        let extract = TempFile::with_content(extract)?;
+        let output = TempFile::new()?;
+        let scratch = ScratchCsound::new();
+        unsafe {
+            match csound_sys::csoundScoreExtract(
+                scratch.0,
+                input.0 as *mut _,
+                output.0 as *mut _,
+                extract.0 as *mut _,
+            ) {
+                CSOUND_STATUS::CSOUND_SUCCESS => output.read_to_string(),
+                code => Err(Error::Engine {
+                    operation: "csoundScoreExtract",
+                    code,
+                }),
+            }
+        }
+    }
+
+    /// Runs a Cscore program on a score without running a performance, this is the
+    /// "standalone Cscore" of the csound's API.
+    ///
+    /// It must be called instead of compiling and performing, on an instance which hasn't been started.
+    /// # Arguments
+    /// * `score` The input score in the sorted format, as returned by
+    ///   [`Csound::sort_score`](struct.Csound.html#method.sort_score).
+    /// * `f` The Cscore program, it reads the input score and writes the output score
+    ///   through the [`Cscore`](struct.Cscore.html) object.
+    ///
+    /// # Returns
+    /// The output score.
+    /// # Example
+    /// ```no_run
+    /// use csound::Csound;
+    ///
+    /// let cs = Csound::new();
+    /// let sorted = cs.sort_score("i 1 0 1 440\ni 2 0 1 220\ni 1 1 1 880\ne").unwrap();
+    /// // Keep the notes of the instrument 1
+    /// let score = cs
+    ///     .run_cscore(&sorted, |cscore| {
+    ///         while let Some(event) = cscore.get_event() {
+    ///             if event.opcode != 'i' || event.pfield(1) == Some(1.0) {
+    ///                 cscore.put_event(&event).unwrap();
+    ///             }
+    ///         }
+    ///     })
+    ///     .unwrap();
+    /// println!("{}", score);
+    /// ```
+    pub fn run_cscore<F>(&self, score: &str, f: F) -> Result<String>
+    where
+        F: FnOnce(&mut Cscore),
+    {
+        let input = TempFile::with_content(score)?;
+        let output = TempFile::new()?;
+        unsafe {
+            match csound_sys::csoundInitializeCscore(
+                self.engine.csound,
+                input.0 as *mut _,
+                output.0 as *mut _,
+            ) {
+                CSOUND_STATUS::CSOUND_SUCCESS => {
+                    f(&mut Cscore::new(self.engine.csound));
+                    output.read_to_string()
+                }
+                code => Err(Error::Engine {
+                    operation: "csoundInitializeCscore",
+                    code,
+                }),
+            }
+        }
+    }
+
     /// # Returns
     /// The current score time in seconds since the beginning of the performance.
     pub fn get_score_time(&self) -> f64 {
@@ -1280,8 +1588,7 @@ impl Csound {
     pub fn create_message_buffer(&self, stdout: i32) {
         unsafe {
             csound_sys::csoundCreateMessageBuffer(self.engine.csound, stdout as c_int);
-            let mut msg_buff = self.engine.use_msg_buffer.borrow_mut();
-            *msg_buff = true;
+            self.engine.use_msg_buffer.store(true, Ordering::SeqCst);
         }
     }
 
@@ -1291,8 +1598,7 @@ impl Csound {
     pub fn destroy_message_buffer(&self) {
         unsafe {
             csound_sys::csoundDestroyMessageBuffer(self.engine.csound);
-            let mut msg_buff = self.engine.use_msg_buffer.borrow_mut();
-            *msg_buff = false;
+            self.engine.use_msg_buffer.store(false, Ordering::SeqCst);
         }
     }
 
@@ -1334,51 +1640,112 @@ impl Csound {
     /// # Returns
     /// A vector with all control channels info or None if there are not control channels. see: [`ChannelInfo`](struct.ChannelInfo.html)
     pub fn list_channels(&self) -> Option<Vec<ChannelInfo>> {
+        self.channel_list().ok().filter(|list| !list.is_empty())
+    }
+
+    // Like list_channels, but it tells an empty list apart from an error.
+    pub(crate) fn channel_list(&self) -> Result<Vec<ChannelInfo>> {
         let mut ptr = ptr::null_mut() as *mut csound_sys::controlChannelInfo_t;
         let ptr2: *mut *mut csound_sys::controlChannelInfo_t = &mut ptr as *mut *mut _;
 
         unsafe {
             let count = csound_sys::csoundListChannels(self.engine.csound, ptr2) as i32;
+            if count < 0 {
+                return Err(Error::Engine {
+                    operation: "csoundListChannels",
+                    code: count,
+                });
+            }
             let mut ptr = *ptr2;
+            let mut list = Vec::with_capacity(count as usize);
+            for _ in 0..count {
+                let name = match Trampoline::ptr_to_string((*ptr).name) {
+                    Some(string) => string,
+                    None => "".into(),
+                };
 
-            if count > 0 {
-                let mut list = Vec::new();
-                for _ in 0..count {
-                    let name = match Trampoline::ptr_to_string((*ptr).name) {
-                        Some(string) => string,
-                        None => "".into(),
-                    };
-
-                    let ctype = (*ptr).type_ as i32;
-                    let hints = (*ptr).hints;
+                let ctype = (*ptr).type_ as i32;
+                let hints = (*ptr).hints;
 
-                    let attributes = match Trampoline::ptr_to_string(hints.attributes) {
-                        Some(string) => string,
-                        None => "".into(),
-                    };
+                let attributes = match Trampoline::ptr_to_string(hints.attributes) {
+                    Some(string) => string,
+                    None => "".into(),
+                };
 
-                    list.push(ChannelInfo {
-                        name,
-                        type_: ctype,
-                        hints: ChannelHints {
-                            behav: ChannelBehavior::from_u32(hints.behav as u32),
-                            dflt: hints.dflt as f64,
-                            min: hints.min as f64,
-                            max: hints.max as f64,
-                            x: hints.x as i32,
-                            y: hints.y as i32,
-                            width: hints.width as i32,
-                            height: hints.height as i32,
-                            attributes,
-                        },
-                    });
-                    ptr = ptr.add(1);
-                }
+                list.push(ChannelInfo {
+                    name,
+                    type_: ctype,
+                    hints: ChannelHints {
+                        behav: ChannelBehavior::from_u32(hints.behav as u32),
+                        dflt: hints.dflt as f64,
+                        min: hints.min as f64,
+                        max: hints.max as f64,
+                        x: hints.x as i32,
+                        y: hints.y as i32,
+                        width: hints.width as i32,
+                        height: hints.height as i32,
+                        attributes,
+                    },
+                });
+                ptr = ptr.add(1);
+            }
+            if count > 0 {
                 csound_sys::csoundDeleteChannelList(self.engine.csound, *ptr2);
-                return Some(list);
             }
-            None
+            Ok(list)
+        }
+    }
+
+    /// Captures the value of every input control and string channel.
+    ///
+    /// Output only channels, e.g meters, are skipped, since they are written by csound.
+    /// # Returns
+    /// A [`ChannelSnapshot`](struct.ChannelSnapshot.html) with the channels listed by
+    /// [`Csound::list_channels`](struct.Csound.html#method.list_channels),
+    /// or an error if csound fails to list them.
+    /// # Example
+    /// ```no_run
+    /// use csound::Csound;
+    ///
+    /// let mut cs = Csound::new();
+    /// cs.compile_csd("synth.csd").unwrap();
+    /// cs.start().unwrap();
+    /// let preset = cs.snapshot_channels().unwrap();
+    /// while !cs.perform_ksmps() {}
+    /// cs.restore_channels(&preset).unwrap();
+    /// ```
+    pub fn snapshot_channels(&self) -> Result<ChannelSnapshot> {
+        let mut snapshot = ChannelSnapshot::new();
+        for info in self
+            .channel_list()?
+            .into_iter()
+            .filter(|info| info.is_input())
+        {
+            let kind = info.kind();
+            if kind == ControlChannelType::CSOUND_CONTROL_CHANNEL {
+                let value = self.get_control_channel(&info.name)?;
+                snapshot.controls.insert(info.name, value);
+            } else if kind == ControlChannelType::CSOUND_STRING_CHANNEL {
+                let content = self.get_string_channel(&info.name);
+                snapshot.strings.insert(info.name, content);
+            }
         }
+        Ok(snapshot)
+    }
+
+    /// Writes the channel values stored in *snapshot*.
+    ///
+    /// Channels which do not exist yet are created.
+    /// see [`Csound::snapshot_channels`](struct.Csound.html#method.snapshot_channels)
+    pub fn restore_channels(&self, snapshot: &ChannelSnapshot) -> Result<()> {
+        let handle = self.shared_handle();
+        for (name, value) in &snapshot.controls {
+            handle.set_control_channel(name, *value)?;
+        }
+        for (name, content) in &snapshot.strings {
+            handle.set_string_channel(name, content)?;
+        }
+        Ok(())
     }
 
     /// Return a [`InputChannel`](struct.InputChannel.html) which represent a csound's input channel ptr.
@@ -1395,18 +1762,22 @@ impl Csound {
     ///     string data (u8 values with enough space to store
     ///     get_channel_data_size() characters, including the
     ///     NULL character at the end of the string)
+    ///  - FsigChannel:
+    ///     pvs data (a PVSDATEXT struct, see [`PvsDataExt`](struct.PvsDataExt.html))
+    ///  - VarChannel:
+    ///     variable data, a k[] or i[] array, read as an [`ArrayView`](struct.ArrayView.html)
     /// If the channel already exists, it must match the data type
-    /// (control, audio, or string)
+    /// (control, audio, string, pvs or var)
     /// # Note
     ///  Audio and String channels
     /// can only be created after calling compile(), because the
     /// storage size is not known until then.
     /// # Returns
-    /// A  Writable InputChannel on success or a Status code,
-    ///   "Not enough memory for allocating the channel" (CS_MEMORY)
-    ///   "The specified name or type is invalid" (CS_ERROR)
-    /// or, if a channel with the same name but incompatible type
-    /// already exists, the type of the existing channel.
+    /// A  Writable InputChannel on success or an [`Error`](enum.Error.html),
+    ///   "Not enough memory for allocating the channel" (CS_MEMORY status)
+    ///   "The specified name or type is invalid" (CS_ERROR status)
+    /// or [`Error::WrongChannelType`](enum.Error.html#variant.WrongChannelType) if a channel
+    /// with the same name but incompatible type already exists.
     /// * Note: to find out the type of a channel without actually
     /// creating or changing it, set 'channel_type' argument  to CSOUND_UNKNOWN_CHANNEL, so that the error
     /// value will be either the type of the channel, or CSOUND_STATUS::CSOUND_ERROR
@@ -1424,12 +1795,15 @@ impl Csound {
     /// examples. Optionally, use the channel get/set functions
     /// which are threadsafe by default.
     ///
+    /// The returned channel implements both approaches: `load`/`store` for control channels
+    /// and `lock` for audio and string channels, see [`InputChannel::lock`](struct.InputChannel.html#method.lock).
+    ///
     /// # Example
     /// ```text
     /// extern crate csound;
     /// use csound::{Csound, InputChannel, AudioChannel, StrChannel, ControlChannel};
     ///  // Creates a Csound instance
-    /// let csound = Csound::new();
+    /// let mut csound = Csound::new();
     /// csound.compile_csd(csd_filename).unwrap();
     /// csound.start();
     /// // Request a csound's input control channel
@@ -1443,7 +1817,7 @@ impl Csound {
     /// let string_channel = csound.get_input_channel::<StrChannel>("myStringChannel").unwrap();
     ///
     /// ```
-    pub fn get_input_channel<T>(&self, name: &str) -> Result<InputChannel<T>, Status>
+    pub fn get_input_channel<T>(&self, name: &str) -> Result<InputChannel<T>>
     where
         T: IsChannel,
     {
@@ -1468,19 +1842,35 @@ impl Csound {
                 bits = (controlChannelType::CSOUND_STRING_CHANNEL
                     | controlChannelType::CSOUND_INPUT_CHANNEL) as c_int;
             }
+            // The channel holds a PVSDATEXT or an ARRAYDAT struct, its size is read on access
+            ControlChannelType::CSOUND_PVS_CHANNEL => {
+                len = 1;
+                bits = (controlChannelType::CSOUND_PVS_CHANNEL
+                    | controlChannelType::CSOUND_INPUT_CHANNEL) as c_int;
+            }
+            ControlChannelType::CSOUND_VAR_CHANNEL => {
+                len = 1;
+                bits = (controlChannelType::CSOUND_VAR_CHANNEL
+                    | controlChannelType::CSOUND_INPUT_CHANNEL) as c_int;
+            }
             _ => unimplemented!(),
         }
 
         unsafe {
-            let result = Status::from(self.get_raw_channel_ptr(name, ptr, bits));
-            match result {
-                Status::CS_SUCCESS => Ok(InputChannel {
+            match self.get_raw_channel_ptr(name, ptr, bits)? {
+                CSOUND_STATUS::CSOUND_SUCCESS => Ok(InputChannel {
                     ptr: *ptr,
                     len,
+                    lock: self.get_channel_lock(name)?,
+                    name: name.to_owned(),
+                    engine: &self.engine,
                     phantom: PhantomData,
                 }),
-                Status::CS_OK(channel) => Err(Status::CS_OK(channel)),
-                result => Err(result),
+                code if code > 0 => Err(Error::WrongChannelType(name.to_owned())),
+                code => Err(Error::Engine {
+                    operation: "csoundGetChannelPtr",
+                    code,
+                }),
             }
         }
     }
@@ -1499,18 +1889,22 @@ impl Csound {
     ///     string data (u8 values with enough space to store
     ///     get_channel_data_size() characters, including the
     ///     NULL character at the end of the string)
+    ///  - FsigChannel:
+    ///     pvs data (a PVSDATEXT struct, see [`PvsDataExt`](struct.PvsDataExt.html))
+    ///  - VarChannel:
+    ///     variable data, a k[] or i[] array, read as an [`ArrayView`](struct.ArrayView.html)
     /// If the channel already exists, it must match the data type
-    /// (control, audio, or string)
+    /// (control, audio, string, pvs or var)
     /// # Note
     ///  Audio and String channels
     /// can only be created after calling compile(), because the
     /// storage size is not known until then.
     /// # Returns
-    /// A  Readable OutputChannel on success or a Status code,
-    ///   "Not enough memory for allocating the channel" (CS_MEMORY)
-    ///   "The specified name or type is invalid" (CS_ERROR)
-    /// or, if a channel with the same name but incompatible type
-    /// already exists, the type of the existing channel.
+    /// A  Readable OutputChannel on success or an [`Error`](enum.Error.html),
+    ///   "Not enough memory for allocating the channel" (CS_MEMORY status)
+    ///   "The specified name or type is invalid" (CS_ERROR status)
+    /// or [`Error::WrongChannelType`](enum.Error.html#variant.WrongChannelType) if a channel
+    /// with the same name but incompatible type already exists.
     /// * Note: to find out the type of a channel without actually
     /// creating or changing it, set 'channel_type' argument  to CSOUND_UNKNOWN_CHANNEL, so that the error
     /// value will be either the type of the channel, or CSOUND_STATUS::CSOUND_ERROR
@@ -1527,13 +1921,42 @@ impl Csound {
     /// See Top/threadsafe.c in the Csound library sources for
     /// examples. Optionally, use the channel get/set functions
     /// which are threadsafe by default.
+    ///
+    /// The returned channel implements both approaches: `load` for control channels
+    /// and `lock` for audio and string channels, see [`OutputChannel::lock`](struct.OutputChannel.html#method.lock).
+    /// [`OutputChannel::into_shared`](struct.OutputChannel.html#method.into_shared) converts it into a channel
+    /// which only exposes those functions and can be used from another thread.
+    /// ```no_run
+    /// use csound::{AudioChannel, Csound};
+    /// use std::thread;
+    ///
+    /// let mut csound = Csound::new();
+    /// csound.compile_csd("some.csd").unwrap();
+    /// csound.start().unwrap();
+    /// let mut meter = csound
+    ///     .get_output_channel::<AudioChannel>("meter")
+    ///     .unwrap()
+    ///     .into_shared();
+    /// let done = std::sync::atomic::AtomicBool::new(false);
+    /// thread::scope(|s| {
+    ///     // UI thread
+    ///     s.spawn(|| {
+    ///         while !done.load(std::sync::atomic::Ordering::Relaxed) {
+    ///             let peak = meter.lock().iter().fold(0f64, |a, b| a.max(b.abs()));
+    ///             println!("peak: {}", peak);
+    ///         }
+    ///     });
+    ///     while !csound.perform_ksmps() {}
+    ///     done.store(true, std::sync::atomic::Ordering::Relaxed);
+    /// });
+    /// ```
     /// # Example
     /// ```text
     /// extern crate csound;
     /// use csound::{Csound, OutputChannel, AudioChannel, StrChannel, ControlChannel};
     ///
     ///  // Creates a Csound instance
-    /// let csound = Csound::new();
+    /// let mut csound = Csound::new();
     /// csound.compile_csd(csd_filename).unwrap();
     /// csound.start();
     /// // Request a csound's output control channel
@@ -1547,7 +1970,7 @@ impl Csound {
     /// let string_channel = csound.get_output_channel::<StrChannel>("myStringChannel").unwrap();
     ///
     /// ```
-    pub fn get_output_channel<T>(&self, name: &str) -> Result<OutputChannel<T>, Status>
+    pub fn get_output_channel<T>(&self, name: &str) -> Result<OutputChannel<T>>
     where
         T: IsChannel,
     {
@@ -1573,48 +1996,72 @@ impl Csound {
                 bits = (controlChannelType::CSOUND_STRING_CHANNEL
                     | controlChannelType::CSOUND_OUTPUT_CHANNEL) as c_int;
             }
+            // The channel holds a PVSDATEXT or an ARRAYDAT struct, its size is read on access
+            ControlChannelType::CSOUND_PVS_CHANNEL => {
+                len = 1;
+                bits = (controlChannelType::CSOUND_PVS_CHANNEL
+                    | controlChannelType::CSOUND_OUTPUT_CHANNEL) as c_int;
+            }
+            ControlChannelType::CSOUND_VAR_CHANNEL => {
+                len = 1;
+                bits = (controlChannelType::CSOUND_VAR_CHANNEL
+                    | controlChannelType::CSOUND_OUTPUT_CHANNEL) as c_int;
+            }
             _ => unimplemented!(),
         }
 
         unsafe {
-            let result = Status::from(self.get_raw_channel_ptr(name, ptr, bits));
-            match result {
-                Status::CS_SUCCESS => Ok(OutputChannel {
+            match self.get_raw_channel_ptr(name, ptr, bits)? {
+                CSOUND_STATUS::CSOUND_SUCCESS => Ok(OutputChannel {
                     ptr: *ptr,
                     len,
+                    lock: self.get_channel_lock(name)?,
                     phantom: PhantomData,
                 }),
-                Status::CS_OK(channel) => Err(Status::CS_OK(channel)),
-                result => Err(result),
+                code if code > 0 => Err(Error::WrongChannelType(name.to_owned())),
+                code => Err(Error::Engine {
+                    operation: "csoundGetChannelPtr",
+                    code,
+                }),
             }
         }
     }
 
+    // Returns the spinlock csound uses to protect the channel *name*
+    fn get_channel_lock(&self, name: &str) -> Result<*mut c_int> {
+        let cname = CString::new(name)?;
+        unsafe {
+            Ok(csound_sys::csoundGetChannelLock(self.engine.csound, cname.as_ptr()) as *mut c_int)
+        }
+    }
+
     pub(crate) fn get_raw_channel_ptr(
         &self,
         name: &str,
         ptr: *mut *mut f64,
         channel_type: c_int,
-    ) -> c_int {
-        let cname = match CString::new(name) {
-            Ok(c) => c,
-            Err(_) => return -1,
-        };
+    ) -> Result<c_int> {
+        let cname = CString::new(name)?;
         unsafe {
-            csound_sys::csoundGetChannelPtr(self.engine.csound, ptr, cname.as_ptr(), channel_type)
+            Ok(csound_sys::csoundGetChannelPtr(
+                self.engine.csound,
+                ptr,
+                cname.as_ptr(),
+                channel_type,
+            ))
         }
     }
 
     /// Set parameters hints for a control channel.
     /// These hints have no internal function but can be used by front ends to construct GUIs or to constrain values.
     /// # Returns
-    /// CS_SUCCESS on success, or CS_ERROR on failure: the channel does not exist, is not a control channel,
+    /// Ok on success, or an error with CS_ERROR status on failure: the channel does not exist, is not a control channel,
     /// or the specified parameters are invalid or CS_MEMORY: could not allocate memory for the
-    /// channel. see: ([`Status`](enum.Status.html))
-    pub fn set_channel_hints(&self, name: &str, hint: &ChannelHints) -> Result<(), Status> {
+    /// channel. see: ([`Error::status`](enum.Error.html#method.status))
+    pub fn set_channel_hints(&self, name: &str, hint: &ChannelHints) -> Result<()> {
         let attr = &hint.attributes[..];
-        let attr = CString::new(attr).map_err(|_| Status::CS_ERROR)?;
-        let cname = CString::new(name).map_err(|_| Status::CS_ERROR)?;
+        let attr = CString::new(attr)?;
+        let cname = CString::new(name)?;
         let channel_hint = csound_sys::controlChannelHints_t {
             behav: ChannelBehavior::to_u32(&hint.behav),
             dflt: hint.dflt,
@@ -1627,14 +2074,16 @@ impl Csound {
             attributes: attr.as_ptr() as *mut c_char,
         };
         unsafe {
-            match Status::from(csound_sys::csoundSetControlChannelHints(
+            match csound_sys::csoundSetControlChannelHints(
                 self.engine.csound,
                 cname.as_ptr(),
                 channel_hint,
-            ) as i32)
-            {
-                Status::CS_SUCCESS => Ok(()),
-                status => Err(status),
+            ) {
+                CSOUND_STATUS::CSOUND_SUCCESS => Ok(()),
+                code => Err(Error::Engine {
+                    operation: "csoundSetControlChannelHints",
+                    code,
+                }),
             }
         }
     }
@@ -1642,8 +2091,8 @@ impl Csound {
     /// Returns special parameters (or None if there are not any) of a control channel.
     /// Previously set with csoundSetControlChannelHints() or the
     /// [chnparams](http://www.csounds.com/manualOLPC/chnparams.html) opcode.
-    pub fn get_channel_hints(&self, name: &str) -> Result<ChannelHints, Status> {
-        let cname = CString::new(name).map_err(|_| Status::CS_ERROR)?;
+    pub fn get_channel_hints(&self, name: &str) -> Result<ChannelHints> {
+        let cname = CString::new(name)?;
         let mut hint = csound_sys::controlChannelHints_t::default();
         unsafe {
             match csound_sys::csoundGetControlChannelHints(
@@ -1671,7 +2120,10 @@ impl Csound {
                     Ok(hints)
                 }
 
-                status => Err(Status::from(status)),
+                code => Err(Error::Engine {
+                    operation: "csoundGetControlChannelHints",
+                    code,
+                }),
             }
         }
     }
@@ -1679,10 +2131,10 @@ impl Csound {
     /// Retrieves the value of a control channel.
     /// # Arguments
     /// * `name`  The channel name.
-    /// An error message will be returned if the channel is not a control channel,
+    /// An error will be returned if the channel is not a control channel,
     /// the channel not exist or if the name is invalid.
-    pub fn get_control_channel(&self, name: &str) -> Result<f64, &'static str> {
-        let cname = CString::new(name).map_err(|_| "invalid channel name")?;
+    pub fn get_control_channel(&self, name: &str) -> Result<f64> {
+        let cname = CString::new(name)?;
         let mut err: c_int = 0;
         unsafe {
             let ret = csound_sys::csoundGetControlChannel(
@@ -1690,10 +2142,13 @@ impl Csound {
                 cname.as_ptr(),
                 &mut err as *mut _,
             ) as f64;
-            if (err) == CSOUND_STATUS::CSOUND_SUCCESS {
-                Ok(ret)
-            } else {
-                Err("channel not exist or is not a control channel")
+            match err {
+                CSOUND_STATUS::CSOUND_SUCCESS => Ok(ret),
+                code if code > 0 => Err(Error::WrongChannelType(name.to_owned())),
+                code => Err(Error::Engine {
+                    operation: "csoundGetControlChannel",
+                    code,
+                }),
             }
         }
     }
@@ -1704,6 +2159,7 @@ impl Csound {
     pub fn set_control_channel(&mut self, name: &str, value: f64) {
         let cname = CString::new(name).unwrap();
         unsafe {
+            self.engine.recorder.record(self.engine.csound, name, value);
             csound_sys::csoundSetControlChannel(self.engine.csound, cname.as_ptr(), value);
         }
     }
@@ -1795,9 +2251,10 @@ impl Csound {
 
     /// Receives a PVSDAT fout from the [*pvsout*](http://www.csounds.com/manual/html/pvsout.html) opcode.
     /// This method will return Ok on success,
-    /// [`Status::CS_ERROR`](enum.Status.html#member.CS_ERROR) if the channel name is not valid or the channel doesn't
-    /// exist or [`Status::CS_MEMORY`](enum.Status.html#member.CS_MEMORY) if the frame buffer lengths haven't the same size
-    /// as the requested table
+    /// [`Error::WrongChannelType`](enum.Error.html#variant.WrongChannelType) if the channel doesn't
+    /// exist or it is not a pvs channel, [`Error::InvalidArgument`](enum.Error.html#variant.InvalidArgument)
+    /// if the analysis size doesn't match the channel's one, or
+    /// [`Error::BufferTooSmall`](enum.Error.html#variant.BufferTooSmall) if the frame buffer can not hold `N + 2` elements.
     /// # Arguments
     /// * `name` The channel identifier.
     /// * `pvs_data` Reference to tha struct which will be filled with the pvs data.
@@ -1809,8 +2266,9 @@ impl Csound {
     /// let mut pvs = PvsDataExt::new(512);
     /// cs.get_pvs_channel("1", &mut pvs);
     /// ```
-    pub fn get_pvs_channel(&self, name: &str, pvs_data: &mut PvsDataExt) -> Result<(), Status> {
-        let cname = CString::new(name).map_err(|_| Status::CS_ERROR)?;
+    /// See [`PvsChannel`](struct.PvsChannel.html) to get only the new frames.
+    pub fn get_pvs_channel(&self, name: &str, pvs_data: &mut PvsDataExt) -> Result<()> {
+        let cname = CString::new(name)?;
         let mut ptr = ptr::null_mut() as *mut f64;
         unsafe {
             if csound_sys::csoundGetChannelPtr(
@@ -1822,54 +2280,90 @@ impl Csound {
             ) == CSOUND_STATUS::CSOUND_SUCCESS
             {
                 // Same data buffer size?
-                if (*(ptr as *mut csound_sys::PVSDATEXT)).N == pvs_data.N as c_int {
-                    let data = &mut csound_sys::PVSDATEXT::default();
-                    data.frame = pvs_data.frame.as_mut_slice().as_ptr() as *mut f32;
-                    let result = csound_sys::csoundGetPvsChannel(
-                        self.engine.csound,
-                        &mut *data,
-                        cname.as_ptr(),
-                    );
-                    match result {
-                        CSOUND_STATUS::CSOUND_SUCCESS => {
-                            pvs_data.N = data.N as u32;
-                            pvs_data.sliding = data.sliding as u32;
-                            pvs_data.NB = data.NB as i32;
-                            pvs_data.overlap = data.overlap as u32;
-                            pvs_data.winsize = data.winsize as u32;
-                            pvs_data.wintype = data.wintype as u32;
-                            pvs_data.format = data.format as u32;
-                            pvs_data.framecount = data.framecount as u32;
-                            Ok(())
-                        }
-                        err => Err(Status::from(err)),
+                let N = (*(ptr as *mut csound_sys::PVSDATEXT)).N as usize;
+                if N != pvs_data.N as usize {
+                    return Err(Error::InvalidArgument(
+                        "the analysis size doesn't match the channel's one",
+                    ));
+                }
+                if pvs_data.frame.len() < N + 2 {
+                    return Err(Error::BufferTooSmall {
+                        required: N + 2,
+                        available: pvs_data.frame.len(),
+                    });
+                }
+                let data = &mut csound_sys::PVSDATEXT::default();
+                data.frame = pvs_data.frame.as_mut_slice().as_ptr() as *mut f32;
+                let result =
+                    csound_sys::csoundGetPvsChannel(self.engine.csound, &mut *data, cname.as_ptr());
+                match result {
+                    CSOUND_STATUS::CSOUND_SUCCESS => {
+                        pvs_data.N = data.N as u32;
+                        pvs_data.sliding = data.sliding as u32;
+                        pvs_data.NB = data.NB as i32;
+                        pvs_data.overlap = data.overlap as u32;
+                        pvs_data.winsize = data.winsize as u32;
+                        pvs_data.wintype = data.wintype as u32;
+                        pvs_data.format = data.format as u32;
+                        pvs_data.framecount = data.framecount as u32;
+                        Ok(())
                     }
-                } else {
-                    Err(Status::CS_MEMORY)
+                    code => Err(Error::Engine {
+                        operation: "csoundGetPvsChannel",
+                        code,
+                    }),
                 }
             } else {
-                Err(Status::CS_ERROR)
+                Err(Error::WrongChannelType(name.to_owned()))
             }
         }
     }
 
-    pub fn set_pvs_channel(&mut self, name: &str, pvs_data: &PvsDataExt) {
+    /// Sends a PVSDAT fin to the [*pvsin*](http://www.csounds.com/manual/html/pvsin.html) opcode.
+    /// # Arguments
+    /// * `name` The channel identifier.
+    /// * `pvs_data` The frame to send, its analysis parameters must match the ones used by *pvsin*.
+    /// # Returns
+    /// [`Error::BufferTooSmall`](enum.Error.html#variant.BufferTooSmall) if the frame buffer
+    /// has less than `N + 2` elements.
+    /// # Example
+    /// ```no_run
+    /// use csound::{Csound, PvsDataExt};
+    ///
+    /// let cs = Csound::new();
+    /// let mut pvs = PvsDataExt::new(1024);
+    /// for (i, (amp, freq)) in pvs.bins_mut().enumerate() {
+    ///     *amp = 1.0 / (i as f32 + 1.0);
+    ///     *freq = 110.0 * (i as f32 + 1.0);
+    /// }
+    /// cs.set_pvs_channel("fin", &pvs).unwrap();
+    /// ```
+    pub fn set_pvs_channel(&self, name: &str, pvs_data: &PvsDataExt) -> Result<()> {
+        let cname = CString::new(name)?;
+        if pvs_data.frame.len() < pvs_data.frame_len() {
+            return Err(Error::BufferTooSmall {
+                required: pvs_data.frame_len(),
+                available: pvs_data.frame.len(),
+            });
+        }
+        let data = csound_sys::PVSDATEXT {
+            N: pvs_data.N as _,
+            sliding: pvs_data.sliding as _,
+            NB: pvs_data.NB as _,
+            overlap: pvs_data.overlap as _,
+            winsize: pvs_data.winsize as _,
+            wintype: pvs_data.wintype as _,
+            format: pvs_data.format as _,
+            framecount: pvs_data.framecount as _,
+            frame: pvs_data.frame.as_ptr() as *mut f32,
+        };
         unsafe {
-            let cname = CString::new(name);
-            if let Ok(cname) = cname {
-                let cname = cname;
-                let data = &mut csound_sys::PVSDATEXT {
-                    N: pvs_data.N as _,
-                    sliding: pvs_data.sliding as _,
-                    NB: pvs_data.NB as _,
-                    overlap: pvs_data.overlap as _,
-                    winsize: pvs_data.winsize as _,
-                    wintype: pvs_data.wintype as _,
-                    format: pvs_data.format as _,
-                    framecount: pvs_data.framecount as _,
-                    frame: pvs_data.frame.as_slice().as_ptr() as *mut f32,
-                };
-                csound_sys::csoundSetPvsChannel(self.engine.csound, &*data, cname.as_ptr());
+            match csound_sys::csoundSetPvsChannel(self.engine.csound, &data, cname.as_ptr()) {
+                CSOUND_STATUS::CSOUND_SUCCESS => Ok(()),
+                code => Err(Error::Engine {
+                    operation: "csoundSetPvsChannel",
+                    code,
+                }),
             }
         }
     }
@@ -1882,20 +2376,26 @@ impl Csound {
     /// ```no_run
     /// use csound::Csound;
     ///
-    /// let cs = Csound::new();
+    /// let mut cs = Csound::new();
     /// let pFields = [1.0, 1.0, 5.0];
     /// while cs.perform_ksmps() == false {
     ///     cs.send_score_event('i', &pFields);
     /// }
     /// ```
-    pub fn send_score_event(&self, event_type: char, pfields: &[f64]) -> Status {
+    pub fn send_score_event(&mut self, event_type: char, pfields: &[f64]) -> Result<()> {
         unsafe {
-            Status::from(csound_sys::csoundScoreEvent(
+            match csound_sys::csoundScoreEvent(
                 self.engine.csound,
                 event_type as c_char,
                 pfields.as_ptr() as *const c_double,
                 pfields.len() as c_long,
-            ) as i32)
+            ) {
+                CSOUND_STATUS::CSOUND_SUCCESS => Ok(()),
+                code => Err(Error::Engine {
+                    operation: "csoundScoreEvent",
+                    code,
+                }),
+            }
         }
     }
 
@@ -1904,26 +2404,32 @@ impl Csound {
     /// but at absolute time with respect to the start of performance,
     /// or from an offset set with *time_offset*
     pub fn send_score_event_absolute(
-        &self,
+        &mut self,
         event_type: char,
         pfields: &[f64],
         time_offset: f64,
-    ) -> Status {
+    ) -> Result<()> {
         unsafe {
-            Status::from(csound_sys::csoundScoreEventAbsolute(
+            match csound_sys::csoundScoreEventAbsolute(
                 self.engine.csound,
                 event_type as c_char,
                 pfields.as_ptr() as *const c_double,
                 pfields.len() as c_long,
                 time_offset as c_double,
-            ) as i32)
+            ) {
+                CSOUND_STATUS::CSOUND_SUCCESS => Ok(()),
+                code => Err(Error::Engine {
+                    operation: "csoundScoreEventAbsolute",
+                    code,
+                }),
+            }
         }
     }
 
     /// Asynchronous version of [`Csound::send_score_event`](struct.Csound.html#method.send_score_event)
     ///
-    /// As this function asynchronous, we can't return [Status] immediately here.
-    pub fn send_score_event_async(&self, event_type: char, pfields: &[f64]) {
+    /// As this function asynchronous, we can't return an error immediately here.
+    pub fn send_score_event_async(&mut self, event_type: char, pfields: &[f64]) {
         unsafe {
             csound_sys::csoundScoreEventAsync(
                 self.engine.csound,
@@ -1936,9 +2442,9 @@ impl Csound {
 
     /// Asynchronous version of [`Csound::send_score_event_absolute`](struct.Csound.html#method.send_score_event_absolute)
     ///
-    /// As this function asynchronous, we can't return [Status] immediately here.
+    /// As this function asynchronous, we can't return an error immediately here.
     pub fn send_score_event_absolute_async(
-        &self,
+        &mut self,
         event_type: char,
         pfields: &[f64],
         time_offset: f64,
@@ -1959,13 +2465,13 @@ impl Csound {
     /// ```no_run
     /// use csound::Csound;
     ///
-    /// let cs = Csound::new();
+    /// let mut cs = Csound::new();
     /// let pFields = [1.0, 1.0, 5.0];
     /// while cs.perform_ksmps() == false {
     ///     cs.send_input_message("i 2 0 0.75  1");
     /// }
     /// ```
-    pub fn send_input_message(&self, message: &str) -> Result<(), NulError> {
+    pub fn send_input_message(&mut self, message: &str) -> Result<()> {
         let cmessage = CString::new(message)?;
         unsafe {
             csound_sys::csoundInputMessage(self.engine.csound, cmessage.as_ptr() as *const c_char);
@@ -1974,7 +2480,7 @@ impl Csound {
     }
 
     /// Asynchronous version of [`Csound::send_input_message`](struct.Csound.html#method.send_input_message)
-    pub fn send_input_message_async(&self, message: &str) -> Result<(), NulError> {
+    pub fn send_input_message_async(&mut self, message: &str) -> Result<()> {
         let cmessage = CString::new(message)?;
         unsafe {
             csound_sys::csoundInputMessageAsync(
@@ -1985,6 +2491,49 @@ impl Csound {
         }
     }
 
+    /// Sends a typed score event.
+    ///
+    /// Events whose p-fields are all numbers are sent with
+    /// [`Csound::send_score_event`](struct.Csound.html#method.send_score_event),
+    /// events with string p-fields or named instruments are sent as line events with
+    /// [`Csound::send_input_message`](struct.Csound.html#method.send_input_message).
+    /// # Returns
+    /// An `InvalidArgument` error if the event can not be written as a score statement,
+    /// see [`ScoreEvent::validate`](enum.ScoreEvent.html#method.validate).
+    /// # Example
+    /// ```no_run
+    /// use csound::{Csound, ScoreEvent};
+    ///
+    /// let mut cs = Csound::new();
+    /// cs.compile_csd("synth.csd").unwrap();
+    /// cs.start().unwrap();
+    /// let table = ScoreEvent::ftable(1, 0.0, 0, 1).arg("sample.wav").args(&[0.0, 0.0, 0.0]);
+    /// cs.send_event(&table.build()).unwrap();
+    /// cs.send_event(&ScoreEvent::instr("Lead", 0.0, 2.0).arg(440.0).build()).unwrap();
+    /// while !cs.perform_ksmps() {}
+    /// ```
+    pub fn send_event(&mut self, event: &ScoreEvent) -> Result<()> {
+        event.validate()?;
+        match event.numeric_pfields() {
+            Some(pfields) => self.send_score_event(event.opcode(), &pfields),
+            None => self.send_input_message(&event.to_string()),
+        }
+    }
+
+    /// Asynchronous version of [`Csound::send_event`](struct.Csound.html#method.send_event)
+    ///
+    /// Only the validation errors are returned, the event is inserted later by csound.
+    pub fn send_event_async(&mut self, event: &ScoreEvent) -> Result<()> {
+        event.validate()?;
+        match event.numeric_pfields() {
+            Some(pfields) => {
+                self.send_score_event_async(event.opcode(), &pfields);
+                Ok(())
+            }
+            None => self.send_input_message_async(&event.to_string()),
+        }
+    }
+
     /// Kills off one or more running instances of an instrument.
     /// # Arguments
     /// * `instr` The numeric identifier of the instrument.
@@ -2000,16 +2549,22 @@ impl Csound {
         name: Option<&str>,
         mode: u32,
         allow_release: bool,
-    ) -> Status {
-        let cname = CString::new(name.unwrap_or_else(|| "")).unwrap();
+    ) -> Result<()> {
+        let cname = CString::new(name.unwrap_or_else(|| ""))?;
         unsafe {
-            Status::from(csound_sys::csoundKillInstance(
+            match csound_sys::csoundKillInstance(
                 self.engine.csound,
                 instr as c_double,
                 cname.as_ptr() as *mut c_char,
                 mode as c_int,
                 allow_release as c_int,
-            ) as i32)
+            ) {
+                CSOUND_STATUS::CSOUND_SUCCESS => Ok(()),
+                code => Err(Error::Engine {
+                    operation: "csoundKillInstance",
+                    code,
+                }),
+            }
         }
     }
 
@@ -2024,27 +2579,27 @@ impl Csound {
 
     /* Engine general Table function  implementations **************************************************************************************** */
 
-    /// Returns the length of a function table (not including the guard point), or an error
-    /// message if the table doens't exist.
+    /// Returns the length of a function table (not including the guard point), or
+    /// [`Error::TableNotFound`](enum.Error.html#variant.TableNotFound) if the table doens't exist.
     /// # Arguments
     /// * `table` The function table identifier.
-    pub fn table_length(&self, table: u32) -> Result<usize, &'static str> {
+    pub fn table_length(&self, table: u32) -> Result<usize> {
         unsafe {
             let value = csound_sys::csoundTableLength(self.engine.csound, table as c_int) as i32;
             if value > 0 {
                 Ok(value as usize)
             } else {
-                Err("Table doesn't exist")
+                Err(Error::TableNotFound(table))
             }
         }
     }
 
     /// Returns the value of a slot in a function table.
-    /// If the Table or index are not valid, an error message will be returned.
+    /// If the Table or index are not valid, an error will be returned.
     /// # Arguments
     /// * `table` The function table identifier.
     /// * `index` The value at table[index] which will be read.
-    pub fn table_get(&self, table: u32, index: u32) -> Result<f64, &'static str> {
+    pub fn table_get(&self, table: u32, index: u32) -> Result<f64> {
         unsafe {
             let size = self.table_length(table)?;
             if index < size as u32 {
@@ -2053,7 +2608,10 @@ impl Csound {
                         as f64,
                 )
             } else {
-                Err("index out of range")
+                Err(Error::IndexOutOfRange {
+                    index: index as usize,
+                    len: size,
+                })
             }
         }
     }
@@ -2063,8 +2621,8 @@ impl Csound {
     /// * `table` The function table identifier.
     /// * `index` The slot at table[index] where value will be added.
     /// # Returns
-    /// An error message if the index or table are no valid
-    pub fn table_set(&mut self, table: u32, index: u32, value: f64) -> Result<(), &'static str> {
+    /// An error if the index or table are no valid
+    pub fn table_set(&mut self, table: u32, index: u32, value: f64) -> Result<()> {
         unsafe {
             let size = self.table_length(table)?;
             if index < size as u32 {
@@ -2076,7 +2634,10 @@ impl Csound {
                 );
                 Ok(())
             } else {
-                Err("index out of range")
+                Err(Error::IndexOutOfRange {
+                    index: index as usize,
+                    len: size,
+                })
             }
         }
     }
@@ -2085,13 +2646,16 @@ impl Csound {
     /// # Arguments
     /// * `table` The function table identifier.
     /// # Returns
-    /// An error message if the table doesn't exist or the passed slice
+    /// An error if the table doesn't exist or the passed slice
     /// doesn't have enough memory to content the table values.
-    pub fn table_copy_out(&self, table: u32, output: &mut [f64]) -> Result<(), &'static str> {
+    pub fn table_copy_out(&self, table: u32, output: &mut [f64]) -> Result<()> {
         unsafe {
             let size = self.table_length(table)?;
             if output.len() < size {
-                Err("Not enough memory to copy the table")
+                Err(Error::BufferTooSmall {
+                    required: size,
+                    available: output.len(),
+                })
             } else {
                 csound_sys::csoundTableCopyOut(
                     self.engine.csound,
@@ -2104,19 +2668,23 @@ impl Csound {
     }
 
     /// Asynchronous version of [`Csound:: table_copy_out`](struct.Csound.html#method.table_copy_out)
-    pub fn table_copy_out_async(&self, table: u32, output: &mut [f64]) -> Result<(), &'static str> {
-        unsafe {
-            let size = self.table_length(table)?;
-            if output.len() < size {
-                Err("Not enough memory to copy the table")
-            } else {
-                csound_sys::csoundTableCopyOutAsync(
-                    self.engine.csound,
-                    table as c_int,
-                    output.as_ptr() as *mut c_double,
-                );
-                Ok(())
-            }
+    /// # Safety
+    /// csound copies the table later, at the beginning of a control period, so *output*
+    /// must stay valid and must not be accessed until that period has been performed.
+    pub unsafe fn table_copy_out_async(&self, table: u32, output: &mut [f64]) -> Result<()> {
+        let size = self.table_length(table)?;
+        if output.len() < size {
+            Err(Error::BufferTooSmall {
+                required: size,
+                available: output.len(),
+            })
+        } else {
+            csound_sys::csoundTableCopyOutAsync(
+                self.engine.csound,
+                table as c_int,
+                output.as_ptr() as *mut c_double,
+            );
+            Ok(())
         }
     }
 
@@ -2125,12 +2693,15 @@ impl Csound {
     /// * `table` The function table identifier.
     /// * `src` Slice with the values to be copied into the function table
     /// # Returns
-    /// An error message if the table doesn't exist or doesn't have enough
+    /// An error if the table doesn't exist or doesn't have enough
     /// capacity.
-    pub fn table_copy_in(&mut self, table: u32, src: &[f64]) -> Result<(), &'static str> {
+    pub fn table_copy_in(&mut self, table: u32, src: &[f64]) -> Result<()> {
         let size = self.table_length(table)?;
         if size < src.len() {
-            Err("Table doesn't have enough capacity")
+            Err(Error::BufferTooSmall {
+                required: src.len(),
+                available: size,
+            })
         } else {
             unsafe {
                 csound_sys::csoundTableCopyIn(
@@ -2144,19 +2715,23 @@ impl Csound {
     }
 
     /// Asynchronous version of [`Csound:: table_copy_in`](struct.Csound.html#method.table_copy_in)
-    pub fn table_copy_in_async(&mut self, table: u32, src: &[f64]) -> Result<(), &'static str> {
+    /// # Safety
+    /// csound copies the values later, at the beginning of a control period, so *src*
+    /// must stay valid and must not be modified until that period has been performed.
+    pub unsafe fn table_copy_in_async(&mut self, table: u32, src: &[f64]) -> Result<()> {
         let size = self.table_length(table)?;
         if size < src.len() {
-            Err("Table doesn't have enough capacity")
+            Err(Error::BufferTooSmall {
+                required: src.len(),
+                available: size,
+            })
         } else {
-            unsafe {
-                csound_sys::csoundTableCopyInAsync(
-                    self.engine.csound,
-                    table as c_int,
-                    src.as_ptr() as *mut c_double,
-                );
-                Ok(())
-            }
+            csound_sys::csoundTableCopyInAsync(
+                self.engine.csound,
+                table as c_int,
+                src.as_ptr() as *mut c_double,
+            );
+            Ok(())
         }
     }
 
@@ -2171,7 +2746,7 @@ impl Csound {
     /// ```no_run
     /// use csound::Csound;
     ///
-    /// let cs = Csound::new();
+    /// let mut cs = Csound::new();
     /// cs.compile_csd("some.csd");
     /// cs.start().unwrap();
     /// while cs.perform_ksmps() == false {
@@ -2359,9 +2934,9 @@ impl Csound {
     /// Simple linear congruential random number generator: seed = seed * 742938285 % 2147483647
     /// # Returns
     /// The next number from the pseudo-random sequence, in the range 1 to 2147483646.
-    /// if the value of seed is not in the range 1 to 2147483646 an error message will
+    /// if the value of seed is not in the range 1 to 2147483646 an error will
     /// be returned.
-    pub fn get_rand31(seed: &mut u32) -> Result<u32, &'static str> {
+    pub fn get_rand31(seed: &mut u32) -> Result<u32> {
         unsafe {
             match seed {
                 1..=2_147_483_646 => {
@@ -2369,7 +2944,7 @@ impl Csound {
                     let res = csound_sys::csoundRand31(ptr as *mut c_int) as u32;
                     Ok(res)
                 }
-                _ => Err("invalid seed value"),
+                _ => Err(Error::InvalidArgument("invalid seed value")),
             }
         }
     }
@@ -2447,9 +3022,9 @@ impl Csound {
 
     /// Sets a function that is called to obtain a list of audio devices.
     /// This should be set by rtaudio modules and should not be set by hosts.
-    pub fn audio_device_list_callback<'c, F>(&self, f: F)
+    pub fn audio_device_list_callback<F>(&self, f: F)
     where
-        F: FnMut(CsAudioDevice) + 'c,
+        F: FnMut(CsAudioDevice) + Send + 'static,
     {
         unsafe {
             (*(csound_sys::csoundGetHostData(self.engine.csound) as *mut CallbackHandler))
@@ -2464,9 +3039,9 @@ impl Csound {
     /// # Arguments
     /// * `user_func` A function/closure which will receive a reference
     ///  to a RtAudioParams struct.
-    pub fn play_open_audio_callback<'c, F>(&self, f: F)
+    pub fn play_open_audio_callback<F>(&self, f: F)
     where
-        F: FnMut(&RtAudioParams) -> Status + 'c,
+        F: FnMut(&RtAudioParams) -> Status + Send + 'static,
     {
         unsafe {
             (*(csound_sys::csoundGetHostData(self.engine.csound) as *mut CallbackHandler))
@@ -2478,9 +3053,9 @@ impl Csound {
     /// Sets a function to be called by Csound for opening real-time audio recording.
     /// This callback is used to inform the user about the current audio device Which
     /// Csound will use for opening realtime audio recording. You have to return Status::CS_SUCCESS
-    pub fn rec_open_audio_callback<'c, F>(&self, f: F)
+    pub fn rec_open_audio_callback<F>(&self, f: F)
     where
-        F: FnMut(&RtAudioParams) -> Status + 'c,
+        F: FnMut(&RtAudioParams) -> Status + Send + 'static,
     {
         unsafe {
             (*(csound_sys::csoundGetHostData(self.engine.csound) as *mut CallbackHandler))
@@ -2493,9 +3068,9 @@ impl Csound {
     /// A reference to a buffer with audio samples is passed
     /// to the user function in the callback. These samples have to be processed and sent
     /// to a proper audio device.
-    pub fn rt_audio_play_callback<'c, F>(&self, f: F)
+    pub fn rt_audio_play_callback<F>(&self, f: F)
     where
-        F: FnMut(&[f64]) + 'c,
+        F: FnMut(&[f64]) + Send + 'static,
     {
         unsafe {
             (*(csound_sys::csoundGetHostData(self.engine.csound) as *mut CallbackHandler))
@@ -2507,9 +3082,9 @@ impl Csound {
     /// Sets a function to be called by Csound for performing real-time audio recording.
     /// With this callback the user can fill a buffer with samples from a custom
     /// audio module, and pass it into csound.
-    pub fn rt_audio_rec_callback<'c, F>(&self, f: F)
+    pub fn rt_audio_rec_callback<F>(&self, f: F)
     where
-        F: FnMut(&mut [f64]) -> usize + 'c,
+        F: FnMut(&mut [f64]) -> usize + Send + 'static,
     {
         unsafe {
             (*(csound_sys::csoundGetHostData(self.engine.csound) as *mut CallbackHandler))
@@ -2519,9 +3094,9 @@ impl Csound {
     }
 
     /// Indicates to the user when csound has closed the rtaudio device.
-    pub fn rt_close_callback<'c, F>(&self, f: F)
+    pub fn rt_close_callback<F>(&self, f: F)
     where
-        F: FnMut() + 'c,
+        F: FnMut() + Send + 'static,
     {
         unsafe {
             (*(csound_sys::csoundGetHostData(self.engine.csound) as *mut CallbackHandler))
@@ -2534,9 +3109,9 @@ impl Csound {
     /// This facility can be used to ensure a function is called synchronously
     /// before every csound control buffer processing.
     /// It is important to make sure no blocking operations are performed in the callback.
-    pub fn sense_event_callback<'c, F>(&self, f: F)
+    pub fn sense_event_callback<F>(&self, f: F)
     where
-        F: FnMut() + 'c,
+        F: FnMut() + Send + 'static,
     {
         unsafe {
             (*(csound_sys::csoundGetHostData(self.engine.csound) as *mut CallbackHandler))
@@ -2545,12 +3120,219 @@ impl Csound {
         }
     }
 
-    /*fn cscore_callback<'c, F>(&mut self, f:F)
-        where F: FnMut() + 'c
+    /// Calls *f* with the new value of the output control channel *name* whenever it changes.
+    ///
+    /// The watched channels are read once per control period from the sense event callback, so *f*
+    /// runs in the performance thread and it must not block. *f* is called with the channel's value on the
+    /// first control period, and afterwards only when the value changes.
+    /// See [`Csound::watch_channel_with`](struct.Csound.html#method.watch_channel_with) to set a threshold or a maximum rate.
+    /// # Returns
+    /// An id to remove the observer with [`Csound::unwatch_channel`](struct.Csound.html#method.unwatch_channel).
+    /// # Example
+    /// ```no_run
+    /// use csound::Csound;
+    /// use std::sync::mpsc;
+    ///
+    /// let mut cs = Csound::new();
+    /// cs.compile_csd("meter.csd").unwrap();
+    /// cs.start().unwrap();
+    /// let (tx, rx) = mpsc::channel();
+    /// cs.watch_channel("rms", move |v| tx.send(v).unwrap()).unwrap();
+    /// while !cs.perform_ksmps() {
+    ///     for rms in rx.try_iter() {
+    ///         println!("rms: {}", rms);
+    ///     }
+    /// }
+    /// ```
+    pub fn watch_channel<F>(&self, name: &str, f: F) -> Result<WatchId>
+    where
+        F: FnMut(f64) + Send + 'static,
+    {
+        self.watch_channel_with(name, WatchOptions::default(), f)
+    }
+
+    /// Calls *f* with the new value of the output control channel *name* when it changes
+    /// according to *options*.
+    /// # Arguments
+    /// * `name` The channel name.
+    /// * `options` The change threshold and the maximum notification rate.
+    /// * `f` The observer.
+    /// # Returns
+    /// An id to remove the observer, or an [`Error::InvalidArgument`](enum.Error.html#variant.InvalidArgument) if
+    /// the options are not valid.
+    /// # Example
+    /// ```no_run
+    /// use csound::{Csound, WatchOptions};
+    ///
+    /// let mut cs = Csound::new();
+    /// cs.compile_csd("meter.csd").unwrap();
+    /// cs.start().unwrap();
+    /// let options = WatchOptions::new().threshold(0.001).max_rate(30.0);
+    /// let id = cs.watch_channel_with("rms", options, |v| println!("rms: {}", v)).unwrap();
+    /// while !cs.perform_ksmps() {}
+    /// cs.unwatch_channel(id);
+    /// ```
+    pub fn watch_channel_with<F>(&self, name: &str, options: WatchOptions, f: F) -> Result<WatchId>
+    where
+        F: FnMut(f64) + Send + 'static,
     {
-        self.engine.inner.handler.callbacks.cscore_cb = Some(Box::new(f));
-        self.engine.enable_callback(CSCORE_CB);
-    }*/
+        unsafe {
+            let callbacks = &mut (*(csound_sys::csoundGetHostData(self.engine.csound)
+                as *mut CallbackHandler))
+                .callbacks;
+            let id = callbacks.watchers.add(name, options, f)?;
+            callbacks.register_sense_event_cb(self.engine.csound);
+            Ok(id)
+        }
+    }
+
+    /// Removes a channel observer.
+    /// # Returns
+    /// false if there is not an observer with this *id*.
+    pub fn unwatch_channel(&self, id: WatchId) -> bool {
+        unsafe {
+            (*(csound_sys::csoundGetHostData(self.engine.csound) as *mut CallbackHandler))
+                .callbacks
+                .watchers
+                .remove(id)
+        }
+    }
+
+    /// Creates a stream of audio from the host into the input audio channel *name*.
+    ///
+    /// The returned [`AudioSender`](struct.AudioSender.html) can be moved to any thread, its samples are copied
+    /// into the channel once per control period from the sense event callback.
+    /// # Arguments
+    /// * `name` The channel name.
+    /// * `capacity` The ring buffer size in samples, it should hold several control periods.
+    /// # Example
+    /// ```no_run
+    /// use csound::Csound;
+    /// use std::thread;
+    ///
+    /// let mut cs = Csound::new();
+    /// cs.compile_csd("fx.csd").unwrap();
+    /// cs.start().unwrap();
+    /// let mut sender = cs.stream_to_audio_channel("input", 8192).unwrap();
+    /// thread::spawn(move || {
+    ///     let block = vec![0.1; 256];
+    ///     loop {
+    ///         if sender.free() >= block.len() {
+    ///             sender.write(&block);
+    ///         }
+    ///         thread::sleep(std::time::Duration::from_millis(1));
+    ///     }
+    /// });
+    /// while !cs.perform_ksmps() {}
+    /// ```
+    pub fn stream_to_audio_channel(&self, name: &str, capacity: usize) -> Result<AudioSender> {
+        unsafe {
+            let callbacks = &mut (*(csound_sys::csoundGetHostData(self.engine.csound)
+                as *mut CallbackHandler))
+                .callbacks;
+            let sender = callbacks.audio_streams.add_input(name, capacity)?;
+            callbacks.register_sense_event_cb(self.engine.csound);
+            Ok(sender)
+        }
+    }
+
+    /// Creates a stream of audio from the output audio channel *name* to the host.
+    ///
+    /// The returned [`AudioReceiver`](struct.AudioReceiver.html) can be moved to any thread, the channel samples
+    /// are copied into it once per control period from the sense event callback.
+    /// # Arguments
+    /// * `name` The channel name.
+    /// * `capacity` The ring buffer size in samples, it should hold several control periods.
+    /// # Example
+    /// ```no_run
+    /// use csound::Csound;
+    /// use std::thread;
+    ///
+    /// let mut cs = Csound::new();
+    /// cs.compile_csd("synth.csd").unwrap();
+    /// cs.start().unwrap();
+    /// let mut receiver = cs.stream_from_audio_channel("out", 8192).unwrap();
+    /// thread::spawn(move || {
+    ///     let mut block = vec![0.0; 1024];
+    ///     loop {
+    ///         let read = receiver.read(&mut block);
+    ///         println!("{} samples, {} overruns", read, receiver.overruns());
+    ///         thread::sleep(std::time::Duration::from_millis(10));
+    ///     }
+    /// });
+    /// while !cs.perform_ksmps() {}
+    /// ```
+    pub fn stream_from_audio_channel(&self, name: &str, capacity: usize) -> Result<AudioReceiver> {
+        unsafe {
+            let callbacks = &mut (*(csound_sys::csoundGetHostData(self.engine.csound)
+                as *mut CallbackHandler))
+                .callbacks;
+            let receiver = callbacks.audio_streams.add_output(name, capacity)?;
+            callbacks.register_sense_event_cb(self.engine.csound);
+            Ok(receiver)
+        }
+    }
+
+    /// # Returns
+    /// The [`Scheduler`](struct.Scheduler.html) of this instance, which runs score events and closures
+    /// at future performance times. All the schedulers returned share the same tasks.
+    pub fn scheduler(&self) -> Scheduler {
+        unsafe {
+            let callbacks = &mut (*(csound_sys::csoundGetHostData(self.engine.csound)
+                as *mut CallbackHandler))
+                .callbacks;
+            let queue = callbacks
+                .scheduler
+                .get_or_insert_with(|| Arc::new(Mutex::new(TaskQueue::new(&self.engine))))
+                .clone();
+            callbacks.register_sense_event_cb(self.engine.csound);
+            Scheduler::new(queue)
+        }
+    }
+
+    /// Sets a Cscore program which processes the score before the performance.
+    ///
+    /// The callback is only called if the `-C` option is set. It receives a
+    /// [`Cscore`](struct.Cscore.html) which reads the events of the sorted score, and the events
+    /// it writes form the score which csound performs.
+    /// The callback is retained after [`Csound::reset`](struct.Csound.html#method.reset).
+    /// # Example
+    /// ```no_run
+    /// use csound::Csound;
+    ///
+    /// let mut cs = Csound::new();
+    /// cs.set_option("-C").unwrap();
+    /// // Play every section twice, the repetition an octave up
+    /// cs.cscore_callback(|cscore| loop {
+    ///     let section = cscore.get_section();
+    ///     if section.is_empty() {
+    ///         cscore.put_string("e").unwrap();
+    ///         break;
+    ///     }
+    ///     cscore.put_events(&section).unwrap();
+    ///     cscore.put_string("s").unwrap();
+    ///     for mut event in section {
+    ///         if event.opcode == 'i' && event.pfields.len() > 3 {
+    ///             event.pfields[3] *= 2.0;
+    ///         }
+    ///         cscore.put_event(&event).unwrap();
+    ///     }
+    ///     cscore.put_string("s").unwrap();
+    /// });
+    /// cs.compile_csd("synth.csd").unwrap();
+    /// cs.start().unwrap();
+    /// while !cs.perform_ksmps() {}
+    /// ```
+    pub fn cscore_callback<F>(&self, f: F)
+    where
+        F: FnMut(&mut Cscore) + Send + 'static,
+    {
+        unsafe {
+            (*(csound_sys::csoundGetHostData(self.engine.csound) as *mut CallbackHandler))
+                .callbacks
+                .set_cscore_cb(self.engine.csound, f);
+        }
+    }
 
     /// Sets a callback which will be called by csound to print an informational message.
     /// # Arguments
@@ -2563,9 +3345,16 @@ impl Csound {
     /// let mut cs = Csound::new();
     /// cs.message_string_callback(|att: MessageType, message: &str| print!("{}", message));
     /// ```
-    pub fn message_string_callback<'c, F>(&'c self, f: F)
+    /// The closure is stored by csound, so it can not borrow local data:
+    /// ```compile_fail
+    /// use csound::{Csound, MessageType};
+    /// let cs = Csound::new();
+    /// let mut log = Vec::new();
+    /// cs.message_string_callback(|_: MessageType, message: &str| log.push(message.to_owned()));
+    /// ```
+    pub fn message_string_callback<F>(&self, f: F)
     where
-        F: FnMut(MessageType, &str) + 'c,
+        F: FnMut(MessageType, &str) + Send + 'static,
     {
         unsafe {
             (*(csound_sys::csoundGetHostData(self.engine.csound) as *mut CallbackHandler))
@@ -2574,9 +3363,9 @@ impl Csound {
         }
     }
 
-    /*fn keyboard_callback<'c, F>(&self, f: F)
+    /*fn keyboard_callback<F>(&self, f: F)
     where
-        F: FnMut() -> char + 'c,
+        F: FnMut() -> char + Send + 'static,
     {
         unsafe{(&mut *(csound_sys::csoundGetHostData(self.engine.csound) as *mut CallbackHandler)).callbacks.keyboard_cb = Some(Box::new(f));}
         self.enable_callback(KEYBOARD_CB);
@@ -2586,8 +3375,8 @@ impl Csound {
     /// # Arguments
     /// * ´f´ Function which implement the FnMut trait. The invalue opcode will trigger this callback passing
     /// the channel name which requiere the data. This function/closure have to return the data which will be
-    /// passed to that specific channel if not only return ChannelData::CS_UNKNOWN_CHANNEL. Only *String* and *control* Channels
-    /// are supported.
+    /// passed to that specific channel if not only return ChannelData::CS_UNKNOWN_CHANNEL. The data is written only if its variant
+    /// matches the invalue's argument: *control*, *String*, *audio* (up to ksmps samples) or *pvs* (same analysis size).
     /// # Example
     /// ```
     /// use csound::{Csound, ChannelData};
@@ -2602,9 +3391,9 @@ impl Csound {
     /// let mut cs = Csound::new();
     /// cs.input_channel_callback(input_channel);
     /// ```
-    pub fn input_channel_callback<'c, F>(&self, f: F)
+    pub fn input_channel_callback<F>(&self, f: F)
     where
-        F: FnMut(&str) -> ChannelData + 'c,
+        F: FnMut(&str) -> ChannelData + Send + 'static,
     {
         unsafe {
             (*(csound_sys::csoundGetHostData(self.engine.csound) as *mut CallbackHandler))
@@ -2616,8 +3405,8 @@ impl Csound {
     /// Sets the function which will be called whenever the [*outvalue*](http://www.csounds.com/manual/html/outvalue.html) opcode is used.
     /// # Arguments
     /// * ´f´ Function which implement the FnMut trait. The outvalue opcode will trigger this callback passing
-    /// the channel ##name and the channel's output data encoded in the ChannelData. Only *String* and *control* Channels
-    /// are supported.
+    /// the channel ##name and the channel's output data encoded in the ChannelData: *control*, *String*, *audio* (ksmps samples)
+    /// or *pvs* data.
     /// # Example
     /// ```
     /// use csound::{Csound, ChannelData};
@@ -2628,9 +3417,9 @@ impl Csound {
     /// let mut cs = Csound::new();
     /// cs.output_channel_callback(output_channel);
     /// ```
-    pub fn output_channel_callback<'c, F>(&self, f: F)
+    pub fn output_channel_callback<F>(&self, f: F)
     where
-        F: FnMut(&str, ChannelData) + 'c,
+        F: FnMut(&str, ChannelData) + Send + 'static,
     {
         unsafe {
             (*(csound_sys::csoundGetHostData(self.engine.csound) as *mut CallbackHandler))
@@ -2644,9 +3433,9 @@ impl Csound {
     /// The following information is passed to the callback:
     /// ## `file_info`
     /// A [`FileInfo`](struct.FileInfo.html) struct containing the relevant file info.
-    pub fn file_open_callback<'c, F>(&self, f: F)
+    pub fn file_open_callback<F>(&self, f: F)
     where
-        F: FnMut(&FileInfo) + 'c,
+        F: FnMut(&FileInfo) + Send + 'static,
     {
         unsafe {
             (*(csound_sys::csoundGetHostData(self.engine.csound) as *mut CallbackHandler))
@@ -2660,9 +3449,9 @@ impl Csound {
     /// # Arguments
     /// * `user_func` A function/closure which will receive a reference
     ///  to a str with the device name.
-    pub fn midi_in_open_callback<'c, F>(&self, f: F)
+    pub fn midi_in_open_callback<F>(&self, f: F)
     where
-        F: FnMut(&str) + 'c,
+        F: FnMut(&str) + Send + 'static,
     {
         unsafe {
             (*(csound_sys::csoundGetHostData(self.engine.csound) as *mut CallbackHandler))
@@ -2676,9 +3465,9 @@ impl Csound {
     /// # Arguments
     /// * `user_func` A function/closure which will receive a reference
     ///  to a str with the device name.
-    pub fn midi_out_open_callback<'c, F>(&self, f: F)
+    pub fn midi_out_open_callback<F>(&self, f: F)
     where
-        F: FnMut(&str) + 'c,
+        F: FnMut(&str) + Send + 'static,
     {
         unsafe {
             (*(csound_sys::csoundGetHostData(self.engine.csound) as *mut CallbackHandler))
@@ -2690,9 +3479,9 @@ impl Csound {
     /// Sets a function to be called by Csound for reading from real time MIDI input.
     /// A reference to a buffer with audio samples is passed
     /// to the user function in the callback.  The callback have to return the number of elements written to the buffer.
-    pub fn midi_read_callback<'c, F>(&self, f: F)
+    pub fn midi_read_callback<F>(&self, f: F)
     where
-        F: FnMut(&mut [u8]) -> usize + 'c,
+        F: FnMut(&mut [u8]) -> usize + Send + 'static,
     {
         unsafe {
             (*(csound_sys::csoundGetHostData(self.engine.csound) as *mut CallbackHandler))
@@ -2705,9 +3494,9 @@ impl Csound {
     /// A reference to the device buffer is passed
     /// to the user function in the callback. The passed buffer have the max length that
     /// the user is able to use, and the callback have to return the number of element written into the buffer.
-    pub fn midi_write_callback<'c, F>(&self, f: F)
+    pub fn midi_write_callback<F>(&self, f: F)
     where
-        F: FnMut(&[u8]) -> usize + 'c,
+        F: FnMut(&[u8]) -> usize + Send + 'static,
     {
         unsafe {
             (*(csound_sys::csoundGetHostData(self.engine.csound) as *mut CallbackHandler))
@@ -2717,9 +3506,9 @@ impl Csound {
     }
 
     /// Indicates to the user when csound has closed the midi input device.
-    pub fn midi_in_close_callback<'c, F>(&self, f: F)
+    pub fn midi_in_close_callback<F>(&self, f: F)
     where
-        F: FnMut() + 'c,
+        F: FnMut() + Send + 'static,
     {
         unsafe {
             (*(csound_sys::csoundGetHostData(self.engine.csound) as *mut CallbackHandler))
@@ -2729,9 +3518,9 @@ impl Csound {
     }
 
     /// Indicates to the user when csound has closed the midi output device.
-    pub fn midi_out_close_callback<'c, F>(&self, f: F)
+    pub fn midi_out_close_callback<F>(&self, f: F)
     where
-        F: FnMut() + 'c,
+        F: FnMut() + Send + 'static,
     {
         unsafe {
             (*(csound_sys::csoundGetHostData(self.engine.csound) as *mut CallbackHandler))
@@ -2746,9 +3535,9 @@ impl Csound {
     /// and you should use this function to do any kind of updating during the operation.
     /// # Returns
     /// If this callback returns *false* it wont be called anymore
-    pub fn yield_callback<'c, F>(&self, f: F)
+    pub fn yield_callback<F>(&self, f: F)
     where
-        F: FnMut() -> bool + 'c,
+        F: FnMut() -> bool + Send + 'static,
     {
         unsafe {
             (*(csound_sys::csoundGetHostData(self.engine.csound) as *mut CallbackHandler))
@@ -2758,21 +3547,36 @@ impl Csound {
     }
 } //End impl block
 
-// Drop method to free the memory using during the csound performance and instantiation
+// Drop method to free the memory using during the csound performance and instantiation.
+// It is called once the Csound instance and all its handles have been dropped.
+// The callbacks can own handles to the instance, which would keep it alive forever.
+// They don't run anymore once the Csound object is gone, so they are released with it.
 impl Drop for Csound {
+    fn drop(&mut self) {
+        if Arc::strong_count(&self.engine) > 1 {
+            unsafe {
+                let handler =
+                    csound_sys::csoundGetHostData(self.engine.csound) as *mut CallbackHandler;
+                drop(std::mem::take(&mut (*handler).callbacks));
+            }
+        }
+    }
+}
+
+impl Drop for Inner {
     fn drop(&mut self) {
         unsafe {
-            csound_sys::csoundStop(self.engine.csound);
-            csound_sys::csoundCleanup(self.engine.csound);
-            let _ = Box::from_raw(
-                csound_sys::csoundGetHostData(self.engine.csound) as *mut CallbackHandler
-            );
+            csound_sys::csoundStop(self.csound);
+            csound_sys::csoundCleanup(self.csound);
             // Checks if a message buffer exists and destroy it.
-            let msg_buffer = self.engine.use_msg_buffer.borrow();
-            if *msg_buffer {
-                csound_sys::csoundDestroyMessageBuffer(self.engine.csound);
+            if self.use_msg_buffer.load(Ordering::SeqCst) {
+                csound_sys::csoundDestroyMessageBuffer(self.csound);
             }
-            csound_sys::csoundDestroy(self.engine.csound);
+            // csound could still call some callbacks while it is being destroyed,
+            // so the callback handler has to be released afterwards.
+            let handler = csound_sys::csoundGetHostData(self.csound) as *mut CallbackHandler;
+            csound_sys::csoundDestroy(self.csound);
+            let _ = Box::from_raw(handler);
         }
     }
 }
@@ -2798,9 +3602,12 @@ where
     /// # Returns
     /// The number of items read **(0 <= n <= items)**.
     /// or an Error if the output buffer doesn't have enough capacity.
-    pub fn read(&self, out: &mut [T], items: u32) -> Result<usize, &'static str> {
-        if items as usize <= out.len() {
-            return Err("your buffer has not enough capacity");
+    pub fn read(&self, out: &mut [T], items: u32) -> Result<usize> {
+        if out.len() < items as usize {
+            return Err(Error::BufferTooSmall {
+                required: items as usize,
+                available: out.len(),
+            });
         }
         unsafe {
             Ok(csound_sys::csoundReadCircularBuffer(
@@ -2819,9 +3626,12 @@ where
     /// # Returns
     /// The actual number of items read **(0 <= n <= items)**, or an error if the number of items
     /// to read/write exceeds the buffer's capacity.
-    pub fn peek(&self, out: &mut [T], items: u32) -> Result<usize, &'static str> {
-        if items as usize <= out.len() {
-            return Err("your buffer has not enough capacity");
+    pub fn peek(&self, out: &mut [T], items: u32) -> Result<usize> {
+        if out.len() < items as usize {
+            return Err(Error::BufferTooSmall {
+                required: items as usize,
+                available: out.len(),
+            });
         }
         unsafe {
             Ok(csound_sys::csoundPeekCircularBuffer(
@@ -2840,9 +3650,12 @@ where
     /// # Returns
     /// The actual number of items written *(0 <= n <= items)**, or an error if the number of items
     /// to read/write exceeds the buffer's capacity.
-    pub fn write(&self, input: &[T], items: u32) -> Result<usize, &'static str> {
-        if items as usize <= input.len() {
-            return Err("your buffer has not enough capacity");
+    pub fn write(&self, input: &[T], items: u32) -> Result<usize> {
+        if input.len() < items as usize {
+            return Err(Error::BufferTooSmall {
+                required: items as usize,
+                available: input.len(),
+            });
         }
         unsafe {
             Ok(csound_sys::csoundWriteCircularBuffer(
@@ -2912,7 +3725,7 @@ impl<'a> Table<'a> {
     /// ```no_run
     /// use csound::Csound;
     ///
-    /// let cs = Csound::new();
+    /// let mut cs = Csound::new();
     /// cs.compile_csd("some.csd");
     /// cs.start().unwrap();
     /// while cs.perform_ksmps() == false {
@@ -2945,7 +3758,7 @@ impl<'a> Table<'a> {
     /// ```no_run
     /// use csound::Csound;
     ///
-    /// let cs = Csound::new();
+    /// let mut cs = Csound::new();
     /// cs.compile_csd("some.csd");
     /// cs.start().unwrap();
     /// while cs.perform_ksmps() == false {
@@ -3095,3 +3908,74 @@ impl<'a> DerefMut for BufferPtr<'a, Writable> {
         self.as_mut_slice()
     }
 }
+
+// A C temporary file, it is removed when it is closed.
+struct TempFile(*mut libc::FILE);
+
+impl TempFile {
+    fn new() -> Result<TempFile> {
+        let file = unsafe { libc::tmpfile() };
+        if file.is_null() {
+            return Err(Error::TempFile);
+        }
+        Ok(TempFile(file))
+    }
+
+    fn with_content(content: &str) -> Result<TempFile> {
+        let file = TempFile::new()?;
+        unsafe {
+            if libc::fwrite(content.as_ptr() as *const c_void, 1, content.len(), file.0)
+                != content.len()
+            {
+                return Err(Error::TempFile);
+            }
+            libc::rewind(file.0);
+        }
+        Ok(file)
+    }
+
+    fn read_to_string(&self) -> Result<String> {
+        let mut content = Vec::new();
+        let mut buffer = [0u8; 4096];
+        unsafe {
+            libc::fflush(self.0);
+            libc::rewind(self.0);
+            loop {
+                let read = libc::fread(buffer.as_mut_ptr() as *mut c_void, 1, buffer.len(), self.0);
+                content.extend_from_slice(&buffer[..read]);
+                if read < buffer.len() {
+                    break;
+                }
+            }
+        }
+        Ok(str::from_utf8(&content)?.to_owned())
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        unsafe {
+            libc::fclose(self.0);
+        }
+    }
+}
+
+// A csound instance used by the score utilities, which overwrite the score reader's state.
+// Its messages go to the default message callback.
+struct ScratchCsound(*mut csound_sys::CSOUND);
+
+impl ScratchCsound {
+    fn new() -> ScratchCsound {
+        let csound = unsafe { csound_sys::csoundCreate(ptr::null_mut()) };
+        assert!(!csound.is_null());
+        ScratchCsound(csound)
+    }
+}
+
+impl Drop for ScratchCsound {
+    fn drop(&mut self) {
+        unsafe {
+            csound_sys::csoundDestroy(self.0);
+        }
+    }
+}
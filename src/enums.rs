@@ -1,6 +1,8 @@
 use bitflags::bitflags;
 use std::mem::transmute;
 
+use crate::channels::PvsDataExt;
+
 #[derive(Debug, PartialEq)]
 /// An audio channel identifier
 pub enum AudioChannel {}
@@ -13,6 +15,14 @@ pub enum ControlChannel {}
 /// A string channel identifier
 pub enum StrChannel {}
 
+#[derive(Debug, PartialEq)]
+/// A pvs (f-sig) channel identifier
+pub enum FsigChannel {}
+
+#[derive(Debug, PartialEq)]
+/// A variable channel identifier, e.g a k[] or i[] array
+pub enum VarChannel {}
+
 /// Define the type of csound messages
 #[derive(Debug, PartialEq)]
 pub enum MessageType {
@@ -105,11 +115,16 @@ impl Status {
 /// Enum variant which represent channel's types in callbacks.
 ///
 /// Channels which could trigger a callback, that is, channels created using  the [*invalue*](http://www.csounds.com/manual/html/invalue.html),
-/// [*outvalue*](http://www.csounds.com/manual/html/outvalue.html) opcodes. Only control and string channels are supported.
+/// [*outvalue*](http://www.csounds.com/manual/html/outvalue.html) opcodes.
+/// The variant matches the type of the opcode's argument: control (i or k-rate), string, audio or pvs (f-sig).
 #[derive(Debug, Clone, PartialEq)]
 pub enum ChannelData {
     CS_CONTROL_CHANNEL(f64),
     CS_STRING_CHANNEL(String),
+    /// ksmps audio samples.
+    CS_AUDIO_CHANNEL(Vec<f64>),
+    /// A pvs frame.
+    CS_PVS_CHANNEL(PvsDataExt),
     CS_UNKNOWN_CHANNEL,
 }
 
@@ -328,3 +343,95 @@ impl From<u8> for FileTypes {
         }
     }
 }
+
+/// Format of the data in a pvs(f-sig) frame.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PvsFormat {
+    /// Interleaved amplitude and frequency pairs, one per bin.
+    PVS_AMP_FREQ,
+    /// Interleaved amplitude and phase pairs, one per bin.
+    PVS_AMP_PHASE,
+    /// Interleaved real and imaginary pairs, one per bin.
+    PVS_COMPLEX,
+    /// Partial tracks (amp, freq, phase, id).
+    PVS_TRACKS,
+    /// A format unknown to this crate.
+    PVS_UNKNOWN(u32),
+}
+
+impl From<u32> for PvsFormat {
+    fn from(value: u32) -> Self {
+        match value {
+            0 => PvsFormat::PVS_AMP_FREQ,
+            1 => PvsFormat::PVS_AMP_PHASE,
+            2 => PvsFormat::PVS_COMPLEX,
+            3 => PvsFormat::PVS_TRACKS,
+            value => PvsFormat::PVS_UNKNOWN(value),
+        }
+    }
+}
+
+impl PvsFormat {
+    pub fn to_u32(self) -> u32 {
+        match self {
+            PvsFormat::PVS_AMP_FREQ => 0,
+            PvsFormat::PVS_AMP_PHASE => 1,
+            PvsFormat::PVS_COMPLEX => 2,
+            PvsFormat::PVS_TRACKS => 3,
+            PvsFormat::PVS_UNKNOWN(value) => value,
+        }
+    }
+}
+
+/// Analysis window used to compute a pvs(f-sig) frame.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WindowType {
+    PVS_WIN_HAMMING,
+    PVS_WIN_HANN,
+    PVS_WIN_KAISER,
+    PVS_WIN_CUSTOM,
+    PVS_WIN_BLACKMAN,
+    PVS_WIN_BLACKMAN_EXACT,
+    PVS_WIN_NUTTALLC3,
+    PVS_WIN_BHARRIS_3,
+    PVS_WIN_BHARRIS_MIN,
+    PVS_WIN_RECT,
+    /// A window unknown to this crate.
+    PVS_WIN_UNKNOWN(u32),
+}
+
+impl From<u32> for WindowType {
+    fn from(value: u32) -> Self {
+        match value {
+            0 => WindowType::PVS_WIN_HAMMING,
+            1 => WindowType::PVS_WIN_HANN,
+            2 => WindowType::PVS_WIN_KAISER,
+            3 => WindowType::PVS_WIN_CUSTOM,
+            4 => WindowType::PVS_WIN_BLACKMAN,
+            5 => WindowType::PVS_WIN_BLACKMAN_EXACT,
+            6 => WindowType::PVS_WIN_NUTTALLC3,
+            7 => WindowType::PVS_WIN_BHARRIS_3,
+            8 => WindowType::PVS_WIN_BHARRIS_MIN,
+            9 => WindowType::PVS_WIN_RECT,
+            value => WindowType::PVS_WIN_UNKNOWN(value),
+        }
+    }
+}
+
+impl WindowType {
+    pub fn to_u32(self) -> u32 {
+        match self {
+            WindowType::PVS_WIN_HAMMING => 0,
+            WindowType::PVS_WIN_HANN => 1,
+            WindowType::PVS_WIN_KAISER => 2,
+            WindowType::PVS_WIN_CUSTOM => 3,
+            WindowType::PVS_WIN_BLACKMAN => 4,
+            WindowType::PVS_WIN_BLACKMAN_EXACT => 5,
+            WindowType::PVS_WIN_NUTTALLC3 => 6,
+            WindowType::PVS_WIN_BHARRIS_3 => 7,
+            WindowType::PVS_WIN_BHARRIS_MIN => 8,
+            WindowType::PVS_WIN_RECT => 9,
+            WindowType::PVS_WIN_UNKNOWN(value) => value,
+        }
+    }
+}
@@ -0,0 +1,161 @@
+use std::error;
+use std::ffi::NulError;
+use std::fmt;
+use std::str::Utf8Error;
+
+use crate::enums::Status;
+
+/// A specialized Result type for csound operations.
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// The error type for the csound's operations.
+///
+/// # Example
+/// ```no_run
+/// use csound::{Csound, Error};
+///
+/// fn run() -> csound::Result<()> {
+///     let mut cs = Csound::new();
+///     cs.compile_csd("some.csd")?;
+///     cs.start()?;
+///     match cs.table_get(1, 1024) {
+///         Ok(value) => println!("value {}", value),
+///         Err(Error::TableNotFound(table)) => println!("there is not a table {}", table),
+///         Err(Error::IndexOutOfRange { index, len }) => println!("{} >= {}", index, len),
+///         Err(e) => return Err(e),
+///     }
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub enum Error {
+    /// The string contains an interior nul byte, so it can not be passed to csound.
+    NulString(NulError),
+
+    /// The string returned by csound is not valid UTF-8.
+    InvalidUtf8(Utf8Error),
+
+    /// An empty string was passed where csound expects some content.
+    EmptyString,
+
+    /// A csound's function returned an error status.
+    Engine {
+        /// The name of the csound's C function which failed.
+        operation: &'static str,
+        /// The raw CSOUND_STATUS value returned by csound.
+        code: i32,
+    },
+
+    /// The option is not valid for csound.
+    InvalidOption(String),
+
+    /// An argument doesn't meet the function's requirements.
+    InvalidArgument(&'static str),
+
+    /// The function table doesn't exist.
+    TableNotFound(u32),
+
+    /// The index is out of the table's bounds.
+    IndexOutOfRange { index: usize, len: usize },
+
+    /// The channel doesn't exist or it was created with another type.
+    WrongChannelType(String),
+
+    /// The channel hasn't been declared, e.g with the `chn_k` opcode.
+    ChannelNotFound(String),
+
+    /// The buffer doesn't have enough capacity for the requested operation.
+    BufferTooSmall { required: usize, available: usize },
+
+    /// The score text can not be parsed.
+    InvalidScore {
+        /// The line of the score, starting from 1.
+        line: usize,
+        message: String,
+    },
+
+    /// The temporary file used to pass data to csound couldn't be created or written.
+    TempFile,
+
+    /// The engine's buffers are not initialized yet,
+    /// some csd, orc or score have to be compiled and then csound must be started.
+    NotStarted,
+}
+
+impl Error {
+    /// # Returns
+    /// The csound's status of this error, or None if this error
+    /// was not reported by csound.
+    pub fn status(&self) -> Option<Status> {
+        match *self {
+            Error::Engine { code, .. } => Some(Status::from(code)),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::NulString(e) => write!(f, "Invalid string: {}", e),
+            Error::InvalidUtf8(e) => write!(f, "Invalid UTF-8 string: {}", e),
+            Error::EmptyString => write!(f, "Empty strings can not be passed to csound"),
+            Error::Engine { operation, code } => write!(
+                f,
+                "{} failed with status {:?}",
+                operation,
+                Status::from(*code)
+            ),
+            Error::InvalidOption(option) => write!(f, "Csound option {} not valid", option),
+            Error::InvalidArgument(msg) => write!(f, "Invalid argument: {}", msg),
+            Error::TableNotFound(table) => write!(f, "Table {} doesn't exist", table),
+            Error::IndexOutOfRange { index, len } => write!(
+                f,
+                "Index {} out of range for a table of length {}",
+                index, len
+            ),
+            Error::WrongChannelType(name) => {
+                write!(f, "Channel {} doesn't exist or has a different type", name)
+            }
+            Error::ChannelNotFound(name) => write!(f, "Channel {} doesn't exist", name),
+            Error::BufferTooSmall {
+                required,
+                available,
+            } => write!(
+                f,
+                "Not enough capacity, {} items are required but the buffer has {}",
+                required, available
+            ),
+            Error::InvalidScore { line, message } => {
+                write!(f, "Invalid score at line {}: {}", line, message)
+            }
+            Error::TempFile => write!(f, "The temporary file couldn't be created or written"),
+            Error::NotStarted => write!(
+                f,
+                "The engine is not started, call the 'compile()' and 'start()' methods"
+            ),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::NulString(e) => Some(e),
+            Error::InvalidUtf8(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<NulError> for Error {
+    fn from(e: NulError) -> Self {
+        Error::NulString(e)
+    }
+}
+
+impl From<Utf8Error> for Error {
+    fn from(e: Utf8Error) -> Self {
+        Error::InvalidUtf8(e)
+    }
+}
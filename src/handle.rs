@@ -0,0 +1,214 @@
+use std::ffi::CString;
+use std::sync::Arc;
+
+use crate::callbacks::Trampoline;
+use crate::csound::Inner;
+use crate::error::{Error, Result};
+use crate::score_event::ScoreEvent;
+use csound_sys::CSOUND_STATUS;
+
+use libc::{c_char, c_double, c_int, c_long};
+
+/// A thread-safe handle to a csound instance.
+///
+/// The handle is created with [`Csound::handle`](struct.Csound.html#method.handle) or
+/// [`PerformanceThread::handle`](struct.PerformanceThread.html#method.handle),
+/// it can be cloned and shared between threads and it only exposes
+/// the csound's functions which are documented as thread-safe, so they can be called
+/// while another thread is performing. The asynchronous table copies are not available,
+/// since csound accesses the buffer after the call returns, see the unsafe
+/// [`Csound::table_copy_out_async`](struct.Csound.html#method.table_copy_out_async).
+/// The csound instance is destroyed when the [`Csound`](struct.Csound.html) object and all
+/// its handles have been dropped. The callbacks are released with the `Csound` object,
+/// so a callback can own a handle without keeping the instance alive.
+/// # Example
+/// ```no_run
+/// use csound::Csound;
+/// use std::thread;
+///
+/// let mut cs = Csound::new();
+/// cs.compile_csd("some.csd").unwrap();
+/// cs.start().unwrap();
+///
+/// let handle = cs.handle();
+/// let gui = thread::spawn(move || {
+///     handle.set_control_channel("amp", 0.5).unwrap();
+///     handle.send_score_event_async('i', &[1.0, 0.0, 2.0]);
+/// });
+///
+/// while !cs.perform_ksmps() {}
+/// gui.join().unwrap();
+/// ```
+/// The [`Csound`](struct.Csound.html) object itself can not be shared between threads:
+/// ```compile_fail
+/// fn is_sync<T: Sync>() {}
+/// is_sync::<csound::Csound>();
+/// ```
+#[derive(Debug, Clone)]
+pub struct CsoundHandle {
+    pub(crate) engine: Arc<Inner>,
+}
+
+impl CsoundHandle {
+    /// Asynchronous version of [`Csound::send_score_event`](struct.Csound.html#method.send_score_event)
+    /// # Arguments
+    /// * `event_type` is the score event type ('a', 'i', 'q', 'f', or 'e').
+    /// * `pfields` is a slice of f64 values with all the pfields for this event.
+    pub fn send_score_event_async(&self, event_type: char, pfields: &[f64]) {
+        unsafe {
+            csound_sys::csoundScoreEventAsync(
+                self.engine.csound,
+                event_type as c_char,
+                pfields.as_ptr() as *const c_double,
+                pfields.len() as c_long,
+            );
+        }
+    }
+
+    /// Asynchronous version of [`Csound::send_score_event_absolute`](struct.Csound.html#method.send_score_event_absolute)
+    pub fn send_score_event_absolute_async(
+        &self,
+        event_type: char,
+        pfields: &[f64],
+        time_offset: f64,
+    ) {
+        unsafe {
+            csound_sys::csoundScoreEventAbsoluteAsync(
+                self.engine.csound,
+                event_type as c_char,
+                pfields.as_ptr() as *const c_double,
+                pfields.len() as c_long,
+                time_offset as c_double,
+            );
+        }
+    }
+
+    /// Asynchronous version of [`Csound::send_event`](struct.Csound.html#method.send_event)
+    ///
+    /// Only the validation errors are returned, the event is inserted later by csound.
+    pub fn send_event_async(&self, event: &ScoreEvent) -> Result<()> {
+        event.validate()?;
+        match event.numeric_pfields() {
+            Some(pfields) => self.send_score_event_async(event.opcode(), &pfields),
+            None => {
+                let message = CString::new(event.to_string())?;
+                unsafe {
+                    csound_sys::csoundInputMessageAsync(self.engine.csound, message.as_ptr());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Asynchronous version of [`Csound::read_score`](struct.Csound.html#method.read_score)
+    pub fn read_score_async(&self, score: &str) -> Result<()> {
+        unsafe {
+            let s = Trampoline::convert_str_to_c(score)?;
+            csound_sys::csoundReadScoreAsync(self.engine.csound, s.as_ptr());
+            Ok(())
+        }
+    }
+
+    /// Async version of [`Csound::compile_orc`](struct.Csound.html#method.compile_orc). The code is parsed and compiled,
+    /// then placed on a queue for asynchronous merge into the running engine, and evaluation.
+    /// The function returns following parsing and compilation.
+    /// # Arguments
+    /// * `orc` A reference to an csound's orchestra definitions
+    pub fn compile_orc_async<T>(&self, orc: T) -> Result<()>
+    where
+        T: AsRef<str>,
+    {
+        let path = Trampoline::convert_str_to_c(orc)?;
+        unsafe {
+            match csound_sys::csoundCompileOrcAsync(self.engine.csound, path.as_ptr()) {
+                CSOUND_STATUS::CSOUND_SUCCESS => Ok(()),
+                code => Err(Error::Engine {
+                    operation: "csoundCompileOrcAsync",
+                    code,
+                }),
+            }
+        }
+    }
+
+    /// # Returns
+    /// The current performance time in samples.
+    /// see [`Csound::get_current_sample_time`](struct.Csound.html#method.get_current_sample_time)
+    pub fn get_current_sample_time(&self) -> usize {
+        unsafe { csound_sys::csoundGetCurrentTimeSamples(self.engine.csound) as usize }
+    }
+
+    /// Retrieves the value of a control channel.
+    /// see [`Csound::get_control_channel`](struct.Csound.html#method.get_control_channel)
+    pub fn get_control_channel(&self, name: &str) -> Result<f64> {
+        let cname = CString::new(name)?;
+        let mut err: c_int = 0;
+        unsafe {
+            let ret = csound_sys::csoundGetControlChannel(
+                self.engine.csound,
+                cname.as_ptr(),
+                &mut err as *mut _,
+            ) as f64;
+            match err {
+                CSOUND_STATUS::CSOUND_SUCCESS => Ok(ret),
+                code if code > 0 => Err(Error::WrongChannelType(name.to_owned())),
+                code => Err(Error::Engine {
+                    operation: "csoundGetControlChannel",
+                    code,
+                }),
+            }
+        }
+    }
+
+    /// Sets the value of a control channel.
+    /// # Arguments
+    /// * `name`  The channel name.
+    pub fn set_control_channel(&self, name: &str, value: f64) -> Result<()> {
+        let cname = CString::new(name)?;
+        unsafe {
+            self.engine.recorder.record(self.engine.csound, name, value);
+            csound_sys::csoundSetControlChannel(self.engine.csound, cname.as_ptr(), value);
+        }
+        Ok(())
+    }
+
+    /// Sets the string channel identified by *name* with *content*
+    pub fn set_string_channel(&self, name: &str, content: &str) -> Result<()> {
+        let cname = CString::new(name)?;
+        let content = CString::new(content)?;
+        unsafe {
+            csound_sys::csoundSetStringChannel(
+                self.engine.csound,
+                cname.as_ptr(),
+                content.as_ptr() as *mut _,
+            );
+        }
+        Ok(())
+    }
+
+    /// Kills off one or more running instances of an instrument.
+    /// see [`Csound::kill_instrument`](struct.Csound.html#method.kill_instrument)
+    pub fn kill_instrument(
+        &self,
+        instr: f64,
+        name: Option<&str>,
+        mode: u32,
+        allow_release: bool,
+    ) -> Result<()> {
+        let cname = CString::new(name.unwrap_or(""))?;
+        unsafe {
+            match csound_sys::csoundKillInstance(
+                self.engine.csound,
+                instr as c_double,
+                cname.as_ptr() as *mut c_char,
+                mode as c_int,
+                allow_release as c_int,
+            ) {
+                CSOUND_STATUS::CSOUND_SUCCESS => Ok(()),
+                code => Err(Error::Engine {
+                    operation: "csoundKillInstance",
+                    code,
+                }),
+            }
+        }
+    }
+}
@@ -57,7 +57,7 @@
 //! </CsoundSynthesizer>";
 //!
 //! fn main() {
-//!     let cs = Csound::new();
+//!     let mut cs = Csound::new();
 //!
 //!     cs.message_string_callback(|_, msg: &str| print!("{}", msg));
 //!     cs.compile_csd_text(CSD).unwrap();
@@ -69,17 +69,62 @@
 
 pub use csound_sys::RTCLOCK;
 
+mod audio_stream;
+mod automation;
+mod binding;
 mod callbacks;
 mod channels;
+mod cscore;
 mod csound;
 mod enums;
+mod error;
+mod handle;
+mod lifecycle;
+mod options;
+mod parameter;
+mod performance_thread;
+mod pvs;
+mod render;
 mod rtaudio;
+mod scheduler;
+pub mod score;
+mod score_event;
+mod snapshot;
+mod soundfile;
+mod watch;
 
-pub use callbacks::FileInfo;
-pub use channels::{ChannelHints, ChannelInfo, InputChannel, OutputChannel, PvsDataExt};
 pub use crate::csound::{BufferPtr, CircularBuffer, Csound, OpcodeListEntry, Table};
+pub use audio_stream::{AudioReceiver, AudioSender};
+pub use automation::{Automation, AutomationEvent, AutomationPlayer, AutomationRecorder};
+pub use binding::{Bound, ChannelBinding, ChannelKind, ChannelSpec, ControlValue, CsoundChannels};
+pub use callbacks::FileInfo;
+pub use channels::{
+    ArrayView, ChannelBehavior, ChannelHints, ChannelInfo, ChannelReadGuard, ChannelWriteGuard,
+    InputChannel, OutputChannel, PvsDataExt, SharedInputChannel, SharedOutputChannel,
+};
+pub use cscore::{Cscore, CscoreEvent};
 pub use enums::{
-    AudioChannel, ChannelData, ControlChannel, FileTypes, Language, MessageType, Status, StrChannel,
+    AudioChannel, ChannelData, ControlChannel, FileTypes, FsigChannel, Language, MessageType,
+    PvsFormat, Status, StrChannel, VarChannel, WindowType,
 };
+pub use error::{Error, Result};
+pub use handle::CsoundHandle;
+pub use lifecycle::{
+    Compiled, Created, Engine, EngineState, Initialized, Running, TransitionError,
+};
+pub use options::{AudioTarget, CsoundParams, Options};
+pub use parameter::Parameter;
+pub use performance_thread::PerformanceThread;
+pub use pvs::PvsChannel;
+pub use render::{RenderedAudio, Renderer};
 pub use rtaudio::{CsAudioDevice, CsMidiDevice, RtAudioParams};
+pub use scheduler::{Scheduler, Task, TaskId, Time};
+pub use score_event::{FTableBuilder, InstrBuilder, Instrument, PField, ScoreEvent};
+pub use snapshot::ChannelSnapshot;
 
+#[cfg(feature = "derive")]
+pub use csound_derive::CsoundChannels;
+pub use soundfile::{
+    SampleFormat, SoundFileReader, SoundFileSource, SoundFileType, SoundFileWriter,
+};
+pub use watch::{WatchId, WatchOptions};
@@ -0,0 +1,497 @@
+use std::error;
+use std::fmt;
+use std::marker::PhantomData;
+
+use crate::channels::{InputChannel, IsChannel, OutputChannel};
+use crate::csound::{BufferPtr, Csound, Readable, Writable};
+use crate::error::{Error, Result};
+use crate::handle::CsoundHandle;
+use crate::options::{CsoundParams, Options};
+use crate::score_event::ScoreEvent;
+
+mod sealed {
+    pub trait Sealed {}
+    impl Sealed for super::Created {}
+    impl Sealed for super::Compiled {}
+    impl Sealed for super::Running {}
+}
+
+/// The lifecycle states of an [`Engine`](struct.Engine.html).
+///
+/// This trait is sealed, it is only implemented by
+/// [`Created`](struct.Created.html), [`Compiled`](struct.Compiled.html) and [`Running`](struct.Running.html).
+pub trait EngineState: sealed::Sealed {}
+
+/// States in which the orchestra has been compiled, so the engine
+/// attributes(sr, ksmps, nchnls, ...) and the channels are available.
+pub trait Initialized: EngineState {}
+
+/// A new or reset csound instance, options can be set and an orchestra can be compiled.
+#[derive(Debug)]
+pub struct Created;
+
+/// A csound instance with a compiled orchestra, ready to be started.
+#[derive(Debug)]
+pub struct Compiled;
+
+/// A started csound instance, it can be performed.
+#[derive(Debug)]
+pub struct Running;
+
+impl EngineState for Created {}
+impl EngineState for Compiled {}
+impl EngineState for Running {}
+impl Initialized for Compiled {}
+impl Initialized for Running {}
+
+/// A csound instance which tracks its lifecycle in its type.
+///
+/// This is an optional wrapper over [`Csound`](struct.Csound.html) where every method
+/// only exists in the state where csound supports it, so calling
+/// `start()` before compiling, starting twice or requesting the *spout* buffer
+/// before starting are compile time errors instead of runtime failures.
+/// The transitions consume the engine and return it in its new state:
+///
+/// `Engine<Created>` --compile--> `Engine<Compiled>` --start--> `Engine<Running>` --reset--> `Engine<Created>`
+///
+/// Buffers and channels borrow the engine, so they can not outlive a `reset()`.
+/// A failed transition returns a [`TransitionError`](struct.TransitionError.html), which gives
+/// the engine back in its previous state, with its options and callbacks.
+/// # Example
+/// ```no_run
+/// use csound::Engine;
+///
+/// let engine = Engine::new();
+/// engine.set_option("-odac").unwrap();
+/// let engine = engine.compile_csd("some.csd").unwrap();
+/// println!("sr: {}", engine.get_sample_rate());
+/// let engine = engine.start().unwrap();
+///
+/// let spout = engine.get_spout().unwrap();
+/// while !engine.perform_ksmps() {
+///     println!("{:?}", spout.as_slice());
+/// }
+/// let engine = engine.reset();
+/// ```
+/// A created engine can not be performed:
+/// ```compile_fail
+/// let engine = csound::Engine::new();
+/// engine.perform_ksmps();
+/// ```
+/// and a running engine can not be started again:
+/// ```compile_fail
+/// let engine = csound::Engine::new().compile_csd("some.csd").unwrap().start().unwrap();
+/// engine.start();
+/// ```
+/// and the buffers can not be used after a reset:
+/// ```compile_fail
+/// let engine = csound::Engine::new().compile_csd("some.csd").unwrap().start().unwrap();
+/// let spout = engine.get_spout().unwrap();
+/// let engine = engine.reset();
+/// println!("{:?}", spout.as_slice());
+/// ```
+#[derive(Debug)]
+pub struct Engine<S: EngineState> {
+    csound: Csound,
+    state: PhantomData<S>,
+}
+
+/// The error of a failed [`Engine`](struct.Engine.html) transition,
+/// it keeps the engine in the state it had before the transition.
+/// # Example
+/// ```no_run
+/// use csound::Engine;
+///
+/// let engine = Engine::new();
+/// engine.set_option("-odac").unwrap();
+/// let engine = match engine.compile_orc("instr 1 \n out oscil(0.1, 440) \n endin") {
+///     Ok(engine) => engine,
+///     Err(e) => {
+///         eprintln!("{}", e);
+///         // The options are kept, only the orchestra has to be fixed
+///         e.into_engine().reset().compile_orc("instr 1 \n endin").unwrap()
+///     }
+/// };
+/// ```
+/// It converts into an [`Error`](enum.Error.html), so `?` can be used in functions
+/// returning a [`csound::Result`](type.Result.html).
+#[derive(Debug)]
+pub struct TransitionError<S: EngineState> {
+    error: Error,
+    engine: Engine<S>,
+}
+
+impl<S: EngineState> TransitionError<S> {
+    /// # Returns
+    /// The error of the transition.
+    pub fn error(&self) -> &Error {
+        &self.error
+    }
+
+    /// # Returns
+    /// The engine in the state it had before the transition.
+    pub fn into_engine(self) -> Engine<S> {
+        self.engine
+    }
+
+    /// # Returns
+    /// The error and the engine.
+    pub fn into_parts(self) -> (Error, Engine<S>) {
+        (self.error, self.engine)
+    }
+}
+
+impl<S: EngineState> From<TransitionError<S>> for Error {
+    fn from(error: TransitionError<S>) -> Self {
+        error.error
+    }
+}
+
+impl<S: EngineState> fmt::Display for TransitionError<S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.error)
+    }
+}
+
+impl<S: EngineState + fmt::Debug> error::Error for TransitionError<S> {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+impl<S: EngineState> Engine<S> {
+    fn transition<N: EngineState>(self) -> Engine<N> {
+        Engine {
+            csound: self.csound,
+            state: PhantomData,
+        }
+    }
+
+    /// See [`Csound::handle`](struct.Csound.html#method.handle)
+    pub fn handle(&mut self) -> CsoundHandle {
+        self.csound.handle()
+    }
+
+    /// See [`Csound::get_params`](struct.Csound.html#method.get_params)
+    pub fn get_params(&self) -> CsoundParams {
+        self.csound.get_params()
+    }
+
+    /// See [`Csound::sort_score`](struct.Csound.html#method.sort_score),
+    /// available in every state since it runs on a scratch csound instance.
+    pub fn sort_score(&self, score: &str) -> Result<String> {
+        self.csound.sort_score(score)
+    }
+
+    /// See [`Csound::extract_score`](struct.Csound.html#method.extract_score)
+    pub fn extract_score(&self, score: &str, extract: &str) -> Result<String> {
+        self.csound.extract_score(score, extract)
+    }
+
+    /// Resets the csound instance, see [`Csound::reset`](struct.Csound.html#method.reset).
+    /// # Returns
+    /// The engine back in the [`Created`](struct.Created.html) state
+    pub fn reset(mut self) -> Engine<Created> {
+        self.csound.reset();
+        self.transition()
+    }
+
+    /// # Returns
+    /// The underlying [`Csound`](struct.Csound.html) object, which does not keep track
+    /// of the lifecycle anymore.
+    pub fn into_inner(self) -> Csound {
+        self.csound
+    }
+}
+
+impl Default for Engine<Created> {
+    fn default() -> Self {
+        Engine::new()
+    }
+}
+
+impl From<Csound> for Engine<Created> {
+    /// Wraps an existing csound instance, callbacks set on *csound* are kept.
+    /// The instance is reset, so any compiled orchestra is discarded.
+    fn from(mut csound: Csound) -> Self {
+        csound.reset();
+        Engine {
+            csound,
+            state: PhantomData,
+        }
+    }
+}
+
+impl Engine<Created> {
+    /// Creates a new csound instance, see [`Csound::new`](struct.Csound.html#method.new)
+    pub fn new() -> Engine<Created> {
+        Engine {
+            csound: Csound::new(),
+            state: PhantomData,
+        }
+    }
+
+    /// Creates a new csound instance with *options* applied,
+    /// see [`Options::build`](struct.Options.html#method.build)
+    pub fn with_options(options: &Options) -> Result<Engine<Created>> {
+        Ok(Engine {
+            csound: options.build()?,
+            state: PhantomData,
+        })
+    }
+
+    /// See [`Csound::set_option`](struct.Csound.html#method.set_option)
+    pub fn set_option(&self, option: &str) -> Result<()> {
+        self.csound.set_option(option)
+    }
+
+    /// See [`Csound::set_params`](struct.Csound.html#method.set_params)
+    pub fn set_params(&self, params: &CsoundParams) {
+        self.csound.set_params(params)
+    }
+
+    /// See [`Options::apply`](struct.Options.html#method.apply)
+    pub fn apply_options(&self, options: &Options) -> Result<()> {
+        options.apply(&self.csound)
+    }
+
+    /// See [`Csound::compile`](struct.Csound.html#method.compile)
+    pub fn compile<T>(
+        mut self,
+        args: &[T],
+    ) -> std::result::Result<Engine<Compiled>, TransitionError<Created>>
+    where
+        T: AsRef<str> + std::fmt::Debug,
+    {
+        match self.csound.compile(args) {
+            Ok(_) => Ok(self.transition()),
+            Err(error) => Err(TransitionError {
+                error,
+                engine: self,
+            }),
+        }
+    }
+
+    /// See [`Csound::compile_csd`](struct.Csound.html#method.compile_csd)
+    pub fn compile_csd<T>(
+        mut self,
+        csd: T,
+    ) -> std::result::Result<Engine<Compiled>, TransitionError<Created>>
+    where
+        T: AsRef<str>,
+    {
+        match self.csound.compile_csd(csd) {
+            Ok(_) => Ok(self.transition()),
+            Err(error) => Err(TransitionError {
+                error,
+                engine: self,
+            }),
+        }
+    }
+
+    /// See [`Csound::compile_csd_text`](struct.Csound.html#method.compile_csd_text)
+    pub fn compile_csd_text<T>(
+        mut self,
+        csd_text: T,
+    ) -> std::result::Result<Engine<Compiled>, TransitionError<Created>>
+    where
+        T: AsRef<str>,
+    {
+        match self.csound.compile_csd_text(csd_text) {
+            Ok(_) => Ok(self.transition()),
+            Err(error) => Err(TransitionError {
+                error,
+                engine: self,
+            }),
+        }
+    }
+
+    /// See [`Csound::compile_orc`](struct.Csound.html#method.compile_orc)
+    pub fn compile_orc<T>(
+        mut self,
+        orc: T,
+    ) -> std::result::Result<Engine<Compiled>, TransitionError<Created>>
+    where
+        T: AsRef<str>,
+    {
+        match self.csound.compile_orc(orc) {
+            Ok(_) => Ok(self.transition()),
+            Err(error) => Err(TransitionError {
+                error,
+                engine: self,
+            }),
+        }
+    }
+}
+
+impl<S: Initialized> Engine<S> {
+    /// See [`Csound::get_sample_rate`](struct.Csound.html#method.get_sample_rate)
+    pub fn get_sample_rate(&self) -> f64 {
+        self.csound.get_sample_rate()
+    }
+
+    /// See [`Csound::get_control_rate`](struct.Csound.html#method.get_control_rate)
+    pub fn get_control_rate(&self) -> f64 {
+        self.csound.get_control_rate()
+    }
+
+    /// See [`Csound::get_ksmps`](struct.Csound.html#method.get_ksmps)
+    pub fn get_ksmps(&self) -> u32 {
+        self.csound.get_ksmps()
+    }
+
+    /// See [`Csound::output_channels`](struct.Csound.html#method.output_channels)
+    pub fn output_channels(&self) -> u32 {
+        self.csound.output_channels()
+    }
+
+    /// See [`Csound::input_channels`](struct.Csound.html#method.input_channels)
+    pub fn input_channels(&self) -> u32 {
+        self.csound.input_channels()
+    }
+
+    /// See [`Csound::get_0dBFS`](struct.Csound.html#method.get_0dBFS)
+    #[allow(non_snake_case)]
+    pub fn get_0dBFS(&self) -> f64 {
+        self.csound.get_0dBFS()
+    }
+
+    /// See [`Csound::compile_orc`](struct.Csound.html#method.compile_orc),
+    /// new instruments are added to the already compiled orchestra.
+    pub fn compile_orc<T>(&mut self, orc: T) -> Result<()>
+    where
+        T: AsRef<str>,
+    {
+        self.csound.compile_orc(orc)
+    }
+
+    /// See [`Csound::get_input_channel`](struct.Csound.html#method.get_input_channel)
+    pub fn get_input_channel<T>(&self, name: &str) -> Result<InputChannel<'_, T>>
+    where
+        T: IsChannel,
+    {
+        self.csound.get_input_channel(name)
+    }
+
+    /// See [`Csound::get_output_channel`](struct.Csound.html#method.get_output_channel)
+    pub fn get_output_channel<T>(&self, name: &str) -> Result<OutputChannel<'_, T>>
+    where
+        T: IsChannel,
+    {
+        self.csound.get_output_channel(name)
+    }
+
+    /// See [`Csound::get_control_channel`](struct.Csound.html#method.get_control_channel)
+    pub fn get_control_channel(&self, name: &str) -> Result<f64> {
+        self.csound.get_control_channel(name)
+    }
+
+    /// See [`Csound::set_control_channel`](struct.Csound.html#method.set_control_channel)
+    pub fn set_control_channel(&mut self, name: &str, value: f64) {
+        self.csound.set_control_channel(name, value)
+    }
+
+    /// See [`Csound::get_string_channel`](struct.Csound.html#method.get_string_channel)
+    pub fn get_string_channel(&self, name: &str) -> String {
+        self.csound.get_string_channel(name)
+    }
+
+    /// See [`Csound::set_string_channel`](struct.Csound.html#method.set_string_channel)
+    pub fn set_string_channel(&mut self, name: &str, content: &str) {
+        self.csound.set_string_channel(name, content)
+    }
+}
+
+impl Engine<Compiled> {
+    /// Starts the engine, see [`Csound::start`](struct.Csound.html#method.start)
+    pub fn start(self) -> std::result::Result<Engine<Running>, TransitionError<Compiled>> {
+        match self.csound.start() {
+            Ok(()) => Ok(self.transition()),
+            Err(error) => Err(TransitionError {
+                error,
+                engine: self,
+            }),
+        }
+    }
+}
+
+impl Engine<Running> {
+    /// See [`Csound::perform`](struct.Csound.html#method.perform)
+    pub fn perform(&self) -> i32 {
+        self.csound.perform()
+    }
+
+    /// See [`Csound::perform_ksmps`](struct.Csound.html#method.perform_ksmps)
+    pub fn perform_ksmps(&self) -> bool {
+        self.csound.perform_ksmps()
+    }
+
+    /// See [`Csound::perform_buffer`](struct.Csound.html#method.perform_buffer)
+    pub fn perform_buffer(&self) -> bool {
+        self.csound.perform_buffer()
+    }
+
+    /// See [`Csound::stop`](struct.Csound.html#method.stop)
+    pub fn stop(&self) {
+        self.csound.stop()
+    }
+
+    /// See [`Csound::get_spin`](struct.Csound.html#method.get_spin)
+    pub fn get_spin(&self) -> Option<BufferPtr<'_, Writable>> {
+        self.csound.get_spin()
+    }
+
+    /// See [`Csound::get_spout`](struct.Csound.html#method.get_spout)
+    pub fn get_spout(&self) -> Option<BufferPtr<'_, Readable>> {
+        self.csound.get_spout()
+    }
+
+    /// See [`Csound::get_input_buffer`](struct.Csound.html#method.get_input_buffer)
+    pub fn get_input_buffer(&self) -> Option<BufferPtr<'_, Writable>> {
+        self.csound.get_input_buffer()
+    }
+
+    /// See [`Csound::get_output_buffer`](struct.Csound.html#method.get_output_buffer)
+    pub fn get_output_buffer(&self) -> Option<BufferPtr<'_, Readable>> {
+        self.csound.get_output_buffer()
+    }
+
+    /// See [`Csound::get_score_time`](struct.Csound.html#method.get_score_time)
+    pub fn get_score_time(&self) -> f64 {
+        self.csound.get_score_time()
+    }
+
+    /// See [`Csound::send_score_event`](struct.Csound.html#method.send_score_event)
+    pub fn send_score_event(&mut self, event_type: char, pfields: &[f64]) -> Result<()> {
+        self.csound.send_score_event(event_type, pfields)
+    }
+
+    /// See [`Csound::send_score_event_absolute`](struct.Csound.html#method.send_score_event_absolute)
+    pub fn send_score_event_absolute(
+        &mut self,
+        event_type: char,
+        pfields: &[f64],
+        time_offset: f64,
+    ) -> Result<()> {
+        self.csound
+            .send_score_event_absolute(event_type, pfields, time_offset)
+    }
+
+    /// See [`Csound::send_event`](struct.Csound.html#method.send_event)
+    pub fn send_event(&mut self, event: &ScoreEvent) -> Result<()> {
+        self.csound.send_event(event)
+    }
+
+    /// See [`Csound::read_score`](struct.Csound.html#method.read_score)
+    pub fn read_score(&mut self, score: &str) -> Result<()> {
+        self.csound.read_score(score)
+    }
+
+    /// See [`Csound::eval_code`](struct.Csound.html#method.eval_code)
+    pub fn eval_code<T>(&mut self, code: T) -> Result<f64>
+    where
+        T: AsRef<str>,
+    {
+        self.csound.eval_code(code)
+    }
+}
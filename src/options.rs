@@ -0,0 +1,539 @@
+use crate::csound::Csound;
+use crate::error::{Error, Result};
+use csound_sys::CSOUND_PARAMS;
+
+use libc::c_int;
+
+/// Csound's engine parameters.
+///
+/// This is a safe mirror of the csound's `CSOUND_PARAMS` struct, it is read with
+/// [`Csound::get_params`](struct.Csound.html#method.get_params) and written with
+/// [`Csound::set_params`](struct.Csound.html#method.set_params).
+/// The override fields are ignored by csound when they are zero.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct CsoundParams {
+    pub debug_mode: bool,
+    /// Software buffer size in sample frames (-b).
+    pub buffer_frames: i32,
+    /// Hardware buffer size in sample frames (-B).
+    pub hardware_buffer_frames: i32,
+    pub displays: bool,
+    pub ascii_graphs: bool,
+    pub postscript_graphs: bool,
+    /// Message level bit field (-m).
+    pub message_level: i32,
+    pub tempo: i32,
+    pub ring_bell: bool,
+    pub use_cscore: bool,
+    pub terminate_on_midi: bool,
+    pub heartbeat: i32,
+    pub defer_gen01_load: bool,
+    pub midi_key: i32,
+    pub midi_key_cps: i32,
+    pub midi_key_oct: i32,
+    pub midi_key_pch: i32,
+    pub midi_velocity: i32,
+    pub midi_velocity_amp: i32,
+    pub no_default_paths: bool,
+    pub number_of_threads: i32,
+    pub syntax_check_only: bool,
+    pub csd_line_counts: bool,
+    pub compute_weights: bool,
+    pub realtime_mode: bool,
+    pub sample_accurate: bool,
+    pub sample_rate_override: f64,
+    pub control_rate_override: f64,
+    pub nchnls_override: i32,
+    pub nchnls_i_override: i32,
+    pub e0dbfs_override: f64,
+    pub daemon: bool,
+    pub ksmps_override: i32,
+    pub FFT_library: i32,
+}
+
+impl From<&CSOUND_PARAMS> for CsoundParams {
+    fn from(p: &CSOUND_PARAMS) -> Self {
+        CsoundParams {
+            debug_mode: p.debug_mode != 0,
+            buffer_frames: p.buffer_frames,
+            hardware_buffer_frames: p.hardware_buffer_frames,
+            displays: p.displays != 0,
+            ascii_graphs: p.ascii_graphs != 0,
+            postscript_graphs: p.postscript_graphs != 0,
+            message_level: p.message_level,
+            tempo: p.tempo,
+            ring_bell: p.ring_bell != 0,
+            use_cscore: p.use_cscore != 0,
+            terminate_on_midi: p.terminate_on_midi != 0,
+            heartbeat: p.heartbeat,
+            defer_gen01_load: p.defer_gen01_load != 0,
+            midi_key: p.midi_key,
+            midi_key_cps: p.midi_key_cps,
+            midi_key_oct: p.midi_key_oct,
+            midi_key_pch: p.midi_key_pch,
+            midi_velocity: p.midi_velocity,
+            midi_velocity_amp: p.midi_velocity_amp,
+            no_default_paths: p.no_default_paths != 0,
+            number_of_threads: p.number_of_threads,
+            syntax_check_only: p.syntax_check_only != 0,
+            csd_line_counts: p.csd_line_counts != 0,
+            compute_weights: p.compute_weights != 0,
+            realtime_mode: p.realtime_mode != 0,
+            sample_accurate: p.sample_accurate != 0,
+            sample_rate_override: p.sample_rate_override,
+            control_rate_override: p.control_rate_override,
+            nchnls_override: p.nchnls_override,
+            nchnls_i_override: p.nchnls_i_override,
+            e0dbfs_override: p.e0dbfs_override,
+            daemon: p.daemon != 0,
+            ksmps_override: p.ksmps_override,
+            FFT_library: p.FFT_library,
+        }
+    }
+}
+
+impl From<&CsoundParams> for CSOUND_PARAMS {
+    fn from(p: &CsoundParams) -> Self {
+        CSOUND_PARAMS {
+            debug_mode: p.debug_mode as c_int,
+            buffer_frames: p.buffer_frames as c_int,
+            hardware_buffer_frames: p.hardware_buffer_frames as c_int,
+            displays: p.displays as c_int,
+            ascii_graphs: p.ascii_graphs as c_int,
+            postscript_graphs: p.postscript_graphs as c_int,
+            message_level: p.message_level as c_int,
+            tempo: p.tempo as c_int,
+            ring_bell: p.ring_bell as c_int,
+            use_cscore: p.use_cscore as c_int,
+            terminate_on_midi: p.terminate_on_midi as c_int,
+            heartbeat: p.heartbeat as c_int,
+            defer_gen01_load: p.defer_gen01_load as c_int,
+            midi_key: p.midi_key as c_int,
+            midi_key_cps: p.midi_key_cps as c_int,
+            midi_key_oct: p.midi_key_oct as c_int,
+            midi_key_pch: p.midi_key_pch as c_int,
+            midi_velocity: p.midi_velocity as c_int,
+            midi_velocity_amp: p.midi_velocity_amp as c_int,
+            no_default_paths: p.no_default_paths as c_int,
+            number_of_threads: p.number_of_threads as c_int,
+            syntax_check_only: p.syntax_check_only as c_int,
+            csd_line_counts: p.csd_line_counts as c_int,
+            compute_weights: p.compute_weights as c_int,
+            realtime_mode: p.realtime_mode as c_int,
+            sample_accurate: p.sample_accurate as c_int,
+            sample_rate_override: p.sample_rate_override,
+            control_rate_override: p.control_rate_override,
+            nchnls_override: p.nchnls_override as c_int,
+            nchnls_i_override: p.nchnls_i_override as c_int,
+            e0dbfs_override: p.e0dbfs_override,
+            daemon: p.daemon as c_int,
+            ksmps_override: p.ksmps_override as c_int,
+            FFT_library: p.FFT_library as c_int,
+        }
+    }
+}
+
+/// Destination of the csound's audio output(-o) or source of its audio input(-i).
+#[derive(Debug, Clone, PartialEq)]
+pub enum AudioTarget {
+    /// The default realtime audio device (`dac` or `adc`).
+    Default,
+    /// A realtime audio device of the current audio module, e.g `Device(0)` is `dac0` or `adc0`.
+    Device(u32),
+    /// A realtime audio device by name, e.g `dac:hw:1,0`.
+    DeviceName(String),
+    /// A sound file.
+    File(String),
+    /// No sound output/input (-n).
+    Null,
+}
+
+/// Typed csound options.
+///
+/// Each field maps to a csound command line flag or to a field of
+/// [`CsoundParams`](struct.CsoundParams.html); unset fields keep the csound's default value.
+/// The options are validated before being applied, so mistakes are reported as an
+/// [`Error::InvalidArgument`](enum.Error.html#variant.InvalidArgument) instead of a
+/// csound message at runtime.
+/// Options have to be applied before compiling, csound ignores its parameters once the engine is compiled.
+/// # Example
+/// ```no_run
+/// use csound::{AudioTarget, Options};
+///
+/// let mut cs = Options::new()
+///     .output(AudioTarget::Default)
+///     .rt_audio("jack")
+///     .sample_rate(48000.0)
+///     .ksmps(64)
+///     .buffer_frames(256)
+///     .hardware_buffer_frames(1024)
+///     .displays(false)
+///     .build()
+///     .unwrap();
+/// cs.compile_csd("some.csd").unwrap();
+/// cs.start().unwrap();
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Options {
+    output: Option<AudioTarget>,
+    input: Option<AudioTarget>,
+    sample_rate: Option<f64>,
+    control_rate: Option<f64>,
+    ksmps: Option<u32>,
+    nchnls: Option<u32>,
+    nchnls_i: Option<u32>,
+    zero_dbfs: Option<f64>,
+    buffer_frames: Option<u32>,
+    hardware_buffer_frames: Option<u32>,
+    message_level: Option<u32>,
+    rt_audio: Option<String>,
+    rt_midi: Option<String>,
+    midi_input: Option<String>,
+    midi_output: Option<String>,
+    displays: Option<bool>,
+    daemon: Option<bool>,
+    realtime: Option<bool>,
+    sample_accurate: Option<bool>,
+    threads: Option<u32>,
+    extra: Vec<String>,
+}
+
+impl Options {
+    /// Creates an empty set of options.
+    pub fn new() -> Options {
+        Options::default()
+    }
+
+    /// Sets the audio output target(-o).
+    pub fn output(mut self, target: AudioTarget) -> Options {
+        self.output = Some(target);
+        self
+    }
+
+    /// Sets the audio input source(-i).
+    pub fn input(mut self, source: AudioTarget) -> Options {
+        self.input = Some(source);
+        self
+    }
+
+    /// Overrides the orchestra's sample rate(--sample-rate).
+    pub fn sample_rate(mut self, sr: f64) -> Options {
+        self.sample_rate = Some(sr);
+        self
+    }
+
+    /// Overrides the orchestra's control rate(--control-rate).
+    pub fn control_rate(mut self, kr: f64) -> Options {
+        self.control_rate = Some(kr);
+        self
+    }
+
+    /// Overrides the orchestra's ksmps(--ksmps).
+    pub fn ksmps(mut self, ksmps: u32) -> Options {
+        self.ksmps = Some(ksmps);
+        self
+    }
+
+    /// Overrides the orchestra's number of output channels(--nchnls).
+    pub fn nchnls(mut self, nchnls: u32) -> Options {
+        self.nchnls = Some(nchnls);
+        self
+    }
+
+    /// Overrides the orchestra's number of input channels(--nchnls_i).
+    pub fn nchnls_i(mut self, nchnls: u32) -> Options {
+        self.nchnls_i = Some(nchnls);
+        self
+    }
+
+    /// Overrides the orchestra's 0dBFS level(--0dbfs).
+    pub fn zero_dbfs(mut self, level: f64) -> Options {
+        self.zero_dbfs = Some(level);
+        self
+    }
+
+    /// Sets the software buffer size in sample frames(-b).
+    pub fn buffer_frames(mut self, frames: u32) -> Options {
+        self.buffer_frames = Some(frames);
+        self
+    }
+
+    /// Sets the hardware buffer size in sample frames(-B).
+    pub fn hardware_buffer_frames(mut self, frames: u32) -> Options {
+        self.hardware_buffer_frames = Some(frames);
+        self
+    }
+
+    /// Sets the message level bit field(-m), see [`Csound::set_message_level`](struct.Csound.html#method.set_message_level).
+    pub fn message_level(mut self, level: u32) -> Options {
+        self.message_level = Some(level);
+        self
+    }
+
+    /// Sets the realtime audio module(-+rtaudio), e.g "jack", "alsa", "pa_cb".
+    pub fn rt_audio(mut self, module: &str) -> Options {
+        self.rt_audio = Some(module.to_owned());
+        self
+    }
+
+    /// Sets the realtime MIDI module(-+rtmidi), e.g "portmidi", "alsa".
+    pub fn rt_midi(mut self, module: &str) -> Options {
+        self.rt_midi = Some(module.to_owned());
+        self
+    }
+
+    /// Sets the realtime MIDI input device(-M).
+    pub fn midi_input(mut self, device: &str) -> Options {
+        self.midi_input = Some(device.to_owned());
+        self
+    }
+
+    /// Sets the realtime MIDI output device(-Q).
+    pub fn midi_output(mut self, device: &str) -> Options {
+        self.midi_output = Some(device.to_owned());
+        self
+    }
+
+    /// Enables or disables the csound's displays(--displays, --nodisplays).
+    pub fn displays(mut self, enable: bool) -> Options {
+        self.displays = Some(enable);
+        self
+    }
+
+    /// Runs csound as a daemon, without an orchestra at start(--daemon).
+    pub fn daemon(mut self, enable: bool) -> Options {
+        self.daemon = Some(enable);
+        self
+    }
+
+    /// Enables the realtime priority mode(--realtime).
+    pub fn realtime(mut self, enable: bool) -> Options {
+        self.realtime = Some(enable);
+        self
+    }
+
+    /// Enables sample accurate scheduling of events(--sample-accurate).
+    pub fn sample_accurate(mut self, enable: bool) -> Options {
+        self.sample_accurate = Some(enable);
+        self
+    }
+
+    /// Sets the number of performance threads(-j).
+    pub fn threads(mut self, threads: u32) -> Options {
+        self.threads = Some(threads);
+        self
+    }
+
+    /// Adds a raw csound option which has not a typed counterpart.
+    /// It is passed as is to [`Csound::set_option`](struct.Csound.html#method.set_option).
+    pub fn option(mut self, option: &str) -> Options {
+        self.extra.push(option.to_owned());
+        self
+    }
+
+    /// Checks that the options are consistent.
+    /// # Returns
+    /// An [`Error::InvalidArgument`](enum.Error.html#variant.InvalidArgument) describing the first invalid field.
+    /// # Example
+    /// ```
+    /// use csound::Options;
+    ///
+    /// assert!(Options::new().sample_rate(44100.0).ksmps(32).validate().is_ok());
+    /// assert!(Options::new().ksmps(0).validate().is_err());
+    /// assert!(Options::new().rt_audio("ja ck").validate().is_err());
+    /// ```
+    pub fn validate(&self) -> Result<()> {
+        fn positive(value: Option<f64>, msg: &'static str) -> Result<()> {
+            match value {
+                Some(v) if !(v.is_finite() && v > 0.0) => Err(Error::InvalidArgument(msg)),
+                _ => Ok(()),
+            }
+        }
+        fn non_zero(value: Option<u32>, msg: &'static str) -> Result<()> {
+            match value {
+                Some(0) => Err(Error::InvalidArgument(msg)),
+                Some(v) if v > i32::MAX as u32 => Err(Error::InvalidArgument(msg)),
+                _ => Ok(()),
+            }
+        }
+        fn name(value: &Option<String>, msg: &'static str) -> Result<()> {
+            match value {
+                Some(s) if s.is_empty() || s.contains(char::is_whitespace) => {
+                    Err(Error::InvalidArgument(msg))
+                }
+                _ => Ok(()),
+            }
+        }
+        fn target(value: &Option<AudioTarget>, msg: &'static str) -> Result<()> {
+            match value {
+                Some(AudioTarget::DeviceName(s)) | Some(AudioTarget::File(s))
+                    if s.is_empty() || s.contains(char::is_whitespace) =>
+                {
+                    Err(Error::InvalidArgument(msg))
+                }
+                _ => Ok(()),
+            }
+        }
+
+        positive(
+            self.sample_rate,
+            "the sample rate must be a positive number",
+        )?;
+        positive(
+            self.control_rate,
+            "the control rate must be a positive number",
+        )?;
+        positive(self.zero_dbfs, "the 0dBFS level must be a positive number")?;
+        non_zero(self.ksmps, "ksmps must be greater than zero")?;
+        non_zero(
+            self.nchnls,
+            "the number of output channels must be greater than zero",
+        )?;
+        non_zero(
+            self.nchnls_i,
+            "the number of input channels must be greater than zero",
+        )?;
+        non_zero(
+            self.buffer_frames,
+            "the software buffer size must be greater than zero",
+        )?;
+        non_zero(
+            self.hardware_buffer_frames,
+            "the hardware buffer size must be greater than zero",
+        )?;
+        non_zero(
+            self.threads,
+            "the number of threads must be greater than zero",
+        )?;
+        if let (Some(sr), Some(kr), Some(ksmps)) = (self.sample_rate, self.control_rate, self.ksmps)
+        {
+            if (sr / kr - f64::from(ksmps)).abs() > 1e-9 {
+                return Err(Error::InvalidArgument(
+                    "the sample rate, control rate and ksmps are inconsistent (sr/kr != ksmps)",
+                ));
+            }
+        }
+        if let (Some(sw), Some(hw)) = (self.buffer_frames, self.hardware_buffer_frames) {
+            if hw < sw {
+                return Err(Error::InvalidArgument(
+                    "the hardware buffer can not be smaller than the software buffer",
+                ));
+            }
+        }
+        if let Some(level) = self.message_level {
+            if level > 0x7FFF {
+                return Err(Error::InvalidArgument("invalid message level"));
+            }
+        }
+        target(&self.output, "invalid audio output name")?;
+        target(&self.input, "invalid audio input name")?;
+        name(&self.rt_audio, "invalid realtime audio module name")?;
+        name(&self.rt_midi, "invalid realtime MIDI module name")?;
+        name(&self.midi_input, "invalid MIDI input device")?;
+        name(&self.midi_output, "invalid MIDI output device")?;
+        Ok(())
+    }
+
+    /// Validates and applies the options to *csound*.
+    ///
+    /// The numeric fields are written through [`Csound::set_params`](struct.Csound.html#method.set_params),
+    /// the input/output targets, modules and devices through [`Csound::set_option`](struct.Csound.html#method.set_option).
+    /// This must be called before compiling.
+    pub fn apply(&self, csound: &Csound) -> Result<()> {
+        self.validate()?;
+
+        let mut params = csound.get_params();
+        if let Some(sr) = self.sample_rate {
+            params.sample_rate_override = sr;
+        }
+        if let Some(kr) = self.control_rate {
+            params.control_rate_override = kr;
+        }
+        if let Some(ksmps) = self.ksmps {
+            params.ksmps_override = ksmps as i32;
+        }
+        if let Some(nchnls) = self.nchnls {
+            params.nchnls_override = nchnls as i32;
+        }
+        if let Some(nchnls) = self.nchnls_i {
+            params.nchnls_i_override = nchnls as i32;
+        }
+        if let Some(level) = self.zero_dbfs {
+            params.e0dbfs_override = level;
+        }
+        if let Some(frames) = self.buffer_frames {
+            params.buffer_frames = frames as i32;
+        }
+        if let Some(frames) = self.hardware_buffer_frames {
+            params.hardware_buffer_frames = frames as i32;
+        }
+        if let Some(level) = self.message_level {
+            params.message_level = level as i32;
+        }
+        if let Some(enable) = self.displays {
+            params.displays = enable;
+        }
+        if let Some(enable) = self.daemon {
+            params.daemon = enable;
+        }
+        if let Some(enable) = self.realtime {
+            params.realtime_mode = enable;
+        }
+        if let Some(enable) = self.sample_accurate {
+            params.sample_accurate = enable;
+        }
+        if let Some(threads) = self.threads {
+            params.number_of_threads = threads as i32;
+        }
+        csound.set_params(&params);
+
+        for option in self.to_flags() {
+            csound.set_option(&option)?;
+        }
+        Ok(())
+    }
+
+    /// Creates a new csound instance with these options applied.
+    pub fn build(&self) -> Result<Csound> {
+        let csound = Csound::new();
+        self.apply(&csound)?;
+        Ok(csound)
+    }
+
+    // Options which are not part of the CSOUND_PARAMS struct
+    fn to_flags(&self) -> Vec<String> {
+        let mut flags = Vec::new();
+        if let Some(module) = &self.rt_audio {
+            flags.push(format!("-+rtaudio={}", module));
+        }
+        if let Some(module) = &self.rt_midi {
+            flags.push(format!("-+rtmidi={}", module));
+        }
+        if let Some(output) = &self.output {
+            flags.push(match output {
+                AudioTarget::Default => "-odac".to_owned(),
+                AudioTarget::Device(n) => format!("-odac{}", n),
+                AudioTarget::DeviceName(name) => format!("-odac:{}", name),
+                AudioTarget::File(path) => format!("-o{}", path),
+                AudioTarget::Null => "-n".to_owned(),
+            });
+        }
+        if let Some(input) = &self.input {
+            match input {
+                AudioTarget::Default => flags.push("-iadc".to_owned()),
+                AudioTarget::Device(n) => flags.push(format!("-iadc{}", n)),
+                AudioTarget::DeviceName(name) => flags.push(format!("-iadc:{}", name)),
+                AudioTarget::File(path) => flags.push(format!("-i{}", path)),
+                AudioTarget::Null => {}
+            }
+        }
+        if let Some(device) = &self.midi_input {
+            flags.push(format!("-M{}", device));
+        }
+        if let Some(device) = &self.midi_output {
+            flags.push(format!("-Q{}", device));
+        }
+        flags.extend(self.extra.iter().cloned());
+        flags
+    }
+}
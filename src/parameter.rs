@@ -0,0 +1,242 @@
+use crate::channels::{ChannelBehavior, ChannelHints, ChannelInfo};
+use crate::csound::Csound;
+use crate::enums::ControlChannelType;
+use crate::error::Result;
+use crate::handle::CsoundHandle;
+
+/// A control channel described by its [`ChannelHints`](struct.ChannelHints.html).
+///
+/// It maps the channel value to a normalized value between 0 and 1 using the hints curve,
+/// which is what knobs, sliders and MIDI controllers work with:
+/// - `CHANNEL_LIN` maps the range linearly.
+/// - `CHANNEL_EXP` maps the range exponentially, the range limits must have the same sign and
+///   not be zero, otherwise it is mapped linearly.
+/// - `CHANNEL_INT` maps the range linearly and rounds the values to integers.
+/// - `CHANNEL_NO_HINTS` maps the range linearly if `min < max`, if not the values are not scaled.
+///
+/// Values are clamped to the range.
+/// # Example
+/// ```
+/// use csound::{ChannelBehavior, ChannelHints, Parameter};
+///
+/// let hints = ChannelHints {
+///     behav: ChannelBehavior::CHANNEL_EXP,
+///     dflt: 1000.0,
+///     min: 20.0,
+///     max: 20000.0,
+///     ..ChannelHints::default()
+/// };
+/// let cutoff = Parameter::new("cutoff", hints);
+/// assert!((cutoff.denormalize(0.5) - 632.455).abs() < 1e-3);
+/// assert_eq!(cutoff.normalize(20.0), 0.0);
+/// assert_eq!(cutoff.clamp(30000.0), 20000.0);
+///
+/// let voices = Parameter::new(
+///     "voices",
+///     ChannelHints {
+///         behav: ChannelBehavior::CHANNEL_INT,
+///         dflt: 4.0,
+///         min: 1.0,
+///         max: 8.0,
+///         ..ChannelHints::default()
+///     },
+/// );
+/// assert_eq!(voices.denormalize(0.5), 5.0);
+/// assert_eq!(voices.clamp(2.4), 2.0);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Parameter {
+    name: String,
+    hints: ChannelHints,
+}
+
+impl Parameter {
+    /// Creates a parameter for the control channel *name* described by *hints*.
+    pub fn new(name: &str, hints: ChannelHints) -> Parameter {
+        Parameter {
+            name: name.to_owned(),
+            hints,
+        }
+    }
+
+    /// Creates a parameter with the hints of the control channel *name*.
+    /// # Returns
+    /// The errors returned by [`Csound::get_channel_hints`](struct.Csound.html#method.get_channel_hints).
+    pub fn from_channel(csound: &Csound, name: &str) -> Result<Parameter> {
+        Ok(Parameter::new(name, csound.get_channel_hints(name)?))
+    }
+
+    /// Creates a parameter from the info returned by
+    /// [`Csound::list_channels`](struct.Csound.html#method.list_channels).
+    pub fn from_info(info: &ChannelInfo) -> Parameter {
+        Parameter::new(&info.name, info.hints.clone())
+    }
+
+    /// # Returns
+    /// A parameter for every control channel which has hints, or an error if csound
+    /// fails to list the channels.
+    pub fn list(csound: &Csound) -> Result<Vec<Parameter>> {
+        Ok(csound
+            .channel_list()?
+            .iter()
+            .filter(|info| {
+                info.kind() == ControlChannelType::CSOUND_CONTROL_CHANNEL
+                    && info.hints.behav != ChannelBehavior::CHANNEL_NO_HINTS
+            })
+            .map(Parameter::from_info)
+            .collect())
+    }
+
+    /// # Returns
+    /// The channel name.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// # Returns
+    /// The channel hints.
+    pub fn hints(&self) -> &ChannelHints {
+        &self.hints
+    }
+
+    /// # Returns
+    /// The default value, clamped to the range.
+    pub fn default_value(&self) -> f64 {
+        self.clamp(self.hints.dflt)
+    }
+
+    /// # Returns
+    /// *value* clamped to the range, integer channels round it and clamp it
+    /// to the integers of the range.
+    pub fn clamp(&self, value: f64) -> f64 {
+        let (mut min, mut max) = (self.hints.min, self.hints.max);
+        let value = match self.hints.behav {
+            ChannelBehavior::CHANNEL_INT => {
+                // If there is no integer in the range, its limits are kept
+                if min.ceil() <= max.floor() {
+                    min = min.ceil();
+                    max = max.floor();
+                }
+                value.round()
+            }
+            _ => value,
+        };
+        if self.has_range() {
+            value.clamp(min, max)
+        } else {
+            value
+        }
+    }
+
+    /// Converts a channel value to a normalized value.
+    /// # Returns
+    /// A value between 0 and 1.
+    pub fn normalize(&self, value: f64) -> f64 {
+        if !self.has_range() {
+            return value.clamp(0.0, 1.0);
+        }
+        let value = self.clamp(value);
+        let (min, max) = (self.hints.min, self.hints.max);
+        let normalized = if self.is_exponential() {
+            (value / min).ln() / (max / min).ln()
+        } else {
+            (value - min) / (max - min)
+        };
+        normalized.clamp(0.0, 1.0)
+    }
+
+    /// Converts a normalized value to a channel value.
+    /// # Arguments
+    /// * `normalized` A value between 0 and 1, it is clamped to that range.
+    pub fn denormalize(&self, normalized: f64) -> f64 {
+        let normalized = normalized.clamp(0.0, 1.0);
+        if !self.has_range() {
+            return normalized;
+        }
+        let (min, max) = (self.hints.min, self.hints.max);
+        let value = if self.is_exponential() {
+            min * (max / min).powf(normalized)
+        } else {
+            min + normalized * (max - min)
+        };
+        self.clamp(value)
+    }
+
+    /// # Returns
+    /// The current channel value.
+    pub fn get(&self, handle: &CsoundHandle) -> Result<f64> {
+        handle.get_control_channel(&self.name)
+    }
+
+    /// # Returns
+    /// The current channel value normalized.
+    pub fn get_normalized(&self, handle: &CsoundHandle) -> Result<f64> {
+        Ok(self.normalize(self.get(handle)?))
+    }
+
+    /// Writes *value* into the channel, clamped and rounded according to the hints.
+    /// # Returns
+    /// The value written.
+    pub fn set(&self, handle: &CsoundHandle, value: f64) -> Result<f64> {
+        let value = self.clamp(value);
+        handle.set_control_channel(&self.name, value)?;
+        Ok(value)
+    }
+
+    /// Writes the channel value for the *normalized* value.
+    /// # Returns
+    /// The value written.
+    pub fn set_normalized(&self, handle: &CsoundHandle, normalized: f64) -> Result<f64> {
+        let value = self.denormalize(normalized);
+        handle.set_control_channel(&self.name, value)?;
+        Ok(value)
+    }
+
+    /// Writes the default value into the channel.
+    /// # Returns
+    /// The value written.
+    pub fn reset(&self, handle: &CsoundHandle) -> Result<f64> {
+        self.set(handle, self.hints.dflt)
+    }
+
+    fn has_range(&self) -> bool {
+        self.hints.min < self.hints.max
+    }
+
+    fn is_exponential(&self) -> bool {
+        self.hints.behav == ChannelBehavior::CHANNEL_EXP && self.hints.min * self.hints.max > 0.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn integer(min: f64, max: f64) -> Parameter {
+        Parameter::new(
+            "int",
+            ChannelHints {
+                behav: ChannelBehavior::CHANNEL_INT,
+                min,
+                max,
+                ..ChannelHints::default()
+            },
+        )
+    }
+
+    #[test]
+    fn clamp_integer() {
+        let param = integer(0.5, 3.7);
+        assert_eq!(param.clamp(10.0), 3.0);
+        assert_eq!(param.clamp(-10.0), 1.0);
+        assert_eq!(param.clamp(0.6), 1.0);
+        assert_eq!(param.clamp(2.4), 2.0);
+        assert_eq!(param.denormalize(1.0), 3.0);
+        assert_eq!(param.denormalize(0.0), 1.0);
+
+        // Without any integer in the range, the limits are kept
+        let param = integer(0.2, 0.4);
+        assert_eq!(param.clamp(10.0), 0.4);
+        assert_eq!(param.clamp(0.0), 0.2);
+    }
+}
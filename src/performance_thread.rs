@@ -0,0 +1,352 @@
+use std::panic;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+
+use crate::csound::Csound;
+use crate::error::{Error, Result};
+use crate::handle::CsoundHandle;
+use crate::score_event::ScoreEvent;
+
+type ProcessCallback = Box<dyn FnMut(&Csound) + Send>;
+
+/// Messages sent from the host to the performance thread.
+/// They are applied between two control periods.
+enum Message {
+    Play,
+    Pause,
+    Stop,
+    // Stops the performance if it is paused, otherwise it continues until the end of the score
+    Join,
+    ScoreEvent {
+        event_type: char,
+        pfields: Vec<f64>,
+        time_offset: Option<f64>,
+    },
+    Event(ScoreEvent),
+    Input(String),
+    ReadScore(String),
+    ControlChannel(String, f64),
+    StringChannel(String, String),
+    ScoreOffset(f64),
+    Process(ProcessCallback),
+}
+
+/// Runs a Csound performance in a separate thread.
+///
+/// This is the equivalent to the *CsoundPerformanceThread* class of the Csound C++ interface.
+/// The performance thread takes the ownership of a [`Csound`](struct.Csound.html) instance
+/// and calls [`Csound::perform_ksmps`](struct.Csound.html#method.perform_ksmps) in a loop,
+/// until the end of the score is reached or [`PerformanceThread::stop`](struct.PerformanceThread.html#method.stop) is called.
+///
+/// Score events, score strings and channel values sent from any other thread are
+/// queued in a lock-free message queue and applied by the performance thread between control periods.
+/// The errors of those operations are collected, see [`PerformanceThread::take_errors`](struct.PerformanceThread.html#method.take_errors).
+/// The thread starts paused, call [`PerformanceThread::play`](struct.PerformanceThread.html#method.play)
+/// to start the performance.
+/// # Example
+/// ```no_run
+/// use csound::{Csound, PerformanceThread};
+///
+/// let mut csound = Csound::new();
+/// csound.compile_csd("some.csd").unwrap();
+/// csound.start().unwrap();
+///
+/// let mut perf_thread = PerformanceThread::new(csound);
+/// perf_thread.play();
+/// // Sends an event from this thread, it will be played by the performance thread
+/// perf_thread.send_score_event('i', &[1.0, 0.0, 2.0]);
+/// perf_thread.set_control_channel("amp", 0.5);
+/// // Blocks until the end of the score
+/// let status = perf_thread.join();
+/// ```
+pub struct PerformanceThread {
+    sender: Sender<Message>,
+    thread: Option<JoinHandle<i32>>,
+    csound: CsoundHandle,
+    status: Option<i32>,
+    running: Arc<AtomicBool>,
+    paused: Arc<AtomicBool>,
+    errors: Arc<Mutex<Vec<Error>>>,
+}
+
+impl PerformanceThread {
+    /// Creates a new performance thread which takes the ownership of `csound`.
+    ///
+    /// Some csd file, text or score have to be compiled first and then
+    /// [`Csound::start`](struct.Csound.html#method.start) must be called before passing
+    /// the instance to the performance thread.
+    /// The performance thread is created in paused state.
+    pub fn new(csound: Csound) -> PerformanceThread {
+        let (sender, receiver) = mpsc::channel();
+        let running = Arc::new(AtomicBool::new(true));
+        let paused = Arc::new(AtomicBool::new(true));
+        let errors = Arc::new(Mutex::new(Vec::new()));
+        let csound_handle = csound.shared_handle();
+
+        let thread = {
+            let running = Arc::clone(&running);
+            let paused = Arc::clone(&paused);
+            let errors = Arc::clone(&errors);
+            thread::spawn(move || {
+                // The flag is cleared even if the performance panics.
+                let _running = RunningFlag(running);
+                Self::perform(csound, receiver, &paused, &errors)
+            })
+        };
+
+        PerformanceThread {
+            sender,
+            thread: Some(thread),
+            csound: csound_handle,
+            status: None,
+            running,
+            paused,
+            errors,
+        }
+    }
+
+    // Performance loop, it runs in the spawned thread.
+    fn perform(
+        mut csound: Csound,
+        receiver: Receiver<Message>,
+        paused: &AtomicBool,
+        errors: &Mutex<Vec<Error>>,
+    ) -> i32 {
+        let mut process_cb: Option<ProcessCallback> = None;
+        let report = |result: Result<()>| {
+            if let Err(e) = result {
+                if let Ok(mut errors) = errors.lock() {
+                    errors.push(e);
+                }
+            }
+        };
+
+        let status = 'perform: loop {
+            // Applies every pending message before running the next control period,
+            // if the performance is paused, it waits for new messages.
+            loop {
+                let message = if paused.load(Ordering::SeqCst) {
+                    match receiver.recv() {
+                        Ok(message) => message,
+                        Err(_) => break 'perform 0,
+                    }
+                } else {
+                    match receiver.try_recv() {
+                        Ok(message) => message,
+                        Err(TryRecvError::Empty) => break,
+                        Err(TryRecvError::Disconnected) => break 'perform 0,
+                    }
+                };
+
+                match message {
+                    Message::Play => paused.store(false, Ordering::SeqCst),
+                    Message::Pause => paused.store(true, Ordering::SeqCst),
+                    Message::Stop => break 'perform 0,
+                    Message::Join => {
+                        if paused.load(Ordering::SeqCst) {
+                            break 'perform 0;
+                        }
+                    }
+                    Message::ScoreEvent {
+                        event_type,
+                        pfields,
+                        time_offset,
+                    } => report(match time_offset {
+                        Some(offset) => {
+                            csound.send_score_event_absolute(event_type, &pfields, offset)
+                        }
+                        None => csound.send_score_event(event_type, &pfields),
+                    }),
+                    Message::Event(event) => report(csound.send_event(&event)),
+                    Message::Input(message) => report(csound.send_input_message(&message)),
+                    Message::ReadScore(score) => report(csound.read_score(&score)),
+                    Message::ControlChannel(name, value) => {
+                        csound.set_control_channel(&name, value);
+                    }
+                    Message::StringChannel(name, content) => {
+                        csound.set_string_channel(&name, &content);
+                    }
+                    Message::ScoreOffset(offset) => csound.set_score_offset_seconds(offset),
+                    Message::Process(cb) => process_cb = Some(cb),
+                }
+            }
+
+            if let Some(cb) = process_cb.as_mut() {
+                cb(&csound);
+            }
+
+            if csound.perform_ksmps() {
+                break 1;
+            }
+        };
+
+        csound.stop();
+        status
+    }
+
+    fn send(&self, message: Message) {
+        // The receiver only goes away once the performance has finished,
+        // messages sent after that point are discarded.
+        let _ = self.sender.send(message);
+    }
+
+    /// Starts or continues the performance if it was paused.
+    pub fn play(&self) {
+        self.send(Message::Play);
+    }
+
+    /// Pauses the performance. The performance thread will wait for new messages
+    /// without consuming cpu time until [`PerformanceThread::play`](struct.PerformanceThread.html#method.play) is called.
+    pub fn pause(&self) {
+        self.send(Message::Pause);
+    }
+
+    /// Stops the performance. Pending messages sent before this call are applied first.
+    /// *Note*: It is not guaranteed that the performance thread has already stopped when this function returns,
+    /// use [`PerformanceThread::join`](struct.PerformanceThread.html#method.join) to wait for it.
+    pub fn stop(&self) {
+        self.send(Message::Stop);
+    }
+
+    /// Waits until the performance is finished or stopped.
+    ///
+    /// The messages sent before this call are applied first. If the performance is paused
+    /// after them, it is stopped, because it would never reach the end of the score.
+    /// # Returns
+    /// A positive value if the end of the score was reached or zero if
+    /// the performance was stopped.
+    /// # Panics
+    /// If the performance thread panicked, e.g. because some callback closure panicked,
+    /// the panic is propagated to the caller.
+    pub fn join(&mut self) -> i32 {
+        if let Some(thread) = self.thread.take() {
+            self.send(Message::Join);
+            match thread.join() {
+                Ok(status) => self.status = Some(status),
+                Err(payload) => panic::resume_unwind(payload),
+            }
+        }
+        self.status.unwrap_or(0)
+    }
+
+    /// Returns a [`CsoundHandle`](struct.CsoundHandle.html) to the csound instance owned by this
+    /// performance thread. Unlike the messages sent through the performance thread, the handle's
+    /// operations are applied immediately by csound.
+    pub fn handle(&self) -> CsoundHandle {
+        self.csound.clone()
+    }
+
+    /// # Returns
+    /// The errors of the score events, input messages and scores which the performance thread
+    /// failed to apply, in the order they happened, since the last call to this function.
+    pub fn take_errors(&self) -> Vec<Error> {
+        match self.errors.lock() {
+            Ok(mut errors) => errors.drain(..).collect(),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    /// # Returns
+    /// *true* while the performance thread is running, *false* once
+    /// the end of the score was reached or the performance was stopped.
+    pub fn is_running(&self) -> bool {
+        self.running.load(Ordering::SeqCst)
+    }
+
+    /// # Returns
+    /// *true* if the performance is paused.
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::SeqCst)
+    }
+
+    /// Sends a new score event to be performed by the performance thread.
+    /// # Arguments
+    /// * `event_type` is the score event type ('a', 'i', 'q', 'f', or 'e').
+    /// * `pfields` is a slice of f64 values with all the pfields for this event.
+    pub fn send_score_event(&self, event_type: char, pfields: &[f64]) {
+        self.send(Message::ScoreEvent {
+            event_type,
+            pfields: pfields.to_vec(),
+            time_offset: None,
+        });
+    }
+
+    /// Like [`PerformanceThread::send_score_event`](struct.PerformanceThread.html#method.send_score_event),
+    /// but the event is inserted at absolute time with respect to the start of performance,
+    /// or from an offset set with *time_offset*.
+    pub fn send_score_event_absolute(&self, event_type: char, pfields: &[f64], time_offset: f64) {
+        self.send(Message::ScoreEvent {
+            event_type,
+            pfields: pfields.to_vec(),
+            time_offset: Some(time_offset),
+        });
+    }
+
+    /// Sends a typed score event to be performed by the performance thread.
+    /// see [`Csound::send_event`](struct.Csound.html#method.send_event)
+    /// # Returns
+    /// The validation errors of the event, see [`ScoreEvent::validate`](enum.ScoreEvent.html#method.validate).
+    pub fn send_event(&self, event: &ScoreEvent) -> Result<()> {
+        event.validate()?;
+        self.send(Message::Event(event.clone()));
+        Ok(())
+    }
+
+    /// Sends a string (as if from a console), used for line events.
+    /// see [`Csound::send_input_message`](struct.Csound.html#method.send_input_message)
+    pub fn send_input_message(&self, message: &str) {
+        self.send(Message::Input(message.to_owned()));
+    }
+
+    /// Sends a score to be read, preprocessed and loaded by the performance thread.
+    /// see [`Csound::read_score`](struct.Csound.html#method.read_score)
+    pub fn read_score(&self, score: &str) {
+        self.send(Message::ReadScore(score.to_owned()));
+    }
+
+    /// Sets the value of a control channel before the next control period.
+    pub fn set_control_channel(&self, name: &str, value: f64) {
+        self.send(Message::ControlChannel(name.to_owned(), value));
+    }
+
+    /// Sets the content of a string channel before the next control period.
+    pub fn set_string_channel(&self, name: &str, content: &str) {
+        self.send(Message::StringChannel(name.to_owned(), content.to_owned()));
+    }
+
+    /// Sets the score offset, see [`Csound::set_score_offset_seconds`](struct.Csound.html#method.set_score_offset_seconds)
+    pub fn set_score_offset_seconds(&self, offset: f64) {
+        self.send(Message::ScoreOffset(offset));
+    }
+
+    /// Sets a function which will be called by the performance thread before every control period.
+    /// Any previous process callback is replaced.
+    /// # Arguments
+    /// * `f` A function/closure which will receive a reference to the performing csound instance.
+    ///   It is important to make sure no blocking operations are performed in the callback.
+    pub fn set_process_callback<F>(&self, f: F)
+    where
+        F: FnMut(&Csound) + Send + 'static,
+    {
+        self.send(Message::Process(Box::new(f)));
+    }
+}
+
+struct RunningFlag(Arc<AtomicBool>);
+
+impl Drop for RunningFlag {
+    fn drop(&mut self) {
+        self.0.store(false, Ordering::SeqCst);
+    }
+}
+
+impl Drop for PerformanceThread {
+    fn drop(&mut self) {
+        self.send(Message::Stop);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
@@ -0,0 +1,125 @@
+use crate::channels::PvsDataExt;
+use crate::csound::Csound;
+use crate::enums::{PvsFormat, WindowType};
+use crate::error::Result;
+
+/// A stream of frames over a csound's pvs channel.
+///
+/// Frames written by the [*pvsout*](http://www.csounds.com/manual/html/pvsout.html) opcode
+/// are read with [`PvsChannel::next_frame`](struct.PvsChannel.html#method.next_frame), which only returns a
+/// frame when the channel's `framecount` has advanced since the last call, so polling it every
+/// k-cycle does not yield the same analysis frame twice.
+/// Frames for the [*pvsin*](http://www.csounds.com/manual/html/pvsin.html) opcode are sent with
+/// [`PvsChannel::frame_mut`](struct.PvsChannel.html#method.frame_mut) and
+/// [`PvsChannel::send`](struct.PvsChannel.html#method.send).
+/// # Example
+/// ```no_run
+/// use csound::{Csound, PvsChannel};
+///
+/// let mut cs = Csound::new();
+/// cs.compile_csd("spectrum.csd").unwrap();
+/// cs.start().unwrap();
+///
+/// let mut spectrum = PvsChannel::new(&cs, "fout", 1024);
+/// while !cs.perform_ksmps() {
+///     if let Some(frame) = spectrum.next_frame().unwrap() {
+///         let peak = frame.bins().fold(0f32, |peak, (amp, _)| peak.max(amp));
+///         println!("frame {} peak: {}", frame.framecount, peak);
+///     }
+/// }
+///
+/// // Feeding a host computed spectrum into pvsin
+/// let mut fin = PvsChannel::new(&cs, "fin", 1024);
+/// for (i, (amp, freq)) in fin.frame_mut().bins_mut().enumerate() {
+///     *amp = 0.1;
+///     *freq = 220.0 * i as f32;
+/// }
+/// fin.send().unwrap();
+/// ```
+pub struct PvsChannel<'a> {
+    csound: &'a Csound,
+    name: String,
+    data: PvsDataExt,
+    last_framecount: Option<u32>,
+}
+
+impl<'a> PvsChannel<'a> {
+    /// Creates a stream over the pvs channel *name*.
+    /// # Arguments
+    /// * `N` The FFT size used by the opcodes writing or reading the channel.
+    pub fn new(csound: &'a Csound, name: &str, N: u32) -> PvsChannel<'a> {
+        PvsChannel::with_data(csound, name, PvsDataExt::new(N))
+    }
+
+    /// Creates a stream over the pvs channel *name* using *data* as the frame buffer.
+    /// Its analysis parameters are used to send frames with [`PvsChannel::send`](struct.PvsChannel.html#method.send).
+    pub fn with_data(csound: &'a Csound, name: &str, data: PvsDataExt) -> PvsChannel<'a> {
+        PvsChannel {
+            csound,
+            name: name.to_owned(),
+            data,
+            last_framecount: None,
+        }
+    }
+
+    /// # Returns
+    /// The channel name.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// # Returns
+    /// The last received frame, or the frame to be sent.
+    pub fn frame(&self) -> &PvsDataExt {
+        &self.data
+    }
+
+    /// # Returns
+    /// A mutable reference to the frame buffer, to be filled and sent with
+    /// [`PvsChannel::send`](struct.PvsChannel.html#method.send).
+    pub fn frame_mut(&mut self) -> &mut PvsDataExt {
+        &mut self.data
+    }
+
+    /// # Returns
+    /// The frame data format.
+    pub fn format(&self) -> PvsFormat {
+        self.data.format()
+    }
+
+    /// # Returns
+    /// The analysis window type.
+    pub fn window_type(&self) -> WindowType {
+        self.data.window_type()
+    }
+
+    /// Reads the channel.
+    /// # Returns
+    /// The new frame if the channel's `framecount` advanced since the last call, `None` otherwise.
+    /// The errors are the ones returned by [`Csound::get_pvs_channel`](struct.Csound.html#method.get_pvs_channel).
+    pub fn next_frame(&mut self) -> Result<Option<&PvsDataExt>> {
+        self.csound.get_pvs_channel(&self.name, &mut self.data)?;
+        if self.last_framecount == Some(self.data.framecount) {
+            return Ok(None);
+        }
+        self.last_framecount = Some(self.data.framecount);
+        Ok(Some(&self.data))
+    }
+
+    /// Sends the stream's frame to the channel, see [`PvsChannel::frame_mut`](struct.PvsChannel.html#method.frame_mut).
+    /// Its `framecount` is advanced by the analysis overlap, so *pvsin* takes it as a new frame.
+    /// The errors are the ones returned by [`Csound::set_pvs_channel`](struct.Csound.html#method.set_pvs_channel).
+    pub fn send(&mut self) -> Result<()> {
+        let previous = self.data.framecount;
+        self.data.framecount = self.next_framecount();
+        if let Err(e) = self.csound.set_pvs_channel(&self.name, &self.data) {
+            self.data.framecount = previous;
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    fn next_framecount(&self) -> u32 {
+        self.data.framecount.wrapping_add(self.data.overlap.max(1))
+    }
+}
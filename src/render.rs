@@ -0,0 +1,149 @@
+use std::path::Path;
+
+use crate::csound::Csound;
+use crate::error::{Error, Result};
+
+/// Audio samples rendered by a [`Renderer`](struct.Renderer.html).
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct RenderedAudio {
+    /// Interleaved audio samples, one frame holds `channels` samples.
+    pub samples: Vec<f64>,
+    /// The number of audio output channels (nchnls).
+    pub channels: u32,
+    /// The number of audio sample frames per second (sr).
+    pub sample_rate: f64,
+}
+
+impl RenderedAudio {
+    /// # Returns
+    /// The number of sample frames.
+    pub fn frames(&self) -> usize {
+        if self.channels == 0 {
+            return 0;
+        }
+        self.samples.len() / self.channels as usize
+    }
+
+    /// # Returns
+    /// The length of the rendered audio in seconds.
+    pub fn duration(&self) -> f64 {
+        if self.sample_rate > 0.0 {
+            self.frames() as f64 / self.sample_rate
+        } else {
+            0.0
+        }
+    }
+
+    /// # Returns
+    /// An iterator over the samples of the channel `channel` (starting from 0).
+    pub fn channel(&self, channel: u32) -> impl Iterator<Item = f64> + '_ {
+        self.samples
+            .iter()
+            .skip(channel as usize)
+            .step_by(self.channels.max(1) as usize)
+            .cloned()
+    }
+}
+
+/// Offline renderer which performs a csd until the end of its score
+/// and collects the output audio in memory.
+///
+/// The csound instance is set up to use host implemented audio IO and no sound
+/// output (`-n`), so no audio device or output file is opened, whatever the
+/// `<CsOptions>` section says. The audio is taken from the *spout* buffer after every control period.
+/// # Example
+/// ```
+/// use csound::Renderer;
+///
+/// static CSD: &str = "<CsoundSynthesizer>
+/// <CsInstruments>
+/// sr = 44100
+/// ksmps = 32
+/// nchnls = 2
+/// 0dbfs  = 1
+///
+/// instr 1
+/// asig oscil 0.5, 440
+///      outs asig, asig
+/// endin
+/// </CsInstruments>
+/// <CsScore>
+/// i 1 0 1
+/// e
+/// </CsScore>
+/// </CsoundSynthesizer>";
+///
+/// let audio = Renderer::new().render_csd_text(CSD).unwrap();
+/// assert_eq!(audio.channels, 2);
+/// assert_eq!(audio.sample_rate, 44100.0);
+/// println!("{} frames rendered", audio.frames());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Renderer {
+    options: Vec<String>,
+}
+
+impl Renderer {
+    /// Creates a new renderer without any extra option.
+    pub fn new() -> Renderer {
+        Renderer::default()
+    }
+
+    /// Adds a csound option(flag) which will be set before compiling the csd,
+    /// see [`Csound::set_option`](struct.Csound.html#method.set_option).
+    pub fn option(mut self, option: &str) -> Renderer {
+        self.options.push(option.to_owned());
+        self
+    }
+
+    /// Renders the csd file *csd*.
+    /// # Returns
+    /// The rendered audio or an error if the csd can not be compiled or performed.
+    pub fn render_csd<P>(&self, csd: P) -> Result<RenderedAudio>
+    where
+        P: AsRef<Path>,
+    {
+        let path = csd
+            .as_ref()
+            .to_str()
+            .ok_or(Error::InvalidArgument("the csd path is not valid UTF-8"))?;
+        self.render(|csound| csound.compile_csd(path))
+    }
+
+    /// Renders the csd contained in the string *csd_text*.
+    /// # Returns
+    /// The rendered audio or an error if the csd can not be compiled or performed.
+    pub fn render_csd_text<T>(&self, csd_text: T) -> Result<RenderedAudio>
+    where
+        T: AsRef<str>,
+    {
+        self.render(|csound| csound.compile_csd_text(csd_text))
+    }
+
+    fn render<F>(&self, compile: F) -> Result<RenderedAudio>
+    where
+        F: FnOnce(&mut Csound) -> Result<()>,
+    {
+        let mut csound = Csound::new();
+        csound.set_host_implemented_audioIO(1, 0);
+        for option in &self.options {
+            csound.set_option(option)?;
+        }
+        compile(&mut csound)?;
+        // The <CsOptions> are read while compiling, so this option
+        // overrides any output set there.
+        csound.set_option("-n")?;
+        csound.start()?;
+
+        let mut audio = RenderedAudio {
+            samples: Vec::new(),
+            channels: csound.output_channels(),
+            sample_rate: csound.get_sample_rate(),
+        };
+        let spout = csound.get_spout().ok_or(Error::NotStarted)?;
+        while !csound.perform_ksmps() {
+            audio.samples.extend_from_slice(spout.as_slice());
+        }
+        Ok(audio)
+    }
+}
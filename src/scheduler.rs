@@ -0,0 +1,669 @@
+use std::cmp::Ordering;
+use std::ffi::CString;
+use std::sync::{Arc, Mutex, MutexGuard, Weak};
+
+use csound_sys as raw;
+use libc::{c_char, c_double, c_long};
+
+use crate::csound::Inner;
+use crate::error::{Error, Result};
+use crate::handle::CsoundHandle;
+use crate::score_event::ScoreEvent;
+
+/// A performance time of the [`Scheduler`](struct.Scheduler.html).
+///
+/// The times are measured from the beginning of the score, so they follow the score when it is
+/// rewound or moved with [`Csound::set_score_offset_seconds`](struct.Csound.html#method.set_score_offset_seconds).
+/// They are also used as the period of repeated tasks.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Time {
+    /// Audio frames, as returned by [`Csound::get_current_sample_time`](struct.Csound.html#method.get_current_sample_time).
+    Samples(usize),
+    /// Seconds, as returned by [`Csound::get_score_time`](struct.Csound.html#method.get_score_time).
+    Seconds(f64),
+    /// Beats at the scheduler's tempo, see [`Scheduler::set_tempo`](struct.Scheduler.html#method.set_tempo).
+    Beats(f64),
+}
+
+impl Time {
+    fn is_valid(self) -> bool {
+        match self {
+            Time::Samples(_) => true,
+            Time::Seconds(t) | Time::Beats(t) => t.is_finite() && t >= 0.0,
+        }
+    }
+
+    fn is_zero(self) -> bool {
+        match self {
+            Time::Samples(t) => t == 0,
+            Time::Seconds(t) | Time::Beats(t) => t == 0.0,
+        }
+    }
+}
+
+enum Action {
+    Event(ScoreEvent),
+    Call(Box<dyn FnMut(&CsoundHandle) + Send>),
+}
+
+/// A score event or a closure to be run by the [`Scheduler`](struct.Scheduler.html) at a performance time.
+/// # Example
+/// ```
+/// use csound::{ScoreEvent, Task, Time};
+///
+/// // A note every beat, starting at the beat 4
+/// let pulse = Task::event(Time::Beats(4.0), ScoreEvent::instr(1, 0.0, 0.1)).every(Time::Beats(1.0));
+/// // Runs before the other tasks of the same control period
+/// let setup = Task::call(Time::Seconds(2.0), |handle| {
+///     handle.set_control_channel("gain", 0.5).unwrap();
+/// })
+/// .priority(10);
+/// ```
+pub struct Task {
+    time: Time,
+    action: Action,
+    priority: i32,
+    period: Option<Time>,
+}
+
+impl Task {
+    /// Creates a task which sends *event* at *time*.
+    pub fn event<E: Into<ScoreEvent>>(time: Time, event: E) -> Task {
+        Task {
+            time,
+            action: Action::Event(event.into()),
+            priority: 0,
+            period: None,
+        }
+    }
+
+    /// Creates a task which calls *f* at *time*, the closure receives a handle to the csound instance.
+    pub fn call<F>(time: Time, f: F) -> Task
+    where
+        F: FnMut(&CsoundHandle) + Send + 'static,
+    {
+        Task {
+            time,
+            action: Action::Call(Box::new(f)),
+            priority: 0,
+            period: None,
+        }
+    }
+
+    /// Tasks due in the same control period run by descending priority, the default is 0.
+    pub fn priority(mut self, priority: i32) -> Task {
+        self.priority = priority;
+        self
+    }
+
+    /// Repeats the task every *period* until it is cancelled.
+    /// Periods shorter than an audio frame, e.g after a tempo change, are rounded up to a frame.
+    pub fn every(mut self, period: Time) -> Task {
+        self.period = Some(period);
+        self
+    }
+
+    fn validate(&self) -> Result<()> {
+        if !self.time.is_valid() {
+            return Err(Error::InvalidArgument(
+                "the time must be a non negative number",
+            ));
+        }
+        if let Some(period) = self.period {
+            if !period.is_valid() || period.is_zero() {
+                return Err(Error::InvalidArgument(
+                    "the period must be a positive number",
+                ));
+            }
+        }
+        match &self.action {
+            Action::Event(event) => event.validate(),
+            Action::Call(_) => Ok(()),
+        }
+    }
+}
+
+impl std::fmt::Debug for Task {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let mut s = f.debug_struct("Task");
+        s.field("time", &self.time);
+        match &self.action {
+            Action::Event(event) => s.field("event", event),
+            Action::Call(_) => s.field("call", &"closure"),
+        };
+        s.field("priority", &self.priority)
+            .field("period", &self.period)
+            .finish()
+    }
+}
+
+/// Identifier of a scheduled task, used to inspect and cancel it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TaskId(u64);
+
+struct Entry {
+    id: TaskId,
+    task: Task,
+}
+
+// The engine time at the beginning of a control period
+#[derive(Clone, Copy)]
+struct Clock {
+    samples: f64,
+    seconds: f64,
+    sr: f64,
+    ksmps: f64,
+    beats: f64,
+    tempo: f64,
+}
+
+impl Clock {
+    // The seconds from the beginning of the period to *time*
+    fn seconds_to(&self, time: Time) -> f64 {
+        match time {
+            Time::Samples(t) => (t as f64 - self.samples) / self.sr,
+            Time::Seconds(t) => t - self.seconds,
+            Time::Beats(t) => (t - self.beats) * 60.0 / self.tempo,
+        }
+    }
+
+    // The offset of *time* in this period, the times which have passed are due at its beginning,
+    // None if *time* is after this period
+    fn offset(&self, time: Time) -> Option<f64> {
+        let offset = self.seconds_to(time);
+        if offset < self.ksmps / self.sr {
+            Some(offset.max(0.0))
+        } else {
+            None
+        }
+    }
+
+    // Periods shorter than a frame are rounded up to a frame, so the time always advances
+    fn add(&self, time: Time, period: Time) -> Time {
+        let frames = match period {
+            Time::Samples(p) => p as f64,
+            Time::Seconds(p) => p * self.sr,
+            Time::Beats(p) => p * 60.0 / self.tempo * self.sr,
+        };
+        let seconds = frames.max(1.0) / self.sr;
+        match (time, period) {
+            (Time::Samples(t), Time::Samples(p)) => Time::Samples(t + p),
+            (Time::Samples(t), _) => Time::Samples(t + (seconds * self.sr).round() as usize),
+            (Time::Seconds(t), Time::Seconds(p)) if frames >= 1.0 => Time::Seconds(t + p),
+            (Time::Seconds(t), _) => Time::Seconds(t + seconds),
+            (Time::Beats(t), Time::Beats(p)) if frames >= 1.0 => Time::Beats(t + p),
+            (Time::Beats(t), _) => Time::Beats(t + seconds * self.tempo / 60.0),
+        }
+    }
+}
+
+/// The tasks of a csound instance, shared by its schedulers and
+/// dispatched once per control period from the sense event callback.
+pub(crate) struct TaskQueue {
+    engine: Weak<Inner>,
+    next_id: u64,
+    entries: Vec<Entry>,
+    tempo: f64,
+    // The score time and the beat of the last tempo change
+    anchor: (f64, f64),
+    // The task which is running and whether it was cancelled meanwhile
+    running: Option<(TaskId, bool)>,
+}
+
+impl TaskQueue {
+    pub(crate) fn new(engine: &Arc<Inner>) -> TaskQueue {
+        TaskQueue {
+            engine: Arc::downgrade(engine),
+            next_id: 0,
+            entries: Vec::new(),
+            tempo: 60.0,
+            anchor: (0.0, 0.0),
+            running: None,
+        }
+    }
+
+    // The beat at the score time *seconds*, the beats start again from the beginning
+    // of the score when it is rewound before the last tempo change
+    fn beats_at(&mut self, seconds: f64) -> f64 {
+        if seconds < self.anchor.0 {
+            self.anchor = (0.0, 0.0);
+        }
+        self.anchor.1 + (seconds - self.anchor.0) * self.tempo / 60.0
+    }
+
+    fn set_tempo(&mut self, seconds: f64, tempo: f64) {
+        self.anchor = (seconds, self.beats_at(seconds));
+        self.tempo = tempo;
+    }
+
+    fn take_due(&mut self, clock: &Clock) -> Vec<(f64, Entry)> {
+        let mut due = Vec::new();
+        let mut i = 0;
+        while i < self.entries.len() {
+            match clock.offset(self.entries[i].task.time) {
+                Some(offset) => due.push((offset, self.entries.remove(i))),
+                None => i += 1,
+            }
+        }
+        due.sort_by(run_order);
+        due
+    }
+}
+
+// By descending priority, then by time, then in scheduling order
+fn run_order((a, x): &(f64, Entry), (b, y): &(f64, Entry)) -> Ordering {
+    y.task
+        .priority
+        .cmp(&x.task.priority)
+        .then(a.total_cmp(b))
+        .then(x.id.0.cmp(&y.id.0))
+}
+
+fn lock(queue: &Mutex<TaskQueue>) -> MutexGuard<'_, TaskQueue> {
+    // The queue is always left in a valid state
+    match queue.lock() {
+        Ok(guard) => guard,
+        Err(poisoned) => poisoned.into_inner(),
+    }
+}
+
+/// Removes the tasks when the csound instance is destroyed,
+/// the closures could own schedulers which keep the queue alive.
+pub(crate) fn close(queue: &Mutex<TaskQueue>) {
+    let mut queue = lock(queue);
+    queue.entries.clear();
+    queue.running = None;
+}
+
+/// Runs the tasks due in the current control period.
+///
+/// The queue is not locked while the tasks run, so they can schedule or cancel other tasks.
+/// The tasks they schedule run from the next control period.
+pub(crate) unsafe fn dispatch(queue: &Mutex<TaskQueue>, csound: *mut raw::CSOUND) {
+    let (clock, engine, mut due) = {
+        let mut queue = lock(queue);
+        if queue.entries.is_empty() {
+            return;
+        }
+        let seconds = raw::csoundGetScoreTime(csound) as f64;
+        let clock = Clock {
+            samples: raw::csoundGetCurrentTimeSamples(csound) as f64,
+            seconds,
+            sr: raw::csoundGetSr(csound) as f64,
+            ksmps: raw::csoundGetKsmps(csound) as f64,
+            beats: queue.beats_at(seconds),
+            tempo: queue.tempo,
+        };
+        let due = queue.take_due(&clock);
+        (clock, queue.engine.clone(), due)
+    };
+    while !due.is_empty() {
+        let (offset, mut entry) = due.remove(0);
+        lock(queue).running = Some((entry.id, false));
+        match &mut entry.task.action {
+            Action::Event(event) => send_event(csound, event, offset),
+            Action::Call(f) => {
+                if let Some(engine) = engine.upgrade() {
+                    f(&CsoundHandle { engine });
+                }
+            }
+        }
+        let mut queue = lock(queue);
+        let cancelled = matches!(queue.running.take(), Some((_, true)));
+        if let Some(period) = entry.task.period {
+            if !cancelled {
+                // The repetitions missed, e.g if the task was scheduled in the past, are skipped
+                entry.task.time = clock.add(entry.task.time, period);
+                while clock.seconds_to(entry.task.time) < 0.0 {
+                    entry.task.time = clock.add(entry.task.time, period);
+                }
+                // Tasks repeated more than once per control period are due again
+                match clock.offset(entry.task.time) {
+                    Some(offset) => {
+                        let next = (offset, entry);
+                        let index = due
+                            .partition_point(|other| run_order(other, &next) != Ordering::Greater);
+                        due.insert(index, next);
+                    }
+                    None => queue.entries.push(entry),
+                }
+            }
+        }
+    }
+}
+
+// Sends the event with its start delayed by *offset* seconds
+unsafe fn send_event(csound: *mut raw::CSOUND, event: &ScoreEvent, offset: f64) {
+    let mut event = event.clone();
+    if let ScoreEvent::Instr { start, .. } = &mut event {
+        *start += offset;
+    }
+    match event.numeric_pfields() {
+        Some(pfields) => {
+            raw::csoundScoreEvent(
+                csound,
+                event.opcode() as c_char,
+                pfields.as_ptr() as *const c_double,
+                pfields.len() as c_long,
+            );
+        }
+        None => {
+            // The event was validated when it was scheduled
+            if let Ok(message) = CString::new(event.to_string()) {
+                raw::csoundInputMessage(csound, message.as_ptr());
+            }
+        }
+    }
+}
+
+/// Runs score events and closures at future performance times.
+///
+/// It is created by [`Csound::scheduler`](struct.Csound.html#method.scheduler), all the
+/// schedulers of a csound instance share the same tasks. The scheduler can be cloned and
+/// sent to other threads.
+/// The tasks are run from the sense event callback at the beginning of the control period
+/// which contains their time. The start time of the `i` events is delayed by the time from
+/// the beginning of the period, so the notes start at the exact sample if csound runs with the
+/// `--sample-accurate` option. The tasks scheduled by a running task wait at least until the next
+/// control period, even if their time has passed.
+/// Unlike the events sent to csound, the scheduled tasks can be inspected and cancelled until they run.
+/// # Example
+/// ```no_run
+/// use csound::{Csound, ScoreEvent, Task, Time};
+///
+/// let mut cs = Csound::new();
+/// cs.compile_csd("synth.csd").unwrap();
+/// cs.start().unwrap();
+///
+/// let scheduler = cs.scheduler();
+/// scheduler.set_tempo(120.0).unwrap();
+/// let pulse = scheduler
+///     .schedule(Task::event(Time::Beats(0.0), ScoreEvent::instr(1, 0.0, 0.25)).every(Time::Beats(1.0)))
+///     .unwrap();
+/// let stop = scheduler.clone();
+/// scheduler
+///     .schedule(Task::call(Time::Seconds(10.0), move |_| {
+///         stop.cancel(pulse);
+///     }))
+///     .unwrap();
+/// while !cs.perform_ksmps() {}
+/// ```
+#[derive(Clone)]
+pub struct Scheduler {
+    queue: Arc<Mutex<TaskQueue>>,
+}
+
+impl Scheduler {
+    pub(crate) fn new(queue: Arc<Mutex<TaskQueue>>) -> Scheduler {
+        Scheduler { queue }
+    }
+
+    /// Adds *task* to the scheduler, tasks whose time has passed run in the next control period.
+    /// # Returns
+    /// The task identifier, or an `InvalidArgument` error if a time is negative,
+    /// the period is zero or the event is not valid.
+    pub fn schedule(&self, task: Task) -> Result<TaskId> {
+        task.validate()?;
+        let mut queue = lock(&self.queue);
+        let id = TaskId(queue.next_id);
+        queue.next_id += 1;
+        queue.entries.push(Entry { id, task });
+        Ok(id)
+    }
+
+    /// Removes a task, repeated tasks are not run again.
+    /// # Returns
+    /// false if the task has already run or it was cancelled.
+    pub fn cancel(&self, id: TaskId) -> bool {
+        let mut queue = lock(&self.queue);
+        let len = queue.entries.len();
+        queue.entries.retain(|entry| entry.id != id);
+        if len != queue.entries.len() {
+            return true;
+        }
+        match queue.running.as_mut() {
+            Some((running, cancelled)) if *running == id && !*cancelled => {
+                *cancelled = true;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Removes all the tasks.
+    pub fn clear(&self) {
+        let mut queue = lock(&self.queue);
+        queue.entries.clear();
+        if let Some((_, cancelled)) = queue.running.as_mut() {
+            *cancelled = true;
+        }
+    }
+
+    /// # Returns
+    /// The next time of the task *id*, or None if it is not scheduled.
+    pub fn time_of(&self, id: TaskId) -> Option<Time> {
+        lock(&self.queue)
+            .entries
+            .iter()
+            .find(|entry| entry.id == id)
+            .map(|entry| entry.task.time)
+    }
+
+    /// # Returns
+    /// The number of tasks waiting to run.
+    pub fn len(&self) -> usize {
+        lock(&self.queue).entries.len()
+    }
+
+    /// # Returns
+    /// true if there are not tasks waiting to run.
+    pub fn is_empty(&self) -> bool {
+        lock(&self.queue).entries.is_empty()
+    }
+
+    /// Sets the tempo in beats per minute used by the [`Time::Beats`](enum.Time.html#variant.Beats) times,
+    /// the default is 60. The beats already elapsed are not changed, unless the score is rewound
+    /// before the tempo change, then the beats are counted again from the beginning of the score
+    /// at the current tempo.
+    pub fn set_tempo(&self, bpm: f64) -> Result<()> {
+        if !(bpm.is_finite() && bpm > 0.0) {
+            return Err(Error::InvalidArgument(
+                "the tempo must be a positive number",
+            ));
+        }
+        let seconds = self.now_seconds();
+        lock(&self.queue).set_tempo(seconds, bpm);
+        Ok(())
+    }
+
+    /// # Returns
+    /// The tempo in beats per minute.
+    pub fn tempo(&self) -> f64 {
+        lock(&self.queue).tempo
+    }
+
+    /// # Returns
+    /// The current performance time in samples, or 0 if the csound instance was destroyed.
+    pub fn now_samples(&self) -> usize {
+        match self.engine() {
+            Some(engine) => unsafe { raw::csoundGetCurrentTimeSamples(engine.csound) as usize },
+            None => 0,
+        }
+    }
+
+    /// # Returns
+    /// The current score time in seconds, or 0 if the csound instance was destroyed.
+    pub fn now_seconds(&self) -> f64 {
+        match self.engine() {
+            Some(engine) => unsafe { raw::csoundGetScoreTime(engine.csound) as f64 },
+            None => 0.0,
+        }
+    }
+
+    /// # Returns
+    /// The current beat.
+    pub fn now_beats(&self) -> f64 {
+        let seconds = self.now_seconds();
+        lock(&self.queue).beats_at(seconds)
+    }
+
+    // The scheduler doesn't keep the instance alive, because the tasks can own schedulers
+    fn engine(&self) -> Option<Arc<Inner>> {
+        lock(&self.queue).engine.upgrade()
+    }
+}
+
+impl std::fmt::Debug for Scheduler {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("Scheduler")
+            .field("tasks", &self.len())
+            .field("tempo", &self.tempo())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clock() -> Clock {
+        Clock {
+            samples: 44100.0,
+            seconds: 1.0,
+            sr: 44100.0,
+            ksmps: 64.0,
+            beats: 2.0,
+            tempo: 120.0,
+        }
+    }
+
+    fn queue(tasks: Vec<Task>) -> TaskQueue {
+        TaskQueue {
+            engine: Weak::new(),
+            next_id: tasks.len() as u64,
+            entries: tasks
+                .into_iter()
+                .enumerate()
+                .map(|(id, task)| Entry {
+                    id: TaskId(id as u64),
+                    task,
+                })
+                .collect(),
+            tempo: 120.0,
+            anchor: (0.0, 0.0),
+            running: None,
+        }
+    }
+
+    #[test]
+    fn offset() {
+        let clock = clock();
+        assert_eq!(clock.offset(Time::Samples(44100)), Some(0.0));
+        assert_eq!(clock.offset(Time::Samples(44132)), Some(32.0 / 44100.0));
+        assert_eq!(clock.offset(Time::Samples(44164)), None);
+        // The times which have passed are due at the beginning of the period
+        assert_eq!(clock.offset(Time::Seconds(0.5)), Some(0.0));
+        assert_eq!(clock.offset(Time::Seconds(1.0)), Some(0.0));
+        assert_eq!(clock.offset(Time::Seconds(1.01)), None);
+        assert_eq!(clock.offset(Time::Beats(2.0)), Some(0.0));
+        assert_eq!(clock.offset(Time::Beats(2.0009765625)), Some(0.00048828125));
+        assert_eq!(clock.offset(Time::Beats(2.01)), None);
+    }
+
+    #[test]
+    fn add() {
+        let clock = clock();
+        assert_eq!(
+            clock.add(Time::Samples(10), Time::Samples(5)),
+            Time::Samples(15)
+        );
+        assert_eq!(
+            clock.add(Time::Samples(10), Time::Seconds(1.0)),
+            Time::Samples(44110)
+        );
+        assert_eq!(
+            clock.add(Time::Samples(10), Time::Beats(1.0)),
+            Time::Samples(22060)
+        );
+        assert_eq!(
+            clock.add(Time::Seconds(1.0), Time::Beats(1.0)),
+            Time::Seconds(1.5)
+        );
+        assert_eq!(
+            clock.add(Time::Beats(1.0), Time::Seconds(1.0)),
+            Time::Beats(3.0)
+        );
+        assert_eq!(
+            clock.add(Time::Beats(1.0), Time::Samples(22050)),
+            Time::Beats(2.0)
+        );
+    }
+
+    #[test]
+    fn add_shorter_than_a_frame() {
+        let clock = clock();
+        assert_eq!(
+            clock.add(Time::Samples(10), Time::Seconds(1e-9)),
+            Time::Samples(11)
+        );
+        assert_eq!(
+            clock.add(Time::Seconds(5.0), Time::Seconds(1e-20)),
+            Time::Seconds(5.0 + 1.0 / 44100.0)
+        );
+        let next = clock.add(Time::Beats(5.0), Time::Beats(1e-20));
+        assert_eq!(next, Time::Beats(5.0 + 1.0 / 44100.0 * 2.0));
+        assert!(clock.seconds_to(next) > clock.seconds_to(Time::Beats(5.0)));
+    }
+
+    #[test]
+    fn take_due_order() {
+        let task = |time, priority| Task::call(time, |_| {}).priority(priority);
+        let mut queue = queue(vec![
+            task(Time::Samples(44110), 0),
+            task(Time::Samples(44100), 0),
+            task(Time::Samples(44200), 5),
+            task(Time::Samples(44120), 1),
+            task(Time::Samples(44110), 0),
+            task(Time::Seconds(0.5), 0),
+        ]);
+        let due: Vec<_> = queue
+            .take_due(&clock())
+            .into_iter()
+            .map(|(_, entry)| entry.id.0)
+            .collect();
+        // By descending priority, then by time, then in scheduling order
+        assert_eq!(due, [3, 1, 5, 0, 4]);
+        assert_eq!(queue.entries.len(), 1);
+        assert_eq!(queue.entries[0].id, TaskId(2));
+    }
+
+    #[test]
+    fn beats_follow_the_score_time() {
+        let mut queue = queue(Vec::new());
+        assert_eq!(queue.beats_at(1.0), 2.0);
+        queue.set_tempo(1.0, 60.0);
+        assert_eq!(queue.beats_at(3.0), 4.0);
+        // A score offset moves the beats as well
+        assert_eq!(queue.beats_at(10.0), 11.0);
+        // Rewinding after the tempo change
+        assert_eq!(queue.beats_at(2.0), 3.0);
+        // Rewinding before the tempo change counts again from the beginning at the current tempo
+        assert_eq!(queue.beats_at(0.5), 0.5);
+        assert_eq!(queue.beats_at(2.0), 2.0);
+    }
+
+    #[test]
+    fn validate() {
+        let task = |time| Task::call(time, |_| {});
+        assert!(task(Time::Seconds(-1.0)).validate().is_err());
+        assert!(task(Time::Beats(f64::NAN)).validate().is_err());
+        assert!(task(Time::Beats(0.0))
+            .every(Time::Samples(0))
+            .validate()
+            .is_err());
+        assert!(task(Time::Beats(0.0))
+            .every(Time::Beats(1.0))
+            .validate()
+            .is_ok());
+    }
+}
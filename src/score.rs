@@ -0,0 +1,1139 @@
+//! Parsing and formatting of Csound scores.
+//!
+//! [`Score::parse`](struct.Score.html#method.parse) reads the standard numeric score
+//! statements into a [`Score`](struct.Score.html), which can be inspected, rewritten and
+//! written back as text with its `Display` implementation, e.g to pass it to
+//! [`Csound::read_score`](../struct.Csound.html#method.read_score).
+//!
+//! The parser doesn't expand anything, carry (`.`), ramps (`<`, `(`, `)`, `~`), the `+` and `^+`
+//! start shortcuts, expressions and macros are kept as written, so they are still
+//! evaluated by csound. Comments and macro definitions are kept too.
+//! A statement continues across comments and line breaks until the next statement,
+//! the comment after its last p-field is the statement's comment and the comments between
+//! its p-fields are kept in place.
+//!
+//! Formatting keeps the layout of the parsed text, the items and p-fields which are not modified
+//! are written as they were, with their spacing, so an unmodified score is written back byte for byte.
+//! Modified p-fields are written in their shortest form and new items in their own line,
+//! with single spaces between the p-fields.
+//!
+//! # Example
+//! ```
+//! use csound::score::{Opcode, Score, Value};
+//!
+//! let mut score = Score::parse(
+//!     "f 1 0 8192 10 1 ; a sine wave
+//! i 1 0 1 440
+//! i . + . <
+//! i . + . 880
+//! e",
+//! )
+//! .unwrap();
+//!
+//! // Transpose an octave down
+//! for note in score.statements_mut().filter(|s| s.opcode == Opcode::Instr) {
+//!     if let Some(Value::Number(freq)) = note.pfield_mut(4) {
+//!         *freq /= 2.0;
+//!     }
+//! }
+//! assert_eq!(
+//!     score.to_string(),
+//!     "f 1 0 8192 10 1 ; a sine wave
+//! i 1 0 1 220
+//! i . + . <
+//! i . + . 440
+//! e"
+//! );
+//! assert_eq!(Score::parse(&score.to_string()).unwrap(), score);
+//! ```
+
+use std::fmt;
+use std::mem;
+use std::str::FromStr;
+
+use crate::error::{Error, Result};
+use crate::score_event::{PField, ScoreEvent};
+
+/// The opcode of a score statement.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Opcode {
+    /// `i` plays an instrument.
+    Instr,
+    /// `f` creates a function table.
+    FTable,
+    /// `a` advances the score time.
+    Advance,
+    /// `b` resets the clock base.
+    ClockBase,
+    /// `t` sets the tempo.
+    Tempo,
+    /// `s` ends a section.
+    Section,
+    /// `e` ends the score.
+    End,
+    /// `m` marks a point to be repeated.
+    Mark,
+    /// `n` repeats a marked section.
+    RepeatMark,
+    /// `r` starts a repeated section.
+    Repeat,
+    /// `x` skips the rest of the section.
+    Skip,
+    /// `q` mutes an instrument.
+    Mute,
+}
+
+impl Opcode {
+    /// # Returns
+    /// The opcode of the letter *c*, or None if it is not a supported statement.
+    pub fn from_char(c: char) -> Option<Opcode> {
+        Some(match c {
+            'i' => Opcode::Instr,
+            'f' => Opcode::FTable,
+            'a' => Opcode::Advance,
+            'b' => Opcode::ClockBase,
+            't' => Opcode::Tempo,
+            's' => Opcode::Section,
+            'e' => Opcode::End,
+            'm' => Opcode::Mark,
+            'n' => Opcode::RepeatMark,
+            'r' => Opcode::Repeat,
+            'x' => Opcode::Skip,
+            'q' => Opcode::Mute,
+            _ => return None,
+        })
+    }
+
+    /// # Returns
+    /// The letter of the statement.
+    pub fn to_char(self) -> char {
+        match self {
+            Opcode::Instr => 'i',
+            Opcode::FTable => 'f',
+            Opcode::Advance => 'a',
+            Opcode::ClockBase => 'b',
+            Opcode::Tempo => 't',
+            Opcode::Section => 's',
+            Opcode::End => 'e',
+            Opcode::Mark => 'm',
+            Opcode::RepeatMark => 'n',
+            Opcode::Repeat => 'r',
+            Opcode::Skip => 'x',
+            Opcode::Mute => 'q',
+        }
+    }
+
+    // m, n and r take the name of a section
+    fn takes_name(self) -> bool {
+        matches!(self, Opcode::Mark | Opcode::RepeatMark | Opcode::Repeat)
+    }
+}
+
+/// A p-field of a score statement, as written in the score.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    /// A number.
+    Number(f64),
+    /// A string between double quotes.
+    String(String),
+    /// A section name of the `m`, `n` and `r` statements.
+    Name(String),
+    /// `.` carries the value of the previous statement.
+    Carry,
+    /// `!` stops carrying values in the rest of the statement.
+    NoCarry,
+    /// `+` in p2, the end time of the previous note.
+    NextStart,
+    /// `^+x` or `^-x` in p2, the start time of the previous note plus *x*.
+    RelativeStart(f64),
+    /// `npN`, the p-field *N* of the next note.
+    NextPField(u32),
+    /// `ppN`, the p-field *N* of the previous note.
+    PreviousPField(u32),
+    /// `<` ramps linearly between the surrounding values.
+    LinearRamp,
+    /// `(` ramps exponentially between the surrounding values.
+    ExpRampOpen,
+    /// `)` ramps exponentially between the surrounding values.
+    ExpRampClose,
+    /// `~` takes a random value between the surrounding values.
+    RandomRamp,
+    /// An expression between brackets, without the brackets.
+    Expression(String),
+    /// A macro expansion, the name and its arguments without the `$`.
+    Macro(String),
+}
+
+impl From<f64> for Value {
+    fn from(number: f64) -> Self {
+        Value::Number(number)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(s: &str) -> Self {
+        Value::String(s.to_owned())
+    }
+}
+
+impl From<PField> for Value {
+    fn from(pfield: PField) -> Self {
+        match pfield {
+            PField::Number(number) => Value::Number(number),
+            PField::String(s) => Value::String(s),
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Number(number) => write!(f, "{}", number),
+            Value::String(s) => write!(f, "\"{}\"", s),
+            Value::Name(name) => write!(f, "{}", name),
+            Value::Carry => write!(f, "."),
+            Value::NoCarry => write!(f, "!"),
+            Value::NextStart => write!(f, "+"),
+            Value::RelativeStart(offset) if offset.is_sign_negative() => {
+                write!(f, "^-{}", -offset)
+            }
+            Value::RelativeStart(offset) => write!(f, "^+{}", offset),
+            Value::NextPField(p) => write!(f, "np{}", p),
+            Value::PreviousPField(p) => write!(f, "pp{}", p),
+            Value::LinearRamp => write!(f, "<"),
+            Value::ExpRampOpen => write!(f, "("),
+            Value::ExpRampClose => write!(f, ")"),
+            Value::RandomRamp => write!(f, "~"),
+            Value::Expression(expression) => write!(f, "[{}]", expression),
+            Value::Macro(text) => write!(f, "${}", text),
+        }
+    }
+}
+
+/// A score statement.
+///
+/// Statements are compared by their opcode, p-fields and comment, regardless of how they were written.
+#[derive(Debug, Clone)]
+pub struct Statement {
+    pub opcode: Opcode,
+    /// The p-fields starting from p1.
+    pub pfields: Vec<Value>,
+    /// The comment after the last p-field, without the `;`.
+    pub comment: Option<String>,
+    layout: StatementLayout,
+}
+
+// How a parsed statement was written
+#[derive(Debug, Clone, Default)]
+struct StatementLayout {
+    // The text before each p-field, the spaces, line breaks and comments inside the statement
+    gaps: Vec<String>,
+    // Each p-field as parsed and its text
+    tokens: Vec<(Value, String)>,
+    // The spaces before the comment
+    comment_gap: Option<String>,
+}
+
+impl Statement {
+    /// Creates a statement without comment.
+    pub fn new(opcode: Opcode, pfields: Vec<Value>) -> Statement {
+        Statement {
+            opcode,
+            pfields,
+            comment: None,
+            layout: StatementLayout::default(),
+        }
+    }
+
+    /// # Returns
+    /// The p-field *index*, starting from p1, or None if the statement doesn't have it.
+    pub fn pfield(&self, index: usize) -> Option<&Value> {
+        index.checked_sub(1).and_then(|i| self.pfields.get(i))
+    }
+
+    /// Mutable version of [`Statement::pfield`](struct.Statement.html#method.pfield).
+    pub fn pfield_mut(&mut self, index: usize) -> Option<&mut Value> {
+        index
+            .checked_sub(1)
+            .and_then(move |i| self.pfields.get_mut(i))
+    }
+}
+
+impl From<&ScoreEvent> for Statement {
+    fn from(event: &ScoreEvent) -> Self {
+        let opcode = match event {
+            ScoreEvent::Instr { .. } => Opcode::Instr,
+            ScoreEvent::FTable { .. } => Opcode::FTable,
+            ScoreEvent::Advance { .. } => Opcode::Advance,
+            ScoreEvent::Mute { .. } => Opcode::Mute,
+            ScoreEvent::End => Opcode::End,
+        };
+        let pfields = event.pfields().into_iter().map(Value::from).collect();
+        Statement::new(opcode, pfields)
+    }
+}
+
+impl PartialEq for Statement {
+    fn eq(&self, other: &Statement) -> bool {
+        self.opcode == other.opcode
+            && self.pfields == other.pfields
+            && self.comment == other.comment
+    }
+}
+
+impl fmt::Display for Statement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.opcode.to_char())?;
+        for (i, pfield) in self.pfields.iter().enumerate() {
+            f.write_str(self.layout.gaps.get(i).map_or(" ", String::as_str))?;
+            match self.layout.tokens.get(i) {
+                Some((value, text)) if value == pfield => f.write_str(text)?,
+                _ => write!(f, "{}", pfield)?,
+            }
+        }
+        if let Some(comment) = &self.comment {
+            let gap = self.layout.comment_gap.as_deref().unwrap_or(" ");
+            write!(f, "{};{}", gap, comment)?;
+        }
+        Ok(())
+    }
+}
+
+/// A macro definition, `#define NAME(A'B) #body#`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MacroDefinition {
+    pub name: String,
+    /// The argument names, None if the macro doesn't take arguments.
+    pub args: Option<Vec<String>>,
+    /// The body as written, with the `\#` escapes.
+    pub body: String,
+}
+
+/// An element of a score.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Item {
+    Statement(Statement),
+    /// A `;` comment in its own line, without the `;`.
+    Comment(String),
+    /// A `/* */` comment, without the delimiters.
+    BlockComment(String),
+    Define(MacroDefinition),
+    /// `#undef NAME`
+    Undef(String),
+    /// `#include "file"`
+    Include(String),
+    /// A macro expanded in its own line, e.g a macro with several statements.
+    Macro(String),
+    /// An empty line.
+    Blank,
+}
+
+impl fmt::Display for Item {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Item::Statement(statement) => write!(f, "{}", statement),
+            Item::Comment(comment) => write!(f, ";{}", comment),
+            Item::BlockComment(comment) => write!(f, "/*{}*/", comment),
+            Item::Define(definition) => {
+                write!(f, "#define {}", definition.name)?;
+                if let Some(args) = &definition.args {
+                    write!(f, "({})", args.join("'"))?;
+                }
+                write!(f, " #{}#", definition.body)
+            }
+            Item::Undef(name) => write!(f, "#undef {}", name),
+            Item::Include(file) => write!(f, "#include \"{}\"", file),
+            Item::Macro(text) => write!(f, "${}", text),
+            Item::Blank => Ok(()),
+        }
+    }
+}
+
+impl Item {
+    // Nothing can follow the item in its line
+    fn ends_line(&self) -> bool {
+        match self {
+            Item::Comment(_) => true,
+            Item::Statement(statement) => statement.comment.is_some(),
+            _ => false,
+        }
+    }
+}
+
+/// A parsed score, see the [module documentation](index.html).
+///
+/// Scores are compared by their items, regardless of how they were written.
+#[derive(Debug, Clone, Default)]
+pub struct Score {
+    items: Vec<Item>,
+    layouts: Vec<ItemLayout>,
+}
+
+// How a parsed item was written
+#[derive(Debug, Clone)]
+struct ItemLayout {
+    // The spaces before the item in its line
+    lead: String,
+    // The item as parsed and its text, which is written while the item is not modified.
+    // Statements and comments keep their text themselves.
+    source: Option<(Item, String)>,
+    // The text up to the next item, the spaces and the line break after the item
+    trail: String,
+}
+
+impl Default for ItemLayout {
+    fn default() -> Self {
+        ItemLayout {
+            lead: String::new(),
+            source: None,
+            trail: "\n".to_owned(),
+        }
+    }
+}
+
+impl Score {
+    /// Creates an empty score.
+    pub fn new() -> Score {
+        Score::default()
+    }
+
+    /// Parses the score *text*.
+    /// # Returns
+    /// An `InvalidScore` error with the line of the first statement which can't be parsed.
+    pub fn parse(text: &str) -> Result<Score> {
+        Parser::new(text).parse()
+    }
+
+    /// # Returns
+    /// The items of the score.
+    pub fn items(&self) -> &[Item] {
+        &self.items
+    }
+
+    /// # Returns
+    /// The items of the score, which can be modified.
+    pub fn items_mut(&mut self) -> &mut [Item] {
+        &mut self.items
+    }
+
+    /// Appends *statement* to the score, in its own line.
+    pub fn push(&mut self, statement: Statement) {
+        self.insert(self.items.len(), Item::Statement(statement));
+    }
+
+    /// Inserts *item* at *index*, in its own line.
+    /// # Panic
+    /// If *index* is greater than the number of items.
+    pub fn insert(&mut self, index: usize, item: Item) {
+        assert!(index <= self.items.len(), "the index is out of range");
+        if let Some(previous) = index.checked_sub(1).map(|i| &mut self.layouts[i]) {
+            if !previous.trail.contains('\n') {
+                previous.trail.push('\n');
+            }
+        }
+        self.items.insert(index, item);
+        self.layouts.insert(index, ItemLayout::default());
+    }
+
+    /// Removes the item at *index*.
+    /// # Returns
+    /// The removed item.
+    /// # Panic
+    /// If *index* is out of range.
+    pub fn remove(&mut self, index: usize) -> Item {
+        self.layouts.remove(index);
+        self.items.remove(index)
+    }
+
+    /// # Returns
+    /// An iterator over the statements of the score.
+    pub fn statements(&self) -> impl Iterator<Item = &Statement> {
+        self.items.iter().filter_map(|item| match item {
+            Item::Statement(statement) => Some(statement),
+            _ => None,
+        })
+    }
+
+    /// # Returns
+    /// An iterator over the statements of the score which allows modifying them.
+    pub fn statements_mut(&mut self) -> impl Iterator<Item = &mut Statement> {
+        self.items.iter_mut().filter_map(|item| match item {
+            Item::Statement(statement) => Some(statement),
+            _ => None,
+        })
+    }
+}
+
+impl FromStr for Score {
+    type Err = Error;
+
+    fn from_str(text: &str) -> Result<Score> {
+        Score::parse(text)
+    }
+}
+
+impl PartialEq for Score {
+    fn eq(&self, other: &Score) -> bool {
+        self.items == other.items
+    }
+}
+
+impl fmt::Display for Score {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, (item, layout)) in self.items.iter().zip(&self.layouts).enumerate() {
+            f.write_str(&layout.lead)?;
+            match &layout.source {
+                Some((parsed, text)) if parsed == item => f.write_str(text)?,
+                _ => write!(f, "{}", item)?,
+            }
+            // A comment added to an item followed by another one in the same line
+            if item.ends_line() && !layout.trail.contains('\n') && i + 1 < self.items.len() {
+                f.write_str("\n")?;
+            }
+            f.write_str(&layout.trail)?;
+        }
+        Ok(())
+    }
+}
+
+// The text between tokens
+enum Trivia {
+    // Spaces without line breaks
+    Space(String),
+    LineBreak,
+    Comment(String),
+    BlockComment(String),
+}
+
+impl fmt::Display for Trivia {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Trivia::Space(space) => write!(f, "{}", space),
+            Trivia::LineBreak => writeln!(f),
+            Trivia::Comment(comment) => write!(f, ";{}", comment),
+            Trivia::BlockComment(comment) => write!(f, "/*{}*/", comment),
+        }
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    line: usize,
+    score: Score,
+    // The item index and the last line of the statement which is being parsed
+    open: Option<(usize, usize)>,
+    // The text after the last p-field of the open statement
+    pending: Vec<Trivia>,
+    // The spaces at the beginning of the line
+    lead: String,
+    // true if an item was read in the current line
+    line_has_item: bool,
+}
+
+impl Parser {
+    fn new(text: &str) -> Parser {
+        Parser {
+            chars: text.chars().collect(),
+            pos: 0,
+            line: 1,
+            score: Score::new(),
+            open: None,
+            pending: Vec::new(),
+            lead: String::new(),
+            line_has_item: false,
+        }
+    }
+
+    fn parse(mut self) -> Result<Score> {
+        while let Some(c) = self.peek(0) {
+            match c {
+                '\n' => {
+                    self.bump();
+                    self.trivia(Trivia::LineBreak);
+                }
+                c if c.is_whitespace() => {
+                    let space = self.take_while(|c| c != '\n' && c.is_whitespace());
+                    self.trivia(Trivia::Space(space));
+                }
+                ';' => {
+                    self.bump();
+                    let comment = self.take_while(|c| c != '\n');
+                    self.trivia(Trivia::Comment(comment));
+                }
+                '/' if self.peek(1) == Some('*') => {
+                    self.pos += 2;
+                    let comment = self.take_until("*/")?;
+                    self.trivia(Trivia::BlockComment(comment));
+                }
+                '#' => {
+                    self.close();
+                    let start = self.pos;
+                    let item = self.directive()?;
+                    self.push(item, Some(start));
+                }
+                '$' if self.open_statement().is_none() => {
+                    self.close();
+                    let start = self.pos;
+                    self.bump();
+                    let text = self.macro_text()?;
+                    self.push(Item::Macro(text), Some(start));
+                }
+                c if self.starts_statement(c) => {
+                    self.close();
+                    self.bump();
+                    let opcode = Opcode::from_char(c).unwrap();
+                    self.push(Item::Statement(Statement::new(opcode, Vec::new())), None);
+                    self.open = Some((self.score.items.len() - 1, self.line));
+                }
+                _ => {
+                    let (index, _) = match self.open {
+                        Some(open) => open,
+                        None => return Err(self.error("p-field outside a statement")),
+                    };
+                    let start = self.pos;
+                    let value = self.value()?;
+                    let text = self.text_from(start);
+                    let gap = self.pending.drain(..).map(|t| t.to_string()).collect();
+                    if let Item::Statement(statement) = &mut self.score.items[index] {
+                        statement.pfields.push(value.clone());
+                        statement.layout.gaps.push(gap);
+                        statement.layout.tokens.push((value, text));
+                    }
+                    self.open = Some((index, self.line));
+                }
+            }
+        }
+        self.close();
+        // The spaces at the end of the text
+        if !self.lead.is_empty() {
+            match self.score.layouts.last_mut() {
+                Some(layout) => layout.trail.push_str(&self.lead),
+                None => {
+                    self.push(Item::Blank, None);
+                    self.line_has_item = false;
+                }
+            }
+        }
+        Ok(self.score)
+    }
+
+    fn peek(&self, offset: usize) -> Option<char> {
+        self.chars.get(self.pos + offset).copied()
+    }
+
+    fn bump(&mut self) {
+        if self.peek(0) == Some('\n') {
+            self.line += 1;
+        }
+        self.pos += 1;
+    }
+
+    fn text_from(&self, start: usize) -> String {
+        self.chars[start..self.pos].iter().collect()
+    }
+
+    // Pushes *item*, whose text starts at *start* if it has to be kept
+    fn push(&mut self, item: Item, start: Option<usize>) {
+        let source = start.map(|start| (item.clone(), self.text_from(start)));
+        self.score.items.push(item);
+        self.score.layouts.push(ItemLayout {
+            lead: mem::take(&mut self.lead),
+            source,
+            trail: String::new(),
+        });
+        self.line_has_item = true;
+    }
+
+    // The text inside a statement is kept until the statement ends or continues
+    fn trivia(&mut self, trivia: Trivia) {
+        if self.open.is_some() {
+            self.pending.push(trivia);
+            return;
+        }
+        match trivia {
+            Trivia::Space(space) if self.line_has_item => self.trail().push_str(&space),
+            Trivia::Space(space) => self.lead.push_str(&space),
+            Trivia::LineBreak => {
+                if !self.line_has_item {
+                    self.push(Item::Blank, None);
+                }
+                self.trail().push('\n');
+                self.line_has_item = false;
+            }
+            Trivia::Comment(comment) => self.push(Item::Comment(comment), None),
+            Trivia::BlockComment(comment) => self.push(Item::BlockComment(comment), None),
+        }
+    }
+
+    fn trail(&mut self) -> &mut String {
+        &mut self.score.layouts.last_mut().unwrap().trail
+    }
+
+    // Ends the open statement, the comment in the line of its last p-field belongs to it
+    fn close(&mut self) {
+        let index = match self.open.take() {
+            Some((index, _)) => index,
+            None => return,
+        };
+        let mut pending = mem::take(&mut self.pending);
+        let comment = match pending.as_slice() {
+            [Trivia::Comment(_), ..] => Some(0),
+            [Trivia::Space(_), Trivia::Comment(_), ..] => Some(1),
+            _ => None,
+        };
+        if let Some(end) = comment {
+            let mut trivia = pending.drain(..=end);
+            let gap = if end == 1 { trivia.next() } else { None };
+            if let (Some(Trivia::Comment(comment)), Item::Statement(statement)) =
+                (trivia.next(), &mut self.score.items[index])
+            {
+                statement.comment = Some(comment);
+                statement.layout.comment_gap = Some(gap.map_or(String::new(), |t| t.to_string()));
+            }
+        }
+        for trivia in pending {
+            self.trivia(trivia);
+        }
+    }
+
+    fn error(&self, message: &str) -> Error {
+        self.error_at(self.line, message)
+    }
+
+    fn error_at(&self, line: usize, message: &str) -> Error {
+        Error::InvalidScore {
+            line,
+            message: message.to_owned(),
+        }
+    }
+
+    // The statement being parsed if it is in the current line
+    fn open_statement(&self) -> Option<&Statement> {
+        match self.open {
+            Some((index, line)) if line == self.line => match &self.score.items[index] {
+                Item::Statement(statement) => Some(statement),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    fn starts_statement(&self, c: char) -> bool {
+        if Opcode::from_char(c).is_none() {
+            return false;
+        }
+        // npN
+        if c == 'n' && self.peek(1) == Some('p') {
+            return false;
+        }
+        // The section names of m, n and r
+        !matches!(self.open_statement(), Some(statement) if statement.opcode.takes_name())
+    }
+
+    fn take_while<F: Fn(char) -> bool>(&mut self, f: F) -> String {
+        let mut s = String::new();
+        while let Some(c) = self.peek(0).filter(|c| f(*c)) {
+            s.push(c);
+            self.bump();
+        }
+        s
+    }
+
+    // Takes the text up to *end*, which is skipped
+    fn take_until(&mut self, end: &str) -> Result<String> {
+        let end: Vec<char> = end.chars().collect();
+        let line = self.line;
+        let mut s = String::new();
+        loop {
+            if self.chars[self.pos..].starts_with(&end) {
+                self.pos += end.len();
+                return Ok(s);
+            }
+            match self.peek(0) {
+                Some(c) => s.push(c),
+                None => return Err(self.error_at(line, "unterminated comment or string")),
+            }
+            self.bump();
+        }
+    }
+
+    fn is_delimiter(c: char) -> bool {
+        c.is_whitespace() || c == ';' || c == '"' || c == '['
+    }
+
+    fn word(&mut self) -> String {
+        let mut s = String::new();
+        while let Some(c) = self.peek(0) {
+            if Parser::is_delimiter(c) || (c == '/' && self.peek(1) == Some('*')) {
+                break;
+            }
+            s.push(c);
+            self.bump();
+        }
+        s
+    }
+
+    fn identifier(&mut self) -> String {
+        self.take_while(|c| c.is_ascii_alphanumeric() || c == '_')
+    }
+
+    fn value(&mut self) -> Result<Value> {
+        match self.peek(0) {
+            Some('"') => {
+                self.bump();
+                Ok(Value::String(self.take_until("\"")?))
+            }
+            Some('[') => {
+                self.bump();
+                let line = self.line;
+                let mut depth = 0;
+                let mut expression = String::new();
+                loop {
+                    match self.peek(0) {
+                        Some(']') if depth == 0 => break,
+                        Some(c) => {
+                            match c {
+                                '[' => depth += 1,
+                                ']' => depth -= 1,
+                                _ => {}
+                            }
+                            expression.push(c);
+                            self.bump();
+                        }
+                        None => return Err(self.error_at(line, "unterminated expression")),
+                    }
+                }
+                self.bump();
+                Ok(Value::Expression(expression))
+            }
+            Some('$') => {
+                self.bump();
+                Ok(Value::Macro(self.macro_text()?))
+            }
+            Some(c) if c.is_alphabetic() => {
+                if matches!(self.open_statement(), Some(statement) if statement.opcode.takes_name())
+                {
+                    let name = self.identifier();
+                    if !name.is_empty() {
+                        return Ok(Value::Name(name));
+                    }
+                }
+                let word = self.word();
+                self.symbol(&word)
+                    .ok_or_else(|| self.error(&format!("unexpected `{}`", word)))
+            }
+            _ => {
+                let word = self.word();
+                self.symbol(&word)
+                    .ok_or_else(|| self.error(&format!("invalid p-field `{}`", word)))
+            }
+        }
+    }
+
+    fn symbol(&self, word: &str) -> Option<Value> {
+        let number = |s: &str| {
+            if !s.is_empty() && s.chars().all(|c| "0123456789.+-eE".contains(c)) {
+                // Overflowing numbers can't be written back
+                s.parse::<f64>().ok().filter(|number| number.is_finite())
+            } else {
+                None
+            }
+        };
+        let index = |s: &str| {
+            if !s.is_empty() && s.chars().all(|c| c.is_ascii_digit()) {
+                s.parse::<u32>().ok()
+            } else {
+                None
+            }
+        };
+        Some(match word {
+            "." => Value::Carry,
+            "!" => Value::NoCarry,
+            "+" => Value::NextStart,
+            "<" => Value::LinearRamp,
+            "(" => Value::ExpRampOpen,
+            ")" => Value::ExpRampClose,
+            "~" => Value::RandomRamp,
+            _ => {
+                if let Some(offset) = word.strip_prefix("^+") {
+                    Value::RelativeStart(number(offset)?)
+                } else if let Some(offset) = word.strip_prefix("^-") {
+                    Value::RelativeStart(-number(offset)?)
+                } else if let Some(p) = word.strip_prefix("np") {
+                    Value::NextPField(index(p)?)
+                } else if let Some(p) = word.strip_prefix("pp") {
+                    Value::PreviousPField(index(p)?)
+                } else {
+                    Value::Number(number(word)?)
+                }
+            }
+        })
+    }
+
+    // The name and arguments of a macro expansion, after the `$`
+    fn macro_text(&mut self) -> Result<String> {
+        let mut text = self.identifier();
+        if text.is_empty() {
+            return Err(self.error("missing macro name"));
+        }
+        if self.peek(0) == Some('(') {
+            self.bump();
+            text.push('(');
+            text.push_str(&self.take_until(")")?);
+            text.push(')');
+        }
+        // The optional `.` which ends the name
+        if self.peek(0) == Some('.') {
+            self.bump();
+        }
+        Ok(text)
+    }
+
+    fn directive(&mut self) -> Result<Item> {
+        self.bump();
+        let directive = self.identifier();
+        self.take_while(|c| c == ' ' || c == '\t');
+        match directive.as_str() {
+            "define" => {
+                let name = self.identifier();
+                if name.is_empty() {
+                    return Err(self.error("missing macro name"));
+                }
+                let args = if self.peek(0) == Some('(') {
+                    self.bump();
+                    let args = self.take_until(")")?;
+                    Some(
+                        args.split(&['\'', '#'][..])
+                            .map(|arg| arg.trim().to_owned())
+                            .collect(),
+                    )
+                } else {
+                    None
+                };
+                self.take_while(char::is_whitespace);
+                if self.peek(0) != Some('#') {
+                    return Err(self.error("the macro body must be between `#`"));
+                }
+                self.bump();
+                let line = self.line;
+                let mut body = String::new();
+                loop {
+                    match self.peek(0) {
+                        Some('#') => break,
+                        Some('\\') if self.peek(1) == Some('#') => {
+                            body.push_str("\\#");
+                            self.pos += 2;
+                        }
+                        Some(c) => {
+                            body.push(c);
+                            self.bump();
+                        }
+                        None => return Err(self.error_at(line, "unterminated macro body")),
+                    }
+                }
+                self.bump();
+                Ok(Item::Define(MacroDefinition { name, args, body }))
+            }
+            "undef" => {
+                let name = self.identifier();
+                if name.is_empty() {
+                    return Err(self.error("missing macro name"));
+                }
+                Ok(Item::Undef(name))
+            }
+            "include" => match self.peek(0) {
+                Some(delimiter) if !delimiter.is_alphanumeric() && delimiter != '\n' => {
+                    self.bump();
+                    let file = self.take_until(&delimiter.to_string())?;
+                    Ok(Item::Include(file))
+                }
+                _ => Err(self.error("the included file must be between delimiters")),
+            },
+            _ => Err(self.error(&format!("unknown directive `#{}`", directive))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(text: &str) -> Score {
+        let score = Score::parse(text).unwrap();
+        assert_eq!(score.to_string(), text);
+        score
+    }
+
+    fn pfields(score: &Score, index: usize) -> &[Value] {
+        &score.statements().nth(index).unwrap().pfields
+    }
+
+    #[test]
+    fn carry_and_start_shortcuts() {
+        let score = round_trip("i 1 0 1 440\ni . + . !\ni . ^+0.5 .\ni . ^-1 2");
+        assert_eq!(
+            pfields(&score, 1),
+            &[Value::Carry, Value::NextStart, Value::Carry, Value::NoCarry]
+        );
+        assert_eq!(pfields(&score, 2)[1], Value::RelativeStart(0.5));
+        assert_eq!(pfields(&score, 3)[1], Value::RelativeStart(-1.0));
+    }
+
+    #[test]
+    fn ramps() {
+        let score =
+            round_trip("i 1 0 1 100\ni 1 1 1 <\ni 1 2 1 (\ni 1 3 1 )\ni 1 4 1 ~\ni 1 5 1 200");
+        let ramps: Vec<_> = score.statements().map(|s| s.pfields[3].clone()).collect();
+        assert_eq!(
+            ramps[1..5],
+            [
+                Value::LinearRamp,
+                Value::ExpRampOpen,
+                Value::ExpRampClose,
+                Value::RandomRamp
+            ]
+        );
+    }
+
+    #[test]
+    fn next_and_previous_pfields() {
+        let score = round_trip("i 1 0 1 np4 pp5\nn np");
+        assert_eq!(
+            pfields(&score, 0)[3..],
+            [Value::NextPField(4), Value::PreviousPField(5)]
+        );
+        assert!(Score::parse("i 1 0 1 npx").is_err());
+    }
+
+    #[test]
+    fn section_names() {
+        let score = round_trip("m intro\ni 1 0 1\nn intro\nr 3 NN\ns");
+        let opcodes: Vec<_> = score.statements().map(|s| s.opcode).collect();
+        assert_eq!(
+            opcodes,
+            [
+                Opcode::Mark,
+                Opcode::Instr,
+                Opcode::RepeatMark,
+                Opcode::Repeat,
+                Opcode::Section
+            ]
+        );
+        assert_eq!(pfields(&score, 0), &[Value::Name("intro".to_owned())]);
+        assert_eq!(pfields(&score, 2), &[Value::Name("intro".to_owned())]);
+        assert_eq!(
+            pfields(&score, 3),
+            &[Value::Number(3.0), Value::Name("NN".to_owned())]
+        );
+    }
+
+    #[test]
+    fn macros() {
+        let score = round_trip(
+            "#define FREQ #440#\n#define NOTE(S'D) #i 1 $S $D\\##\ni 1 0 1 $FREQ.\n$NOTE(2'1)\n#undef FREQ\n#include \"more.sco\"",
+        );
+        assert_eq!(
+            score.items[0],
+            Item::Define(MacroDefinition {
+                name: "FREQ".to_owned(),
+                args: None,
+                body: "440".to_owned(),
+            })
+        );
+        assert_eq!(
+            score.items[1],
+            Item::Define(MacroDefinition {
+                name: "NOTE".to_owned(),
+                args: Some(vec!["S".to_owned(), "D".to_owned()]),
+                body: "i 1 $S $D\\#".to_owned(),
+            })
+        );
+        assert_eq!(pfields(&score, 0)[3], Value::Macro("FREQ".to_owned()));
+        assert_eq!(score.items[3], Item::Macro("NOTE(2'1)".to_owned()));
+        assert_eq!(score.items[4], Item::Undef("FREQ".to_owned()));
+        assert_eq!(score.items[5], Item::Include("more.sco".to_owned()));
+    }
+
+    #[test]
+    fn multi_line_statements() {
+        let score = round_trip("i 1 0\n  1 440 ; freq\n  0.5 ; dur\ni 2 0 1");
+        let statement = score.statements().next().unwrap();
+        assert_eq!(statement.pfields.len(), 5);
+        assert_eq!(statement.comment.as_deref(), Some(" dur"));
+        assert_eq!(score.statements().count(), 2);
+        assert_eq!(score.items.len(), 2);
+    }
+
+    #[test]
+    fn comments_inside_statements() {
+        let score = round_trip("i 1 /* c */ 0 1");
+        assert_eq!(pfields(&score, 0).len(), 3);
+        assert_eq!(score.items.len(), 1);
+
+        let score = round_trip("i 1 0 1\n; note\n\n 440\n; after\n");
+        assert_eq!(pfields(&score, 0).len(), 4);
+        assert_eq!(score.items[1], Item::Comment(" after".to_owned()));
+    }
+
+    #[test]
+    fn strings_and_expressions() {
+        let score = round_trip("f 1 0 0 1 \"a b.wav\" 0 0 0\ni 1 0 [1/3] [2*[3+1]]");
+        assert_eq!(pfields(&score, 0)[4], Value::String("a b.wav".to_owned()));
+        assert_eq!(
+            pfields(&score, 1)[2..],
+            [
+                Value::Expression("1/3".to_owned()),
+                Value::Expression("2*[3+1]".to_owned())
+            ]
+        );
+    }
+
+    #[test]
+    fn round_trip_text() {
+        let score = round_trip(
+            "  ; header\n\nf 1 0 8192 10 1   ; sine\n  i 1  0    1 -0.25 1e-7 440.0\ni 2 /* p2 */ 0.5 1\t; tab\n#define A  #1#\nt 0 120  \r\n$A.\n   \ne",
+        );
+        assert_eq!(pfields(&score, 1)[4], Value::Number(1e-7));
+        assert_eq!(pfields(&score, 1)[5], Value::Number(440.0));
+        assert_eq!(Score::parse("").unwrap().to_string(), "");
+        round_trip("\n \n");
+        round_trip(" ");
+    }
+
+    #[test]
+    fn modified_statements() {
+        let mut score = Score::parse("i 1  0  1  440 ; a\ni 1  1  1  440.0\ne").unwrap();
+        let statement = score.statements_mut().nth(1).unwrap();
+        statement.pfields[3] = Value::Number(220.0);
+        statement.pfields.push(Value::Carry);
+        statement.comment = Some(" b".to_owned());
+        score.push(Statement::new(Opcode::Instr, vec![Value::Number(2.0)]));
+        assert_eq!(
+            score.to_string(),
+            "i 1  0  1  440 ; a\ni 1  1  1  220 . ; b\ne\ni 2\n"
+        );
+        assert_eq!(Score::parse(&score.to_string()).unwrap(), score);
+
+        // The comment can't end the line of the next statement
+        let mut score = Score::parse("i 1 0 1 i 2 0 1").unwrap();
+        score.statements_mut().next().unwrap().comment = Some(" c".to_owned());
+        assert_eq!(score.to_string(), "i 1 0 1 ; c\n i 2 0 1");
+
+        score.remove(0);
+        score.insert(0, Item::Comment(" first".to_owned()));
+        assert_eq!(score.to_string(), "; first\ni 2 0 1");
+    }
+
+    #[test]
+    fn invalid_scores() {
+        assert!(Score::parse("i 1 0 1e400").is_err());
+        assert!(Score::parse("440").is_err());
+        assert!(Score::parse("i 1 0 1 \"open").is_err());
+        assert!(Score::parse("i 1 /* open").is_err());
+        match Score::parse("i 1 0 1\ni 1 0 1 @") {
+            Err(Error::InvalidScore { line, .. }) => assert_eq!(line, 2),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,367 @@
+use std::fmt;
+
+use crate::error::{Error, Result};
+
+/// The instrument of an `i` or `q` event, identified by number or by name.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Instrument {
+    /// A numeric instrument, a fractional part identifies a single instance, e.g `1.01`.
+    Number(f64),
+    /// A named instrument, e.g `instr Lead`.
+    Name(String),
+}
+
+impl From<f64> for Instrument {
+    fn from(number: f64) -> Self {
+        Instrument::Number(number)
+    }
+}
+
+impl From<i32> for Instrument {
+    fn from(number: i32) -> Self {
+        Instrument::Number(number as f64)
+    }
+}
+
+impl From<&str> for Instrument {
+    fn from(name: &str) -> Self {
+        Instrument::Name(name.to_owned())
+    }
+}
+
+impl From<String> for Instrument {
+    fn from(name: String) -> Self {
+        Instrument::Name(name)
+    }
+}
+
+/// A p-field of a score event.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PField {
+    /// A numeric value.
+    Number(f64),
+    /// A string, written between double quotes in the score.
+    String(String),
+}
+
+impl PField {
+    /// # Returns
+    /// The numeric value, or None if this p-field is a string.
+    pub fn as_number(&self) -> Option<f64> {
+        match self {
+            PField::Number(number) => Some(*number),
+            PField::String(_) => None,
+        }
+    }
+
+    fn validate(&self) -> Result<()> {
+        match self {
+            PField::Number(number) if !number.is_finite() => Err(Error::InvalidArgument(
+                "numeric p-fields must be finite numbers",
+            )),
+            PField::String(s) if s.contains(&['"', '\n', '\r'][..]) => Err(Error::InvalidArgument(
+                "string p-fields can not contain quotes or line breaks",
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl From<f64> for PField {
+    fn from(number: f64) -> Self {
+        PField::Number(number)
+    }
+}
+
+impl From<i32> for PField {
+    fn from(number: i32) -> Self {
+        PField::Number(number as f64)
+    }
+}
+
+impl From<&str> for PField {
+    fn from(s: &str) -> Self {
+        PField::String(s.to_owned())
+    }
+}
+
+impl From<String> for PField {
+    fn from(s: String) -> Self {
+        PField::String(s)
+    }
+}
+
+impl From<Instrument> for PField {
+    fn from(instr: Instrument) -> Self {
+        match instr {
+            Instrument::Number(number) => PField::Number(number),
+            Instrument::Name(name) => PField::String(name),
+        }
+    }
+}
+
+impl fmt::Display for PField {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PField::Number(number) => write!(f, "{}", number),
+            PField::String(s) => write!(f, "\"{}\"", s),
+        }
+    }
+}
+
+/// A real-time score event.
+///
+/// Events are sent with [`Csound::send_event`](struct.Csound.html#method.send_event) or
+/// its asynchronous version, which use `csoundScoreEvent` for events with numeric p-fields only
+/// and send the event as a line event, see
+/// [`Csound::send_input_message`](struct.Csound.html#method.send_input_message), when some
+/// p-field is a string or the instrument is named.
+/// The `i` and `f` events are created with builders which take their arguments,
+/// the other events don't take arguments.
+/// The `Display` implementation writes the event as a score statement.
+/// # Example
+/// ```
+/// use csound::ScoreEvent;
+///
+/// let note = ScoreEvent::instr("Lead", 0.0, 1.5).arg(440.0).arg("soft").build();
+/// assert_eq!(note.to_string(), "i \"Lead\" 0 1.5 440 \"soft\"");
+///
+/// let table = ScoreEvent::ftable(1, 0.0, 8192, 10).args(&[1.0, 0.5]).build();
+/// assert_eq!(table.to_string(), "f 1 0 8192 10 1 0.5");
+/// assert_eq!(table.numeric_pfields(), Some(vec![1.0, 0.0, 8192.0, 10.0, 1.0, 0.5]));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScoreEvent {
+    /// `i` statement, plays an instrument.
+    Instr {
+        instr: Instrument,
+        /// Start time in beats from now.
+        start: f64,
+        /// Duration in beats, a negative value holds the note until it is turned off.
+        duration: f64,
+        /// The p-fields from p4.
+        args: Vec<PField>,
+    },
+    /// `f` statement, creates a function table.
+    FTable {
+        /// The table number, a negative number deletes the table.
+        table: i32,
+        start: f64,
+        size: i32,
+        /// The GEN routine, a negative number skips the normalization.
+        gen: i32,
+        /// The p-fields from p5.
+        args: Vec<PField>,
+    },
+    /// `a` statement, advances the score time skipping the events in between.
+    Advance { start: f64, duration: f64 },
+    /// `q` statement, mutes or unmutes an instrument.
+    Mute {
+        instr: Instrument,
+        start: f64,
+        mute: bool,
+    },
+    /// `e` statement, ends the performance.
+    End,
+}
+
+/// Builds an `i` event, created by [`ScoreEvent::instr`](enum.ScoreEvent.html#method.instr).
+#[derive(Debug, Clone, PartialEq)]
+pub struct InstrBuilder {
+    instr: Instrument,
+    start: f64,
+    duration: f64,
+    args: Vec<PField>,
+}
+
+impl InstrBuilder {
+    /// Appends a p-field, the first one is p4.
+    pub fn arg<P: Into<PField>>(mut self, pfield: P) -> InstrBuilder {
+        self.args.push(pfield.into());
+        self
+    }
+
+    /// Appends several numeric p-fields.
+    pub fn args(mut self, pfields: &[f64]) -> InstrBuilder {
+        self.args
+            .extend(pfields.iter().map(|pfield| PField::Number(*pfield)));
+        self
+    }
+
+    /// # Returns
+    /// The `i` event.
+    pub fn build(self) -> ScoreEvent {
+        ScoreEvent::Instr {
+            instr: self.instr,
+            start: self.start,
+            duration: self.duration,
+            args: self.args,
+        }
+    }
+}
+
+impl From<InstrBuilder> for ScoreEvent {
+    fn from(builder: InstrBuilder) -> Self {
+        builder.build()
+    }
+}
+
+/// Builds an `f` event, created by [`ScoreEvent::ftable`](enum.ScoreEvent.html#method.ftable).
+#[derive(Debug, Clone, PartialEq)]
+pub struct FTableBuilder {
+    table: i32,
+    start: f64,
+    size: i32,
+    gen: i32,
+    args: Vec<PField>,
+}
+
+impl FTableBuilder {
+    /// Appends an argument of the GEN routine, the first one is p5.
+    pub fn arg<P: Into<PField>>(mut self, pfield: P) -> FTableBuilder {
+        self.args.push(pfield.into());
+        self
+    }
+
+    /// Appends several numeric arguments of the GEN routine.
+    pub fn args(mut self, pfields: &[f64]) -> FTableBuilder {
+        self.args
+            .extend(pfields.iter().map(|pfield| PField::Number(*pfield)));
+        self
+    }
+
+    /// # Returns
+    /// The `f` event.
+    pub fn build(self) -> ScoreEvent {
+        ScoreEvent::FTable {
+            table: self.table,
+            start: self.start,
+            size: self.size,
+            gen: self.gen,
+            args: self.args,
+        }
+    }
+}
+
+impl From<FTableBuilder> for ScoreEvent {
+    fn from(builder: FTableBuilder) -> Self {
+        builder.build()
+    }
+}
+
+impl ScoreEvent {
+    /// Creates an `i` event, the arguments from p4 are added with
+    /// [`InstrBuilder::arg`](struct.InstrBuilder.html#method.arg).
+    pub fn instr<I: Into<Instrument>>(instr: I, start: f64, duration: f64) -> InstrBuilder {
+        InstrBuilder {
+            instr: instr.into(),
+            start,
+            duration,
+            args: Vec::new(),
+        }
+    }
+
+    /// Creates an `f` event, the arguments of the GEN routine are added with
+    /// [`FTableBuilder::arg`](struct.FTableBuilder.html#method.arg).
+    pub fn ftable(table: i32, start: f64, size: i32, gen: i32) -> FTableBuilder {
+        FTableBuilder {
+            table,
+            start,
+            size,
+            gen,
+            args: Vec::new(),
+        }
+    }
+
+    /// Creates an `a` event.
+    pub fn advance(start: f64, duration: f64) -> ScoreEvent {
+        ScoreEvent::Advance { start, duration }
+    }
+
+    /// Creates a `q` event.
+    pub fn mute<I: Into<Instrument>>(instr: I, start: f64, mute: bool) -> ScoreEvent {
+        ScoreEvent::Mute {
+            instr: instr.into(),
+            start,
+            mute,
+        }
+    }
+
+    /// # Returns
+    /// The opcode of the score statement: 'i', 'f', 'a', 'q' or 'e'.
+    pub fn opcode(&self) -> char {
+        match self {
+            ScoreEvent::Instr { .. } => 'i',
+            ScoreEvent::FTable { .. } => 'f',
+            ScoreEvent::Advance { .. } => 'a',
+            ScoreEvent::Mute { .. } => 'q',
+            ScoreEvent::End => 'e',
+        }
+    }
+
+    /// # Returns
+    /// All the p-fields of the event, starting from p1.
+    pub fn pfields(&self) -> Vec<PField> {
+        match self {
+            ScoreEvent::Instr {
+                instr,
+                start,
+                duration,
+                args,
+            } => {
+                let mut pfields = vec![instr.clone().into(), (*start).into(), (*duration).into()];
+                pfields.extend(args.iter().cloned());
+                pfields
+            }
+            ScoreEvent::FTable {
+                table,
+                start,
+                size,
+                gen,
+                args,
+            } => {
+                let mut pfields = vec![
+                    (*table).into(),
+                    (*start).into(),
+                    (*size).into(),
+                    (*gen).into(),
+                ];
+                pfields.extend(args.iter().cloned());
+                pfields
+            }
+            // p1 is ignored by csound
+            ScoreEvent::Advance { start, duration } => {
+                vec![0.into(), (*start).into(), (*duration).into()]
+            }
+            ScoreEvent::Mute { instr, start, mute } => vec![
+                instr.clone().into(),
+                (*start).into(),
+                PField::Number(if *mute { 0.0 } else { 1.0 }),
+            ],
+            ScoreEvent::End => Vec::new(),
+        }
+    }
+
+    /// # Returns
+    /// The p-fields as numbers if none of them is a string, the event can then be sent with
+    /// [`Csound::send_score_event`](struct.Csound.html#method.send_score_event).
+    pub fn numeric_pfields(&self) -> Option<Vec<f64>> {
+        self.pfields().iter().map(PField::as_number).collect()
+    }
+
+    /// Checks that the event can be sent to csound, numbers must be finite and
+    /// strings can not contain quotes or line breaks.
+    pub fn validate(&self) -> Result<()> {
+        self.pfields().iter().try_for_each(PField::validate)
+    }
+}
+
+impl fmt::Display for ScoreEvent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.opcode())?;
+        for pfield in self.pfields() {
+            write!(f, " {}", pfield)?;
+        }
+        Ok(())
+    }
+}
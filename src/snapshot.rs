@@ -0,0 +1,154 @@
+use std::collections::BTreeMap;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// The values of a set of control and string channels.
+///
+/// Snapshots are taken with [`Csound::snapshot_channels`](struct.Csound.html#method.snapshot_channels)
+/// and written back with [`Csound::restore_channels`](struct.Csound.html#method.restore_channels),
+/// so they can be used as presets of a csd.
+/// With the `serde` feature snapshots implement `Serialize` and `Deserialize`, so they can be
+/// stored in any format supported by serde, e.g JSON or TOML.
+/// # Example
+/// ```
+/// use csound::ChannelSnapshot;
+///
+/// let mut soft = ChannelSnapshot::new();
+/// soft.set_control("cutoff", 500.0);
+/// soft.set_string("label", "soft");
+///
+/// let mut bright = ChannelSnapshot::new();
+/// bright.set_control("cutoff", 5000.0);
+/// bright.set_string("label", "bright");
+///
+/// let preset = soft.morph(&bright, 0.25);
+/// assert_eq!(preset.control("cutoff"), Some(1625.0));
+/// assert_eq!(preset.string("label"), Some("soft"));
+/// ```
+/// Saving a preset to a JSON file:
+/// ```no_run
+/// # #[cfg(feature = "serde")]
+/// # mod example {
+/// use csound::Csound;
+/// use std::fs::File;
+///
+/// # pub fn run() {
+/// let mut cs = Csound::new();
+/// cs.compile_csd("synth.csd").unwrap();
+/// cs.start().unwrap();
+/// let snapshot = cs.snapshot_channels().unwrap();
+/// serde_json::to_writer_pretty(File::create("preset.json").unwrap(), &snapshot).unwrap();
+///
+/// let preset = serde_json::from_reader(File::open("preset.json").unwrap()).unwrap();
+/// cs.restore_channels(&preset).unwrap();
+/// # }
+/// # }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ChannelSnapshot {
+    /// The control channels values by channel name.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub controls: BTreeMap<String, f64>,
+    /// The string channels content by channel name.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub strings: BTreeMap<String, String>,
+}
+
+impl ChannelSnapshot {
+    /// Creates an empty snapshot.
+    pub fn new() -> ChannelSnapshot {
+        ChannelSnapshot::default()
+    }
+
+    /// # Returns
+    /// The value of the control channel *name*, or None if it is not in the snapshot.
+    pub fn control(&self, name: &str) -> Option<f64> {
+        self.controls.get(name).copied()
+    }
+
+    /// Sets the value of the control channel *name*.
+    pub fn set_control(&mut self, name: &str, value: f64) {
+        self.controls.insert(name.to_owned(), value);
+    }
+
+    /// # Returns
+    /// The content of the string channel *name*, or None if it is not in the snapshot.
+    pub fn string(&self, name: &str) -> Option<&str> {
+        self.strings.get(name).map(String::as_str)
+    }
+
+    /// Sets the content of the string channel *name*.
+    pub fn set_string(&mut self, name: &str, content: &str) {
+        self.strings.insert(name.to_owned(), content.to_owned());
+    }
+
+    /// # Returns
+    /// The number of channels in the snapshot.
+    pub fn len(&self) -> usize {
+        self.controls.len() + self.strings.len()
+    }
+
+    /// # Returns
+    /// true if the snapshot doesn't have any channel.
+    pub fn is_empty(&self) -> bool {
+        self.controls.is_empty() && self.strings.is_empty()
+    }
+
+    /// Interpolates between this snapshot and *other*.
+    /// # Arguments
+    /// * `other` The target snapshot.
+    /// * `amount` The position between both snapshots, 0 gives this snapshot and 1 gives *other*.
+    ///   It is clamped to that range.
+    ///
+    /// # Returns
+    /// A snapshot where the control channels which are in both snapshots are interpolated linearly
+    /// and the string channels are taken from the nearest snapshot.
+    /// Channels which are only in one of the snapshots keep their value.
+    pub fn morph(&self, other: &ChannelSnapshot, amount: f64) -> ChannelSnapshot {
+        let amount = amount.clamp(0.0, 1.0);
+        let mut controls = self.controls.clone();
+        for (name, target) in &other.controls {
+            let value = match self.controls.get(name) {
+                Some(value) => value + (target - value) * amount,
+                None => *target,
+            };
+            controls.insert(name.clone(), value);
+        }
+        let (near, far) = if amount < 0.5 {
+            (self, other)
+        } else {
+            (other, self)
+        };
+        let mut strings = far.strings.clone();
+        strings.extend(near.strings.clone());
+        ChannelSnapshot { controls, strings }
+    }
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_round_trip() {
+        let mut snapshot = ChannelSnapshot::new();
+        snapshot.set_control("cutoff", 1234.5);
+        snapshot.set_control("gain", -0.25);
+        snapshot.set_string("label", "a \"quoted\" label");
+
+        let json = serde_json::to_string(&snapshot).unwrap();
+        assert_eq!(
+            json,
+            r#"{"controls":{"cutoff":1234.5,"gain":-0.25},"strings":{"label":"a \"quoted\" label"}}"#
+        );
+        let restored: ChannelSnapshot = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored, snapshot);
+
+        // Both maps are optional
+        let controls: ChannelSnapshot = serde_json::from_str(r#"{"controls":{"gain":1}}"#).unwrap();
+        assert_eq!(controls.control("gain"), Some(1.0));
+        assert!(controls.strings.is_empty());
+    }
+}
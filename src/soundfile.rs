@@ -0,0 +1,1155 @@
+use std::convert::TryFrom;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::mem;
+use std::path::Path;
+
+use crate::csound::Csound;
+
+/// Sound file containers supported by [`SoundFileWriter`](struct.SoundFileWriter.html)
+/// and [`SoundFileReader`](struct.SoundFileReader.html).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SoundFileType {
+    /// RIFF WAVE file, little endian.
+    Wav,
+    /// AIFF file, big endian. Float samples are written as AIFF-C.
+    Aiff,
+}
+
+/// Encoding of the samples in a sound file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SampleFormat {
+    /// 16 bits signed integer.
+    Int16,
+    /// 24 bits signed integer.
+    Int24,
+    /// 32 bits signed integer.
+    Int32,
+    /// 32 bits IEEE float.
+    Float32,
+    /// 64 bits IEEE float.
+    Float64,
+}
+
+impl SampleFormat {
+    /// # Returns
+    /// The size in bytes of one sample.
+    pub fn bytes(self) -> usize {
+        match self {
+            SampleFormat::Int16 => 2,
+            SampleFormat::Int24 => 3,
+            SampleFormat::Int32 | SampleFormat::Float32 => 4,
+            SampleFormat::Float64 => 8,
+        }
+    }
+
+    /// # Returns
+    /// *true* if the samples are floating point values.
+    pub fn is_float(self) -> bool {
+        matches!(self, SampleFormat::Float32 | SampleFormat::Float64)
+    }
+
+    fn bits(self) -> u16 {
+        self.bytes() as u16 * 8
+    }
+
+    fn encode(self, sample: f64, big_endian: bool, out: &mut Vec<u8>) {
+        match self {
+            SampleFormat::Int16 => {
+                let value = (clamp(sample) * 32_767.0).round() as i16;
+                if big_endian {
+                    out.extend_from_slice(&value.to_be_bytes());
+                } else {
+                    out.extend_from_slice(&value.to_le_bytes());
+                }
+            }
+            SampleFormat::Int24 => {
+                let value = (clamp(sample) * 8_388_607.0).round() as i32;
+                if big_endian {
+                    out.extend_from_slice(&value.to_be_bytes()[1..]);
+                } else {
+                    out.extend_from_slice(&value.to_le_bytes()[..3]);
+                }
+            }
+            SampleFormat::Int32 => {
+                let value = (clamp(sample) * 2_147_483_647.0).round() as i32;
+                if big_endian {
+                    out.extend_from_slice(&value.to_be_bytes());
+                } else {
+                    out.extend_from_slice(&value.to_le_bytes());
+                }
+            }
+            SampleFormat::Float32 => {
+                let value = sample as f32;
+                if big_endian {
+                    out.extend_from_slice(&value.to_be_bytes());
+                } else {
+                    out.extend_from_slice(&value.to_le_bytes());
+                }
+            }
+            SampleFormat::Float64 => {
+                if big_endian {
+                    out.extend_from_slice(&sample.to_be_bytes());
+                } else {
+                    out.extend_from_slice(&sample.to_le_bytes());
+                }
+            }
+        }
+    }
+
+    fn decode(self, bytes: &[u8], big_endian: bool) -> f64 {
+        let mut raw = [0u8; 8];
+        let len = self.bytes();
+        raw[..len].copy_from_slice(&bytes[..len]);
+        if big_endian {
+            raw[..len].reverse();
+        }
+        // raw holds the sample bytes in little endian order
+        match self {
+            SampleFormat::Int16 => f64::from(i16::from_le_bytes([raw[0], raw[1]])) / 32_768.0,
+            SampleFormat::Int24 => {
+                // sign extension of the 24 bits value
+                let value = i32::from_le_bytes([0, raw[0], raw[1], raw[2]]) >> 8;
+                f64::from(value) / 8_388_608.0
+            }
+            SampleFormat::Int32 => {
+                f64::from(i32::from_le_bytes([raw[0], raw[1], raw[2], raw[3]])) / 2_147_483_648.0
+            }
+            SampleFormat::Float32 => {
+                f64::from(f32::from_le_bytes([raw[0], raw[1], raw[2], raw[3]]))
+            }
+            SampleFormat::Float64 => f64::from_le_bytes(raw),
+        }
+    }
+}
+
+// The largest prefix of the fmt and COMM chunks which is read,
+// the longest fields used are the WAVE_FORMAT_EXTENSIBLE sub format and the AIFF-C compression type.
+const MAX_HEADER_CHUNK: u64 = 64;
+
+fn clamp(sample: f64) -> f64 {
+    sample.clamp(-1.0, 1.0)
+}
+
+fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+fn not_started() -> io::Error {
+    io::Error::other(
+        "The csound's buffers are not initialized, call the 'compile()' and 'start()' methods.",
+    )
+}
+
+// Sample rates are stored as 80 bits IEEE extended floats in the AIFF files.
+fn f64_to_extended(value: f64) -> [u8; 10] {
+    let mut out = [0u8; 10];
+    if value <= 0.0 || !value.is_normal() {
+        return out;
+    }
+    let bits = value.to_bits();
+    let exponent = ((bits >> 52) & 0x7ff) as i32 - 1023 + 16383;
+    let mantissa = ((bits & ((1 << 52) - 1)) | (1 << 52)) << 11;
+    out[..2].copy_from_slice(&(exponent as u16).to_be_bytes());
+    out[2..].copy_from_slice(&mantissa.to_be_bytes());
+    out
+}
+
+fn extended_to_f64(bytes: &[u8]) -> f64 {
+    let exponent = i32::from(u16::from_be_bytes([bytes[0], bytes[1]]) & 0x7fff);
+    let mut mantissa = [0u8; 8];
+    mantissa.copy_from_slice(&bytes[2..10]);
+    let mantissa = u64::from_be_bytes(mantissa);
+    if exponent == 0 && mantissa == 0 {
+        return 0.0;
+    }
+    mantissa as f64 * 2f64.powi(exponent - 16383 - 63)
+}
+
+/// Writes interleaved audio samples into a WAV or AIFF file.
+///
+/// It is intended to be used together with
+/// [`Csound::set_host_implemented_audioIO`](struct.Csound.html#method.set_host_implemented_audioIO),
+/// to store the content of the csound's spout or output buffer after each performance step
+/// without using any csound's audio module.
+/// The file header is updated when [`SoundFileWriter::finish`](struct.SoundFileWriter.html#method.finish)
+/// is called or when the writer is dropped.
+/// # Example
+/// ```no_run
+/// use csound::{Csound, SampleFormat, SoundFileSource, SoundFileType, SoundFileWriter};
+///
+/// let mut cs = Csound::new();
+/// cs.set_host_implemented_audioIO(1, 0);
+/// cs.compile_csd("some.csd").unwrap();
+/// cs.start().unwrap();
+///
+/// let mut input = SoundFileSource::open("input.aiff", cs.input_channels(), cs.get_sample_rate()).unwrap();
+/// let mut output = SoundFileWriter::create(
+///     "output.wav",
+///     SoundFileType::Wav,
+///     SampleFormat::Int24,
+///     cs.output_channels(),
+///     cs.get_sample_rate(),
+/// ).unwrap();
+///
+/// loop {
+///     input.write_spin(&cs).unwrap();
+///     if cs.perform_ksmps() {
+///         break;
+///     }
+///     output.write_spout(&cs).unwrap();
+/// }
+/// output.finish().unwrap();
+/// ```
+#[derive(Debug)]
+pub struct SoundFileWriter<W: Write + Seek> {
+    inner: W,
+    file_type: SoundFileType,
+    format: SampleFormat,
+    channels: u32,
+    frames: u64,
+    // Offsets of the header fields which depend on the data length
+    form_size_pos: u64,
+    frames_pos: Option<u64>,
+    data_size_pos: u64,
+    data_start: u64,
+    buffer: Vec<u8>,
+    finished: bool,
+}
+
+impl SoundFileWriter<BufWriter<File>> {
+    /// Creates a new sound file at *path*.
+    /// # Arguments
+    /// * `file_type` The file container.
+    /// * `format` The samples encoding.
+    /// * `channels` The number of interleaved channels.
+    /// * `sample_rate` The number of frames per second.
+    pub fn create<P>(
+        path: P,
+        file_type: SoundFileType,
+        format: SampleFormat,
+        channels: u32,
+        sample_rate: f64,
+    ) -> io::Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        let file = BufWriter::new(File::create(path)?);
+        SoundFileWriter::new(file, file_type, format, channels, sample_rate)
+    }
+}
+
+impl<W: Write + Seek> SoundFileWriter<W> {
+    /// Creates a sound file writer over *inner*, the header is written immediately.
+    /// See [`SoundFileWriter::create`](struct.SoundFileWriter.html#method.create).
+    pub fn new(
+        mut inner: W,
+        file_type: SoundFileType,
+        format: SampleFormat,
+        channels: u32,
+        sample_rate: f64,
+    ) -> io::Result<Self> {
+        if channels == 0 || channels > u32::from(u16::MAX) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid number of channels",
+            ));
+        }
+        let start = inner.stream_position()?;
+        let mut header = Vec::with_capacity(80);
+        let frames_pos;
+        let form_size_pos = start + 4;
+        let data_size_pos;
+
+        match file_type {
+            SoundFileType::Wav => {
+                let block_align = channels as u16 * format.bytes() as u16;
+                header.extend_from_slice(b"RIFF\0\0\0\0WAVE");
+                header.extend_from_slice(b"fmt ");
+                if format.is_float() {
+                    header.extend_from_slice(&18u32.to_le_bytes());
+                    header.extend_from_slice(&3u16.to_le_bytes());
+                } else {
+                    header.extend_from_slice(&16u32.to_le_bytes());
+                    header.extend_from_slice(&1u16.to_le_bytes());
+                }
+                header.extend_from_slice(&(channels as u16).to_le_bytes());
+                header.extend_from_slice(&(sample_rate.round() as u32).to_le_bytes());
+                header.extend_from_slice(
+                    &(sample_rate.round() as u32 * u32::from(block_align)).to_le_bytes(),
+                );
+                header.extend_from_slice(&block_align.to_le_bytes());
+                header.extend_from_slice(&format.bits().to_le_bytes());
+                if format.is_float() {
+                    // Non PCM files need the extension size and a fact chunk
+                    header.extend_from_slice(&0u16.to_le_bytes());
+                    header.extend_from_slice(b"fact");
+                    header.extend_from_slice(&4u32.to_le_bytes());
+                    frames_pos = Some(start + header.len() as u64);
+                    header.extend_from_slice(&0u32.to_le_bytes());
+                } else {
+                    frames_pos = None;
+                }
+                header.extend_from_slice(b"data");
+                data_size_pos = start + header.len() as u64;
+                header.extend_from_slice(&0u32.to_le_bytes());
+            }
+            SoundFileType::Aiff => {
+                if format.is_float() {
+                    let (compression, name): (&[u8], &[u8]) = match format {
+                        SampleFormat::Float32 => (b"fl32", b"\x0c32-bit float\0"),
+                        _ => (b"fl64", b"\x0c64-bit float\0"),
+                    };
+                    header.extend_from_slice(b"FORM\0\0\0\0AIFC");
+                    header.extend_from_slice(b"FVER");
+                    header.extend_from_slice(&4u32.to_be_bytes());
+                    header.extend_from_slice(&0xA280_5140u32.to_be_bytes());
+                    header.extend_from_slice(b"COMM");
+                    header.extend_from_slice(&(22 + name.len() as u32).to_be_bytes());
+                    header.extend_from_slice(&(channels as u16).to_be_bytes());
+                    frames_pos = Some(start + header.len() as u64);
+                    header.extend_from_slice(&0u32.to_be_bytes());
+                    header.extend_from_slice(&format.bits().to_be_bytes());
+                    header.extend_from_slice(&f64_to_extended(sample_rate));
+                    header.extend_from_slice(compression);
+                    header.extend_from_slice(name);
+                } else {
+                    header.extend_from_slice(b"FORM\0\0\0\0AIFF");
+                    header.extend_from_slice(b"COMM");
+                    header.extend_from_slice(&18u32.to_be_bytes());
+                    header.extend_from_slice(&(channels as u16).to_be_bytes());
+                    frames_pos = Some(start + header.len() as u64);
+                    header.extend_from_slice(&0u32.to_be_bytes());
+                    header.extend_from_slice(&format.bits().to_be_bytes());
+                    header.extend_from_slice(&f64_to_extended(sample_rate));
+                }
+                header.extend_from_slice(b"SSND");
+                data_size_pos = start + header.len() as u64;
+                // chunk size, offset and block size
+                header.extend_from_slice(&[0u8; 12]);
+            }
+        }
+        inner.write_all(&header)?;
+
+        Ok(SoundFileWriter {
+            inner,
+            file_type,
+            format,
+            channels,
+            frames: 0,
+            form_size_pos,
+            frames_pos,
+            data_size_pos,
+            data_start: start + header.len() as u64,
+            buffer: Vec::new(),
+            finished: false,
+        })
+    }
+
+    /// # Returns
+    /// The number of interleaved channels.
+    pub fn channels(&self) -> u32 {
+        self.channels
+    }
+
+    /// # Returns
+    /// The number of frames written so far.
+    pub fn frames(&self) -> u64 {
+        self.frames
+    }
+
+    /// Writes interleaved samples into the file. Samples are expected to be in the range [-1, 1],
+    /// integer formats are clipped outside that range.
+    /// Only complete frames are written, the remaining samples are ignored.
+    pub fn write_samples(&mut self, samples: &[f64]) -> io::Result<()> {
+        self.write_scaled(samples, 1.0)
+    }
+
+    /// Writes the content of the csound's spout buffer, scaled by the 0dBFS level.
+    /// This should be called after each call to [`Csound::perform_ksmps`](struct.Csound.html#method.perform_ksmps).
+    pub fn write_spout(&mut self, csound: &Csound) -> io::Result<()> {
+        let spout = csound.get_spout().ok_or_else(not_started)?;
+        self.write_scaled(spout.as_slice(), 1.0 / csound.get_0dBFS())
+    }
+
+    /// Writes the content of the csound's output buffer, scaled by the 0dBFS level.
+    /// This should be called after each call to [`Csound::perform_buffer`](struct.Csound.html#method.perform_buffer).
+    pub fn write_output_buffer(&mut self, csound: &Csound) -> io::Result<()> {
+        let output = csound.get_output_buffer().ok_or_else(not_started)?;
+        self.write_scaled(output.as_slice(), 1.0 / csound.get_0dBFS())
+    }
+
+    fn write_scaled(&mut self, samples: &[f64], scale: f64) -> io::Result<()> {
+        if self.finished {
+            return Err(io::Error::other("the sound file was already finished"));
+        }
+        let channels = self.channels as usize;
+        let frames = samples.len() / channels;
+        let big_endian = self.file_type == SoundFileType::Aiff;
+        self.buffer.clear();
+        for sample in &samples[..frames * channels] {
+            self.format
+                .encode(sample * scale, big_endian, &mut self.buffer);
+        }
+        self.inner.write_all(&self.buffer)?;
+        self.frames += frames as u64;
+        Ok(())
+    }
+
+    /// Updates the file header with the final data length and flushes the output.
+    /// No more samples can be written afterwards.
+    pub fn finish(&mut self) -> io::Result<()> {
+        if self.finished {
+            return Ok(());
+        }
+        self.finished = true;
+
+        let data_len = self.frames * self.channels as u64 * self.format.bytes() as u64;
+        // Chunks have an even size
+        if data_len % 2 == 1 {
+            self.inner.write_all(&[0])?;
+        }
+        let end = self.inner.stream_position()?;
+        let form_size = (end - self.form_size_pos - 4) as u32;
+        let frames = self.frames as u32;
+
+        match self.file_type {
+            SoundFileType::Wav => {
+                self.inner.seek(SeekFrom::Start(self.form_size_pos))?;
+                self.inner.write_all(&form_size.to_le_bytes())?;
+                if let Some(pos) = self.frames_pos {
+                    self.inner.seek(SeekFrom::Start(pos))?;
+                    self.inner.write_all(&frames.to_le_bytes())?;
+                }
+                self.inner.seek(SeekFrom::Start(self.data_size_pos))?;
+                self.inner.write_all(&(data_len as u32).to_le_bytes())?;
+            }
+            SoundFileType::Aiff => {
+                self.inner.seek(SeekFrom::Start(self.form_size_pos))?;
+                self.inner.write_all(&form_size.to_be_bytes())?;
+                if let Some(pos) = self.frames_pos {
+                    self.inner.seek(SeekFrom::Start(pos))?;
+                    self.inner.write_all(&frames.to_be_bytes())?;
+                }
+                // the SSND chunk size includes the offset and block size fields
+                self.inner.seek(SeekFrom::Start(self.data_size_pos))?;
+                self.inner.write_all(&(data_len as u32 + 8).to_be_bytes())?;
+            }
+        }
+        debug_assert!(self.data_start <= end);
+        self.inner.seek(SeekFrom::Start(end))?;
+        self.inner.flush()
+    }
+}
+
+impl<W: Write + Seek> Drop for SoundFileWriter<W> {
+    fn drop(&mut self) {
+        let _ = self.finish();
+    }
+}
+
+/// Reads interleaved audio samples from a WAV or AIFF(-C) file.
+///
+/// Integer and float samples are converted to f64 values in the range [-1, 1].
+#[derive(Debug)]
+pub struct SoundFileReader<R: Read + Seek> {
+    inner: R,
+    file_type: SoundFileType,
+    format: SampleFormat,
+    big_endian: bool,
+    channels: u32,
+    sample_rate: f64,
+    frames: u64,
+    position: u64,
+    buffer: Vec<u8>,
+}
+
+impl SoundFileReader<BufReader<File>> {
+    /// Opens the sound file at *path*.
+    pub fn open<P>(path: P) -> io::Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        SoundFileReader::new(BufReader::new(File::open(path)?))
+    }
+}
+
+impl<R: Read + Seek> SoundFileReader<R> {
+    /// Creates a reader over *inner* and parses the file header.
+    /// # Returns
+    /// An error of kind *InvalidData* if the file is not a supported WAV or AIFF file.
+    pub fn new(mut inner: R) -> io::Result<Self> {
+        let mut header = [0u8; 12];
+        inner.read_exact(&mut header)?;
+        match (&header[..4], &header[8..]) {
+            (b"RIFF", b"WAVE") => Self::parse_wav(inner),
+            (b"FORM", b"AIFF") => Self::parse_aiff(inner, false),
+            (b"FORM", b"AIFC") => Self::parse_aiff(inner, true),
+            _ => Err(invalid_data("not a WAV or AIFF file")),
+        }
+    }
+
+    fn read_chunk_header(inner: &mut R) -> io::Result<Option<([u8; 4], [u8; 4])>> {
+        let mut id = [0u8; 4];
+        match inner.read_exact(&mut id) {
+            Ok(()) => {}
+            Err(ref e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(e),
+        }
+        let mut size = [0u8; 4];
+        inner.read_exact(&mut size)?;
+        Ok(Some((id, size)))
+    }
+
+    // Reads the beginning of a header chunk, which has the fields used by the parser,
+    // and skips the rest, so the allocation doesn't depend on the size written in the file.
+    fn read_chunk(inner: &mut R, size: u64) -> io::Result<Vec<u8>> {
+        let len = size.min(MAX_HEADER_CHUNK);
+        let mut chunk = Vec::with_capacity(len as usize);
+        inner.by_ref().take(len).read_to_end(&mut chunk)?;
+        if (chunk.len() as u64) < len {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        inner.seek(SeekFrom::Current((size - len + size % 2) as i64))?;
+        Ok(chunk)
+    }
+
+    fn parse_wav(mut inner: R) -> io::Result<Self> {
+        let mut fmt = None;
+        let mut data = None;
+
+        while fmt.is_none() || data.is_none() {
+            let (id, size) = match Self::read_chunk_header(&mut inner)? {
+                Some(chunk) => chunk,
+                None => break,
+            };
+            let size = u64::from(u32::from_le_bytes(size));
+            match &id {
+                b"fmt " => fmt = Some(Self::read_chunk(&mut inner, size)?),
+                b"data" => {
+                    let start = inner.stream_position()?;
+                    data = Some((start, size));
+                    inner.seek(SeekFrom::Current((size + size % 2) as i64))?;
+                }
+                _ => {
+                    inner.seek(SeekFrom::Current((size + size % 2) as i64))?;
+                }
+            }
+        }
+
+        let fmt = fmt.ok_or_else(|| invalid_data("the WAV file has not a fmt chunk"))?;
+        let (data_start, data_len) =
+            data.ok_or_else(|| invalid_data("the WAV file has not a data chunk"))?;
+        if fmt.len() < 16 {
+            return Err(invalid_data("invalid WAV fmt chunk"));
+        }
+
+        let mut tag = u16::from_le_bytes([fmt[0], fmt[1]]);
+        let channels = u32::from(u16::from_le_bytes([fmt[2], fmt[3]]));
+        let sample_rate = f64::from(u32::from_le_bytes([fmt[4], fmt[5], fmt[6], fmt[7]]));
+        let bits = u16::from_le_bytes([fmt[14], fmt[15]]);
+        // WAVE_FORMAT_EXTENSIBLE, the format tag is the first field of the sub format GUID
+        if tag == 0xFFFE && fmt.len() >= 26 {
+            tag = u16::from_le_bytes([fmt[24], fmt[25]]);
+        }
+        let format = match (tag, bits) {
+            (1, 16) => SampleFormat::Int16,
+            (1, 24) => SampleFormat::Int24,
+            (1, 32) => SampleFormat::Int32,
+            (3, 32) => SampleFormat::Float32,
+            (3, 64) => SampleFormat::Float64,
+            _ => return Err(invalid_data("unsupported WAV sample format")),
+        };
+
+        Self::with_data(
+            inner,
+            SoundFileType::Wav,
+            format,
+            false,
+            channels,
+            sample_rate,
+            data_start,
+            data_len,
+        )
+    }
+
+    fn parse_aiff(mut inner: R, aifc: bool) -> io::Result<Self> {
+        let mut comm = None;
+        let mut data = None;
+
+        while comm.is_none() || data.is_none() {
+            let (id, size) = match Self::read_chunk_header(&mut inner)? {
+                Some(chunk) => chunk,
+                None => break,
+            };
+            let size = u64::from(u32::from_be_bytes(size));
+            match &id {
+                b"COMM" => comm = Some(Self::read_chunk(&mut inner, size)?),
+                b"SSND" => {
+                    if size < 8 {
+                        return Err(invalid_data("invalid AIFF SSND chunk"));
+                    }
+                    let mut fields = [0u8; 8];
+                    inner.read_exact(&mut fields)?;
+                    let offset = u64::from(u32::from_be_bytes([
+                        fields[0], fields[1], fields[2], fields[3],
+                    ]));
+                    let start = inner.stream_position()? + offset;
+                    data = Some((start, size.saturating_sub(8 + offset)));
+                    inner.seek(SeekFrom::Current((size - 8 + size % 2) as i64))?;
+                }
+                _ => {
+                    inner.seek(SeekFrom::Current((size + size % 2) as i64))?;
+                }
+            }
+        }
+
+        let comm = comm.ok_or_else(|| invalid_data("the AIFF file has not a COMM chunk"))?;
+        let (data_start, data_len) =
+            data.ok_or_else(|| invalid_data("the AIFF file has not a SSND chunk"))?;
+        if comm.len() < 18 || (aifc && comm.len() < 22) {
+            return Err(invalid_data("invalid AIFF COMM chunk"));
+        }
+
+        let channels = u32::from(u16::from_be_bytes([comm[0], comm[1]]));
+        let bits = u16::from_be_bytes([comm[6], comm[7]]);
+        let sample_rate = extended_to_f64(&comm[8..18]);
+        let compression: &[u8] = if aifc { &comm[18..22] } else { b"NONE" };
+        let (format, big_endian) = match (compression, bits) {
+            (b"NONE", 16) => (SampleFormat::Int16, true),
+            (b"NONE", 24) => (SampleFormat::Int24, true),
+            (b"NONE", 32) => (SampleFormat::Int32, true),
+            (b"sowt", 16) => (SampleFormat::Int16, false),
+            (b"sowt", 24) => (SampleFormat::Int24, false),
+            (b"sowt", 32) => (SampleFormat::Int32, false),
+            (b"fl32", _) | (b"FL32", _) => (SampleFormat::Float32, true),
+            (b"fl64", _) | (b"FL64", _) => (SampleFormat::Float64, true),
+            _ => return Err(invalid_data("unsupported AIFF sample format")),
+        };
+
+        Self::with_data(
+            inner,
+            SoundFileType::Aiff,
+            format,
+            big_endian,
+            channels,
+            sample_rate,
+            data_start,
+            data_len,
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn with_data(
+        mut inner: R,
+        file_type: SoundFileType,
+        format: SampleFormat,
+        big_endian: bool,
+        channels: u32,
+        sample_rate: f64,
+        data_start: u64,
+        data_len: u64,
+    ) -> io::Result<Self> {
+        if channels == 0 {
+            return Err(invalid_data("the sound file has not any channel"));
+        }
+        inner.seek(SeekFrom::Start(data_start))?;
+        Ok(SoundFileReader {
+            inner,
+            file_type,
+            format,
+            big_endian,
+            channels,
+            sample_rate,
+            frames: data_len / (u64::from(channels) * format.bytes() as u64),
+            position: 0,
+            buffer: Vec::new(),
+        })
+    }
+
+    /// # Returns
+    /// The file container.
+    pub fn file_type(&self) -> SoundFileType {
+        self.file_type
+    }
+
+    /// # Returns
+    /// The samples encoding.
+    pub fn sample_format(&self) -> SampleFormat {
+        self.format
+    }
+
+    /// # Returns
+    /// The number of interleaved channels.
+    pub fn channels(&self) -> u32 {
+        self.channels
+    }
+
+    /// # Returns
+    /// The number of frames per second.
+    pub fn sample_rate(&self) -> f64 {
+        self.sample_rate
+    }
+
+    /// # Returns
+    /// The total number of frames in the file, as written in its header
+    /// until the end of the data is reached.
+    pub fn frames(&self) -> u64 {
+        self.frames
+    }
+
+    /// Reads interleaved samples into *out*.
+    /// # Returns
+    /// The number of frames read, zero at the end of the file.
+    pub fn read_frames(&mut self, out: &mut [f64]) -> io::Result<usize> {
+        let channels = self.channels as usize;
+        let remaining = usize::try_from(self.frames - self.position).unwrap_or(usize::MAX);
+        let requested = (out.len() / channels).min(remaining);
+        let sample_size = self.format.bytes();
+        let frame_size = channels * sample_size;
+
+        // The data can be shorter than the header says, e.g in a truncated file
+        // or a WAV file written by a stream, so the frames available are read.
+        self.buffer.resize(requested * frame_size, 0);
+        let mut len = 0;
+        while len < self.buffer.len() {
+            match self.inner.read(&mut self.buffer[len..]) {
+                Ok(0) => break,
+                Ok(read) => len += read,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+        let frames = len / frame_size;
+        if frames < requested {
+            self.frames = self.position + frames as u64;
+        }
+        for (sample, bytes) in out
+            .iter_mut()
+            .zip(self.buffer[..frames * frame_size].chunks_exact(sample_size))
+        {
+            *sample = self.format.decode(bytes, self.big_endian);
+        }
+        self.position += frames as u64;
+        Ok(frames)
+    }
+}
+
+/// Streams a sound file into csound, converting its channels and sample rate.
+///
+/// If the file has less channels than requested, the file channels are repeated,
+/// (e.g. a mono file is copied into every channel), if it has more channels, they are mixed down.
+/// The sample rate is converted using linear interpolation.
+/// Once the end of file is reached, silence is written.
+/// See [`SoundFileWriter`](struct.SoundFileWriter.html) for an example.
+#[derive(Debug)]
+pub struct SoundFileSource<R: Read + Seek> {
+    reader: SoundFileReader<R>,
+    channels: u32,
+    step: f64,
+    position: f64,
+    started: bool,
+    // Interleaved frames read from the file
+    input: Vec<f64>,
+    input_frames: usize,
+    input_pos: usize,
+    // The two frames (already converted to the output channels) around the current position
+    prev: Vec<f64>,
+    next: Vec<f64>,
+    prev_valid: bool,
+    next_valid: bool,
+}
+
+impl SoundFileSource<BufReader<File>> {
+    /// Opens the sound file at *path* to be played with *channels* channels
+    /// at *sample_rate* frames per second.
+    pub fn open<P>(path: P, channels: u32, sample_rate: f64) -> io::Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        Ok(SoundFileSource::new(
+            SoundFileReader::open(path)?,
+            channels,
+            sample_rate,
+        ))
+    }
+}
+
+impl<R: Read + Seek> SoundFileSource<R> {
+    /// Creates a new source which reads from *reader* and produces *channels* interleaved channels
+    /// at *sample_rate* frames per second.
+    pub fn new(reader: SoundFileReader<R>, channels: u32, sample_rate: f64) -> Self {
+        let channels = channels.max(1);
+        let step = if sample_rate > 0.0 {
+            reader.sample_rate() / sample_rate
+        } else {
+            1.0
+        };
+        SoundFileSource {
+            input: vec![0f64; 1024 * reader.channels() as usize],
+            reader,
+            channels,
+            step,
+            position: 0.0,
+            started: false,
+            input_frames: 0,
+            input_pos: 0,
+            prev: vec![0f64; channels as usize],
+            next: vec![0f64; channels as usize],
+            prev_valid: false,
+            next_valid: false,
+        }
+    }
+
+    /// # Returns
+    /// *true* once every frame of the file has been played.
+    pub fn is_finished(&self) -> bool {
+        self.started && !self.prev_valid
+    }
+
+    /// Fills *out* with interleaved samples in the range [-1, 1].
+    /// # Returns
+    /// The number of frames taken from the file, frames after the end of the file are filled with zeros.
+    pub fn fill(&mut self, out: &mut [f64]) -> io::Result<usize> {
+        self.fill_scaled(out, 1.0)
+    }
+
+    /// Fills the csound's spin buffer, scaling the samples by the 0dBFS level.
+    /// This should be called before each call to [`Csound::perform_ksmps`](struct.Csound.html#method.perform_ksmps).
+    pub fn write_spin(&mut self, csound: &Csound) -> io::Result<usize> {
+        let mut spin = csound.get_spin().ok_or_else(not_started)?;
+        self.fill_scaled(spin.as_mut_slice(), csound.get_0dBFS())
+    }
+
+    /// Fills the csound's input buffer, scaling the samples by the 0dBFS level.
+    /// This should be called before each call to [`Csound::perform_buffer`](struct.Csound.html#method.perform_buffer).
+    pub fn write_input_buffer(&mut self, csound: &Csound) -> io::Result<usize> {
+        let mut input = csound.get_input_buffer().ok_or_else(not_started)?;
+        self.fill_scaled(input.as_mut_slice(), csound.get_0dBFS())
+    }
+
+    fn fill_scaled(&mut self, out: &mut [f64], scale: f64) -> io::Result<usize> {
+        if !self.started {
+            self.started = true;
+            self.advance()?;
+            self.advance()?;
+        }
+
+        let mut frames = 0;
+        for frame in out.chunks_mut(self.channels as usize) {
+            while self.position >= 1.0 {
+                self.advance()?;
+                self.position -= 1.0;
+            }
+            if !self.prev_valid {
+                for sample in frame.iter_mut() {
+                    *sample = 0.0;
+                }
+                continue;
+            }
+            for (i, sample) in frame.iter_mut().enumerate() {
+                let value = if self.next_valid {
+                    self.prev[i] + (self.next[i] - self.prev[i]) * self.position
+                } else {
+                    self.prev[i]
+                };
+                *sample = value * scale;
+            }
+            self.position += self.step;
+            frames += 1;
+        }
+        Ok(frames)
+    }
+
+    // Moves the next frame to the previous one and reads a new frame from the file.
+    fn advance(&mut self) -> io::Result<()> {
+        mem::swap(&mut self.prev, &mut self.next);
+        self.prev_valid = self.next_valid;
+        self.next_valid = self.read_frame()?;
+        Ok(())
+    }
+
+    fn read_frame(&mut self) -> io::Result<bool> {
+        if self.input_pos == self.input_frames {
+            self.input_frames = self.reader.read_frames(&mut self.input)?;
+            self.input_pos = 0;
+            if self.input_frames == 0 {
+                return Ok(false);
+            }
+        }
+
+        let in_channels = self.reader.channels() as usize;
+        let out_channels = self.channels as usize;
+        let frame = &self.input[self.input_pos * in_channels..(self.input_pos + 1) * in_channels];
+        if out_channels >= in_channels {
+            for (i, sample) in self.next.iter_mut().enumerate() {
+                *sample = frame[i % in_channels];
+            }
+        } else {
+            for (i, sample) in self.next.iter_mut().enumerate() {
+                let mixed = frame.iter().skip(i).step_by(out_channels);
+                let count = mixed.clone().count();
+                *sample = mixed.sum::<f64>() / count as f64;
+            }
+        }
+        self.input_pos += 1;
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    const FORMATS: [SampleFormat; 5] = [
+        SampleFormat::Int16,
+        SampleFormat::Int24,
+        SampleFormat::Int32,
+        SampleFormat::Float32,
+        SampleFormat::Float64,
+    ];
+
+    fn tolerance(format: SampleFormat) -> f64 {
+        match format {
+            SampleFormat::Int16 => 1e-4,
+            SampleFormat::Int24 => 1e-6,
+            SampleFormat::Int32 => 1e-9,
+            SampleFormat::Float32 => 1e-7,
+            SampleFormat::Float64 => 0.0,
+        }
+    }
+
+    fn write(file_type: SoundFileType, format: SampleFormat, samples: &[f64]) -> Vec<u8> {
+        write_with(file_type, format, 2, 44_100.0, samples)
+    }
+
+    fn write_with(
+        file_type: SoundFileType,
+        format: SampleFormat,
+        channels: u32,
+        sample_rate: f64,
+        samples: &[f64],
+    ) -> Vec<u8> {
+        let mut file = Cursor::new(Vec::new());
+        let mut writer =
+            SoundFileWriter::new(&mut file, file_type, format, channels, sample_rate).unwrap();
+        writer.write_samples(samples).unwrap();
+        writer.finish().unwrap();
+        drop(writer);
+        file.into_inner()
+    }
+
+    fn open_source(
+        channels: u32,
+        sample_rate: f64,
+        samples: &[f64],
+        out_channels: u32,
+        out_rate: f64,
+    ) -> SoundFileSource<Cursor<Vec<u8>>> {
+        let bytes = write_with(
+            SoundFileType::Wav,
+            SampleFormat::Float64,
+            channels,
+            sample_rate,
+            samples,
+        );
+        SoundFileSource::new(
+            SoundFileReader::new(Cursor::new(bytes)).unwrap(),
+            out_channels,
+            out_rate,
+        )
+    }
+
+    fn assert_close(actual: &[f64], expected: &[f64]) {
+        assert_eq!(actual.len(), expected.len());
+        for (a, e) in actual.iter().zip(expected) {
+            assert!((a - e).abs() < 1e-12, "{:?} != {:?}", actual, expected);
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let samples = [
+            -1.0,
+            -0.5,
+            -0.123_456_789,
+            0.0,
+            0.25,
+            0.999,
+            0.5,
+            -0.75,
+            0.1,
+        ];
+        for &file_type in &[SoundFileType::Wav, SoundFileType::Aiff] {
+            for &format in &FORMATS {
+                let bytes = write(file_type, format, &samples);
+                let mut reader = SoundFileReader::new(Cursor::new(bytes)).unwrap();
+                assert_eq!(reader.file_type(), file_type);
+                assert_eq!(reader.sample_format(), format);
+                assert_eq!(reader.channels(), 2);
+                assert_eq!(reader.sample_rate(), 44_100.0);
+                // The last sample is not a complete frame
+                assert_eq!(reader.frames(), 4);
+
+                let mut out = [0.0; 10];
+                assert_eq!(reader.read_frames(&mut out).unwrap(), 4);
+                for (read, written) in out.iter().zip(&samples[..8]) {
+                    assert!(
+                        (read - written).abs() <= tolerance(format),
+                        "{:?} {:?}: {} != {}",
+                        file_type,
+                        format,
+                        read,
+                        written
+                    );
+                }
+                assert_eq!(reader.read_frames(&mut out).unwrap(), 0);
+            }
+        }
+    }
+
+    #[test]
+    fn int24_sign_extension() {
+        let format = SampleFormat::Int24;
+        assert_eq!(format.decode(&[0x00, 0x00, 0x80], false), -1.0);
+        assert_eq!(format.decode(&[0x80, 0x00, 0x00], true), -1.0);
+        assert_eq!(
+            format.decode(&[0xff, 0xff, 0xff], false),
+            -1.0 / 8_388_608.0
+        );
+        assert_eq!(
+            format.decode(&[0xff, 0xff, 0x7f], false),
+            8_388_607.0 / 8_388_608.0
+        );
+
+        let mut out = Vec::new();
+        format.encode(-0.5, false, &mut out);
+        format.encode(-0.5, true, &mut out);
+        assert_eq!(out, [0x00, 0x00, 0xc0, 0xc0, 0x00, 0x00]);
+        assert_eq!(format.decode(&out[..3], false), -0.5);
+    }
+
+    #[test]
+    fn extended_sample_rate() {
+        assert_eq!(
+            f64_to_extended(44_100.0),
+            [0x40, 0x0e, 0xac, 0x44, 0, 0, 0, 0, 0, 0]
+        );
+        for &rate in &[8_000.0, 22_050.0, 48_000.0, 96_000.0, 11_025.5, 1.0] {
+            assert_eq!(extended_to_f64(&f64_to_extended(rate)), rate);
+        }
+        assert_eq!(extended_to_f64(&f64_to_extended(0.0)), 0.0);
+    }
+
+    #[test]
+    fn short_ssnd_chunk() {
+        let mut file = b"FORM\0\0\0\x2eAIFFCOMM\0\0\0\x12".to_vec();
+        file.extend_from_slice(&[0, 1, 0, 0, 0, 0, 0, 16]);
+        file.extend_from_slice(&f64_to_extended(44_100.0));
+        file.extend_from_slice(b"SSND\0\0\0\x04\0\0\0\0");
+        let error = SoundFileReader::new(Cursor::new(file)).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn oversized_chunk() {
+        let mut file = b"RIFF\0\0\0\0WAVEfmt \xf0\xff\xff\xff".to_vec();
+        file.extend_from_slice(&[1, 0, 1, 0, 0x44, 0xac, 0, 0, 0x88, 0x58, 1, 0, 2, 0, 16, 0]);
+        let error = SoundFileReader::new(Cursor::new(file)).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn truncated_data() {
+        let samples = [0.1, 0.2, 0.3, 0.4, 0.5, 0.6, 0.7, 0.8];
+        let mut bytes = write(SoundFileType::Wav, SampleFormat::Int16, &samples);
+        bytes.truncate(bytes.len() - 5);
+        let mut reader = SoundFileReader::new(Cursor::new(bytes)).unwrap();
+        assert_eq!(reader.frames(), 4);
+
+        let mut out = [0.0; 8];
+        assert_eq!(reader.read_frames(&mut out).unwrap(), 2);
+        assert!((out[3] - 0.4).abs() < 1e-4);
+        assert_eq!(reader.frames(), 2);
+        assert_eq!(reader.read_frames(&mut out).unwrap(), 0);
+    }
+
+    #[test]
+    fn streamed_wav_size() {
+        let samples = [0.1, 0.2, 0.3, 0.4, 0.5, 0.6];
+        let mut bytes = write(SoundFileType::Wav, SampleFormat::Float32, &samples);
+        let data = bytes.windows(4).position(|id| id == b"data").unwrap();
+        bytes[data + 4..data + 8].copy_from_slice(&[0xff; 4]);
+        let mut reader = SoundFileReader::new(Cursor::new(bytes)).unwrap();
+
+        let mut out = [0.0; 4];
+        assert_eq!(reader.read_frames(&mut out).unwrap(), 2);
+        assert_eq!(reader.read_frames(&mut out).unwrap(), 1);
+        assert!((out[1] - 0.6).abs() < 1e-7);
+        assert_eq!(reader.read_frames(&mut out).unwrap(), 0);
+        assert_eq!(reader.frames(), 3);
+    }
+
+    #[test]
+    fn source_upmix() {
+        let mut source = open_source(1, 44_100.0, &[0.1, 0.2, 0.3], 2, 44_100.0);
+        let mut out = [1.0; 8];
+        assert_eq!(source.fill(&mut out).unwrap(), 3);
+        assert_close(&out, &[0.1, 0.1, 0.2, 0.2, 0.3, 0.3, 0.0, 0.0]);
+        assert!(source.is_finished());
+
+        let mut source = open_source(2, 44_100.0, &[0.1, -0.1, 0.2, -0.2], 3, 44_100.0);
+        let mut out = [0.0; 6];
+        assert_eq!(source.fill(&mut out).unwrap(), 2);
+        assert_close(&out, &[0.1, -0.1, 0.1, 0.2, -0.2, 0.2]);
+    }
+
+    #[test]
+    fn source_downmix() {
+        let samples = [0.1, 0.2, 0.3, 0.4, -0.1, -0.2, -0.3, -0.4];
+        let mut source = open_source(4, 44_100.0, &samples, 2, 44_100.0);
+        let mut out = [0.0; 4];
+        assert_eq!(source.fill(&mut out).unwrap(), 2);
+        assert_close(&out, &[0.2, 0.3, -0.2, -0.3]);
+
+        let mut source = open_source(2, 44_100.0, &[0.2, 0.4, -0.2, 0.0], 1, 44_100.0);
+        let mut out = [0.0; 2];
+        assert_eq!(source.fill(&mut out).unwrap(), 2);
+        assert_close(&out, &[0.3, -0.1]);
+    }
+
+    #[test]
+    fn source_upsampling() {
+        let mut source = open_source(1, 22_050.0, &[0.0, 0.1, 0.2, 0.3], 1, 44_100.0);
+        let mut out = [1.0; 10];
+        assert_eq!(source.fill(&mut out).unwrap(), 8);
+        // The last frame is held until the next one would have been read
+        assert_close(&out, &[0.0, 0.05, 0.1, 0.15, 0.2, 0.25, 0.3, 0.3, 0.0, 0.0]);
+        assert!(source.is_finished());
+    }
+
+    #[test]
+    fn source_downsampling() {
+        let samples = [0.0, 0.1, 0.2, 0.3, 0.4, 0.5, 0.6];
+        let mut source = open_source(1, 44_100.0, &samples, 1, 29_400.0);
+        let mut out = [0.0; 6];
+        assert_eq!(source.fill(&mut out).unwrap(), 5);
+        assert_close(&out, &[0.0, 0.15, 0.3, 0.45, 0.6, 0.0]);
+    }
+
+    #[test]
+    fn source_split_fills() {
+        let samples: Vec<f64> = (0..3000).map(|i| i as f64 / 3000.0).collect();
+        let mut whole = open_source(1, 48_000.0, &samples, 2, 44_100.0);
+        let mut expected = vec![0.0; 8000];
+        whole.fill(&mut expected).unwrap();
+
+        let mut split = open_source(1, 48_000.0, &samples, 2, 44_100.0);
+        let mut actual = vec![0.0; 8000];
+        for block in actual.chunks_mut(64) {
+            split.fill(block).unwrap();
+        }
+        assert_close(&actual, &expected);
+    }
+}
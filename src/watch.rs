@@ -0,0 +1,162 @@
+use std::ffi::CString;
+use std::ptr;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use csound_sys as raw;
+use libc::c_int;
+use raw::{controlChannelType, CSOUND_STATUS};
+
+use crate::error::{Error, Result};
+
+/// Options of an output channel observer, see [`Csound::watch_channel_with`](struct.Csound.html#method.watch_channel_with).
+///
+/// By default the observer is called on every change of the channel's value.
+/// # Example
+/// ```
+/// use csound::WatchOptions;
+///
+/// // Ignore changes smaller than 0.01 and notify at most 30 times per second
+/// let options = WatchOptions::new().threshold(0.01).max_rate(30.0);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WatchOptions {
+    threshold: f64,
+    max_rate: Option<f64>,
+}
+
+impl Default for WatchOptions {
+    fn default() -> WatchOptions {
+        WatchOptions {
+            threshold: 0.0,
+            max_rate: None,
+        }
+    }
+}
+
+impl WatchOptions {
+    /// Creates the default options.
+    pub fn new() -> WatchOptions {
+        WatchOptions::default()
+    }
+
+    /// The observer is called only when the value differs from the last notified one
+    /// by more than *threshold*.
+    pub fn threshold(mut self, threshold: f64) -> WatchOptions {
+        self.threshold = threshold;
+        self
+    }
+
+    /// Calls the observer at most *rate* times per second of performance time.
+    /// Changes which arrive earlier are notified once the interval has elapsed.
+    pub fn max_rate(mut self, rate: f64) -> WatchOptions {
+        self.max_rate = Some(rate);
+        self
+    }
+
+    fn validate(&self) -> Result<()> {
+        if self.threshold.is_nan() || self.threshold < 0.0 {
+            return Err(Error::InvalidArgument(
+                "the threshold must be a non negative number",
+            ));
+        }
+        match self.max_rate {
+            Some(rate) if !(rate.is_finite() && rate > 0.0) => {
+                Err(Error::InvalidArgument("the rate must be a positive number"))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Identifier of an output channel observer, used to remove it with
+/// [`Csound::unwatch_channel`](struct.Csound.html#method.unwatch_channel).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct WatchId(u64);
+
+struct ChannelWatcher {
+    id: WatchId,
+    name: CString,
+    options: WatchOptions,
+    last_value: Option<f64>,
+    last_time: f64,
+    callback: Box<dyn FnMut(f64) + Send>,
+}
+
+/// The observers of the output channels, polled once per control period
+/// from the sense event callback.
+#[derive(Default)]
+pub(crate) struct ChannelWatchers {
+    next_id: u64,
+    watchers: Vec<ChannelWatcher>,
+}
+
+impl ChannelWatchers {
+    pub(crate) fn add<F>(&mut self, name: &str, options: WatchOptions, f: F) -> Result<WatchId>
+    where
+        F: FnMut(f64) + Send + 'static,
+    {
+        options.validate()?;
+        let id = WatchId(self.next_id);
+        self.next_id += 1;
+        self.watchers.push(ChannelWatcher {
+            id,
+            name: CString::new(name)?,
+            options,
+            last_value: None,
+            last_time: 0.0,
+            callback: Box::new(f),
+        });
+        Ok(id)
+    }
+
+    pub(crate) fn remove(&mut self, id: WatchId) -> bool {
+        let len = self.watchers.len();
+        self.watchers.retain(|watcher| watcher.id != id);
+        len != self.watchers.len()
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.watchers.is_empty()
+    }
+
+    /// Reads every watched channel and calls the observers whose value changed.
+    ///
+    /// The channel pointers are requested on each call, because csound frees them on reset.
+    pub(crate) unsafe fn poll(&mut self, csound: *mut raw::CSOUND) {
+        if self.watchers.is_empty() {
+            return;
+        }
+        let time =
+            raw::csoundGetCurrentTimeSamples(csound) as f64 / raw::csoundGetSr(csound) as f64;
+        for watcher in self.watchers.iter_mut() {
+            let mut ptr: *mut f64 = ptr::null_mut();
+            // csound creates the channel if it does not exist yet,
+            // channels with another type are skipped
+            if raw::csoundGetChannelPtr(
+                csound,
+                &mut ptr as *mut *mut _,
+                watcher.name.as_ptr(),
+                (controlChannelType::CSOUND_CONTROL_CHANNEL
+                    | controlChannelType::CSOUND_OUTPUT_CHANNEL) as c_int,
+            ) != CSOUND_STATUS::CSOUND_SUCCESS
+                || ptr.is_null()
+            {
+                continue;
+            }
+            let value = f64::from_bits((*(ptr as *const AtomicU64)).load(Ordering::SeqCst));
+            if let Some(last) = watcher.last_value {
+                if (value - last).abs() <= watcher.options.threshold {
+                    continue;
+                }
+                if let Some(rate) = watcher.options.max_rate {
+                    if time - watcher.last_time < 1.0 / rate {
+                        continue;
+                    }
+                }
+            }
+            watcher.last_value = Some(value);
+            watcher.last_time = time;
+            (watcher.callback)(value);
+        }
+    }
+}